@@ -0,0 +1,191 @@
+// Copyright 2023 The ChromiumOS Authors
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+//! A small git abstraction backed by `git2`, used in place of shelling out to the `git` binary.
+//! This gives repo-root discovery, status checks, worktree add/remove, checkout, and commit
+//! structured errors instead of exit-code/porcelain-text sniffing, and removes the hard
+//! dependency on a `git` binary being present in `$PATH`.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, bail, Context, Result};
+use git2::{ErrorClass, ErrorCode, Repository, StatusOptions, WorktreeAddOptions, WorktreePruneOptions};
+use log::{debug, error};
+
+/// Returns `true` for errors that typically mean "a stale or corrupt worktree is sitting where we
+/// want to create a new one" -- e.g. left behind by an interrupted previous run -- as opposed to
+/// a fatal error like a bad ref or a network failure. Modeled on Cargo's own "reset harder"
+/// recovery strategy: these are worth blowing away and retrying once, rather than aborting an
+/// otherwise-clean update run.
+fn is_retryable_corruption(e: &git2::Error) -> bool {
+    matches!(e.class(), ErrorClass::Worktree)
+        || matches!(e.code(), ErrorCode::Exists | ErrorCode::Locked)
+}
+
+/// Ensures there are no changes -- tracked or untracked -- in the git repo rooted at `repo`.
+/// Returns an error if there are, or if checking failed.
+pub fn ensure_repo_is_clean(repo: &Path) -> Result<()> {
+    let repo = Repository::open(repo)
+        .with_context(|| format!("opening git repo at {}", repo.display()))?;
+
+    let mut opts = StatusOptions::new();
+    opts.include_untracked(true).include_ignored(false);
+    let statuses = repo
+        .statuses(Some(&mut opts))
+        .with_context(|| format!("reading git status for {}", repo.path().display()))?;
+    if !statuses.is_empty() {
+        bail!(
+            "uncommitted changes found in git repo at {}",
+            repo.workdir().unwrap_or_else(|| repo.path()).display()
+        );
+    }
+    Ok(())
+}
+
+/// Given a directory, discovers the root of the enclosing git repo's working tree.
+pub fn find_git_repo_root(dir: &Path) -> Option<PathBuf> {
+    Repository::discover(dir)
+        .ok()?
+        .workdir()
+        .map(Path::to_path_buf)
+}
+
+/// Restores `file` to its state at `HEAD`, discarding any local changes to it.
+pub fn revert_file_to_head(file: &Path) -> Result<()> {
+    let repo = Repository::discover(file)
+        .with_context(|| format!("opening git repo for {}", file.display()))?;
+    let repo_root = repo
+        .workdir()
+        .ok_or_else(|| anyhow!("{} has no working directory", repo.path().display()))?;
+    let relative = file
+        .strip_prefix(repo_root)
+        .with_context(|| format!("{} isn't inside {}", file.display(), repo_root.display()))?;
+
+    let mut checkout = git2::build::CheckoutBuilder::new();
+    checkout.path(relative).force();
+    repo.checkout_head(Some(&mut checkout))
+        .with_context(|| format!("git-checkout {}", file.display()))?;
+    Ok(())
+}
+
+/// Stages every change in the working directory and commits it on top of `HEAD`.
+pub fn commit_all_changes(git_root: &Path, commit_message: &str) -> Result<()> {
+    let repo = Repository::open(git_root)
+        .with_context(|| format!("opening git repo at {}", git_root.display()))?;
+
+    let mut index = repo
+        .index()
+        .with_context(|| format!("reading index for {}", git_root.display()))?;
+    index
+        .add_all(["*"].iter(), git2::IndexAddOption::DEFAULT, None)
+        .with_context(|| format!("staging changes in {}", git_root.display()))?;
+    index
+        .update_all(["*"].iter(), None)
+        .with_context(|| format!("staging deletions in {}", git_root.display()))?;
+    index.write().with_context(|| "writing index")?;
+    let tree = repo
+        .find_tree(index.write_tree().with_context(|| "writing tree")?)
+        .with_context(|| "looking up the tree we just wrote")?;
+
+    let signature = repo
+        .signature()
+        .with_context(|| "determining commit author/committer from git config")?;
+    let head_commit = repo
+        .head()
+        .and_then(|head| head.peel_to_commit())
+        .with_context(|| "resolving HEAD commit")?;
+
+    repo.commit(
+        Some("HEAD"),
+        &signature,
+        &signature,
+        commit_message,
+        &tree,
+        &[&head_commit],
+    )
+    .with_context(|| format!("committing changes in {}", git_root.display()))?;
+    Ok(())
+}
+
+/// Convenient struct that cleans up a worktree on drop.
+pub struct Worktree {
+    repo_root: PathBuf,
+    name: String,
+    location: PathBuf,
+}
+
+impl Worktree {
+    pub fn new(git_root: &Path, checkout_ref: &str) -> Result<Worktree> {
+        let repo = Repository::open(git_root)
+            .with_context(|| format!("opening git repo at {}", git_root.display()))?;
+
+        // Hang onto the `TempDir` guard until we know we've succeeded: if every attempt below
+        // fails, letting it drop here cleans up the directory we created for it. Only on success
+        // do we `into_path()` it, handing ownership of cleanup to git's own worktree machinery
+        // (see `Drop`, below).
+        let temp_dir = tempdir::TempDir::new("cargo-update-worktree")?;
+        let location = temp_dir.path().to_path_buf();
+        let name = location
+            .file_name()
+            .ok_or_else(|| anyhow!("{} has no file name", location.display()))?
+            .to_string_lossy()
+            .into_owned();
+
+        let reference = repo
+            .resolve_reference_from_short_name(checkout_ref)
+            .or_else(|_| repo.find_reference(checkout_ref))
+            .with_context(|| format!("resolving ref {checkout_ref:?}"))?;
+        let mut opts = WorktreeAddOptions::new();
+        opts.reference(Some(&reference));
+
+        if let Err(e) = repo.worktree(&name, &location, Some(&opts)) {
+            if !is_retryable_corruption(&e) {
+                return Err(e)
+                    .with_context(|| format!("creating worktree at {}", location.display()));
+            }
+            debug!(
+                "Worktree creation at {} failed with retryable error ({e}); blowing it away and \
+                 retrying once...",
+                location.display()
+            );
+            if let Ok(stale) = repo.find_worktree(&name) {
+                let mut prune_opts = WorktreePruneOptions::new();
+                prune_opts.valid(true).locked(true).working_tree(true);
+                let _ = stale.prune(Some(&mut prune_opts));
+            }
+            let _ = std::fs::remove_dir_all(&location);
+            repo.worktree(&name, &location, Some(&opts)).with_context(|| {
+                format!(
+                    "creating worktree at {} (after retrying once)",
+                    location.display()
+                )
+            })?;
+        }
+
+        Ok(Worktree {
+            repo_root: git_root.to_path_buf(),
+            name,
+            location: temp_dir.into_path(),
+        })
+    }
+
+    pub fn location(&self) -> &Path {
+        &self.location
+    }
+}
+
+impl Drop for Worktree {
+    fn drop(&mut self) {
+        let result = Repository::open(&self.repo_root)
+            .and_then(|repo| repo.find_worktree(&self.name))
+            .and_then(|worktree| {
+                let mut opts = WorktreePruneOptions::new();
+                opts.valid(true).locked(true).working_tree(true);
+                worktree.prune(Some(&mut opts))
+            });
+        if let Err(e) = result {
+            error!("Failed removing worktree at {}: {}", self.location.display(), e);
+        }
+    }
+}
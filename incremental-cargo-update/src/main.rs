@@ -5,11 +5,14 @@
 
 use std::collections::HashSet;
 use std::path::{Path, PathBuf};
-use std::process::{Command, Stdio};
+use std::process::Command;
 
 use anyhow::{anyhow, bail, Context, Result};
 use clap::Parser;
-use log::{debug, error, info};
+use log::{debug, info};
+
+mod git;
+use git::{commit_all_changes, ensure_repo_is_clean, find_git_repo_root, revert_file_to_head, Worktree};
 
 /// Convenience trait to replace this repetitive pattern:
 /// ```ignore
@@ -80,39 +83,13 @@ impl<T: StatusToResult> StatusToResult for std::io::Result<T> {
     }
 }
 
-/// Ensures there are no unstaged changes in the git repo rooted at `repo`. Returns an error if
-/// there are, or if checking failed.
-fn ensure_repo_is_clean(repo: &Path) -> Result<()> {
-    let output = Command::new("git")
-        .args(["status", "--porcelain"])
-        .current_dir(repo)
-        .output()
-        .status_to_result(|| format!("`git status` in {}", repo.display()))?;
-
-    let has_changes = output.stdout.iter().any(|x| !x.is_ascii_whitespace());
-    if has_changes {
-        bail!(
-            "uncommitted changes found in git repo at {}",
-            repo.display()
-        );
-    }
-    Ok(())
-}
-
-/// Given a directory, walks up until a `.git` directory entry can be found.
-fn find_git_repo_root(dir: &Path) -> Option<PathBuf> {
-    let mut buf = dir.to_path_buf();
-    loop {
-        buf.push(".git");
-        let exists = buf.exists();
-        buf.pop();
-        if exists {
-            return Some(buf);
-        }
-        if !buf.pop() {
-            return None;
-        }
-    }
+/// Where a `PackageInfo` was resolved from.
+#[derive(Debug, PartialEq, Eq, Hash)]
+enum PackageSource {
+    /// The default registry (crates.io).
+    Registry,
+    /// A git dependency, pinned to an exact revision.
+    Git { url: String, rev: String },
 }
 
 /// Information that can uniquely identify a package, for cargo-update's sake.
@@ -120,12 +97,16 @@ fn find_git_repo_root(dir: &Path) -> Option<PathBuf> {
 struct PackageInfo {
     name: String,
     version: String,
+    source: PackageSource,
 }
 
 impl std::fmt::Display for PackageInfo {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         // This is the format required by cargo-update to uniquely identify a package.
-        write!(f, "{}@{}", self.name, self.version)
+        match &self.source {
+            PackageSource::Registry => write!(f, "{}@{}", self.name, self.version),
+            PackageSource::Git { url, .. } => write!(f, "{url}#{}@{}", self.name, self.version),
+        }
     }
 }
 
@@ -142,14 +123,24 @@ fn parse_cargo_lock_packages(path: &Path) -> Result<Vec<PackageInfo>> {
     Ok(lockfile_packages
         .into_iter()
         .filter_map(|package| {
-            if package.source?.is_default_registry() {
-                Some(PackageInfo {
-                    name: package.name.to_string(),
-                    version: package.version.to_string(),
-                })
+            let source = package.source?;
+            let source = if source.is_default_registry() {
+                PackageSource::Registry
+            } else if source.is_git() {
+                PackageSource::Git {
+                    url: source.url().to_string(),
+                    // `cargo_lock` tracks the exact revision a git source is locked to in its
+                    // `precise` field, the same way it tracks "locked" for registry sources.
+                    rev: source.precise()?.to_string(),
+                }
             } else {
-                None
-            }
+                return None;
+            };
+            Some(PackageInfo {
+                name: package.name.to_string(),
+                version: package.version.to_string(),
+                source,
+            })
         })
         .collect())
 }
@@ -167,6 +158,9 @@ enum PackageSpec<'a> {
     /// update` will not be asked to `--aggressive`ly update these, since aggressive updates can
     /// lead to us exceeding our soft update limit by more than necessary.
     Only(&'a PackageInfo),
+    /// Pin the named package to an exact version, mirroring Cargo's own `precise: Option<&str>`
+    /// field on `UpdateOptions`.
+    Precise { name: &'a str, version: &'a str },
 }
 
 /// Runs `cargo update` on the given lockfile.
@@ -179,9 +173,18 @@ fn cargo_update_packages(lock_file: &Path, package: PackageSpec<'_>, ty: UpdateT
         .ok_or_else(|| anyhow!("lockfiles shouldn't be parentless"))?;
     cmd.current_dir(parent);
 
-    if let PackageSpec::Only(package) = package {
-        cmd.arg("--package");
-        cmd.arg(package.to_string());
+    match package {
+        PackageSpec::All => (),
+        PackageSpec::Only(package) => {
+            cmd.arg("--package");
+            cmd.arg(package.to_string());
+        }
+        PackageSpec::Precise { name, version } => {
+            cmd.arg("--package");
+            cmd.arg(name);
+            cmd.arg("--precise");
+            cmd.arg(version);
+        }
     }
 
     if matches!(ty, UpdateType::Offline) {
@@ -193,6 +196,89 @@ fn cargo_update_packages(lock_file: &Path, package: PackageSpec<'_>, ty: UpdateT
     Ok(())
 }
 
+/// A `name@version` pin supplied via `--precise`, requesting that `name` land on exactly
+/// `version` rather than whatever is newest-compatible.
+#[derive(Debug, Clone)]
+struct PrecisePin {
+    name: String,
+    version: String,
+}
+
+impl std::str::FromStr for PrecisePin {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (name, version) = s
+            .split_once('@')
+            .ok_or_else(|| anyhow!("expected `name@version`, got {s:?}"))?;
+        Ok(PrecisePin {
+            name: name.to_string(),
+            version: version.to_string(),
+        })
+    }
+}
+
+/// A single package's version change, pairing the version it was removed at with the version (if
+/// any) it was replaced by.
+struct PackageTransition {
+    name: String,
+    from_version: String,
+    to_version: Option<String>,
+}
+
+impl std::fmt::Display for PackageTransition {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match &self.to_version {
+            Some(to_version) => write!(f, "{} {} -> {}", self.name, self.from_version, to_version),
+            None => write!(f, "{} {} removed", self.name, self.from_version),
+        }
+    }
+}
+
+/// Pairs each `removed` package with its same-named counterpart in `new_listing`, if any, and
+/// sorts the result by name. Packages with no same-named counterpart were removed outright.
+fn pair_updated_packages(
+    removed: &[&PackageInfo],
+    new_listing: &[PackageInfo],
+) -> Vec<PackageTransition> {
+    let mut transitions: Vec<_> = removed
+        .iter()
+        .map(|package| PackageTransition {
+            name: package.name.clone(),
+            from_version: package.version.clone(),
+            to_version: new_listing
+                .iter()
+                .find(|candidate| candidate.name == package.name)
+                .map(|candidate| candidate.version.clone()),
+        })
+        .collect();
+    transitions.sort_unstable_by(|a, b| a.name.cmp(&b.name));
+    transitions
+}
+
+/// Renders `transitions` as a sorted, newline-separated summary suitable for interpolating into a
+/// commit message.
+fn format_transitions_summary(transitions: &[PackageTransition]) -> String {
+    let mut lines = transitions
+        .iter()
+        .map(|transition| transition.to_string())
+        .collect::<Vec<_>>();
+    lines.sort_unstable();
+    lines.join("\n")
+}
+
+/// Prints the package transitions a `--dry-run` would have applied, without committing to
+/// anything.
+fn report_dry_run(transitions: &[PackageTransition]) {
+    info!(
+        "Dry run: the following {} package(s) would be updated:",
+        transitions.len()
+    );
+    for transition in transitions {
+        info!("  {transition}");
+    }
+}
+
 /// Returns a list of packages from `initial_listing` which no longer exist in `new_listing`.
 fn find_updated_packages<'a>(
     initial_listing: &'a [PackageInfo],
@@ -205,39 +291,74 @@ fn find_updated_packages<'a>(
         .collect()
 }
 
-/// Runs `git checkout ${file}`.
-fn revert_file_to_head(file: &Path) -> Result<()> {
-    let mut cmd = Command::new("git");
-    cmd.args(["checkout", "--"]);
-
-    let file_name = file
-        .file_name()
-        .ok_or_else(|| anyhow!("{} has no file name", file.display()))?;
-    cmd.arg(file_name);
-
-    // Always `cd` into the parent directory, since $PWD might be outside of the git dir.
-    let parent = file
-        .parent()
-        .ok_or_else(|| anyhow!("lockfiles shouldn't be parentless"))?;
-    cmd.current_dir(parent);
+/// Applies every `--precise` pin to `cargo_lock`, each as its own `cargo update --precise` call.
+/// Pins are applied last (after any blanket update), so a blanket update's resolution can never
+/// leave a pinned package off the exact version it was asked to land on.
+fn apply_precise_pins(cargo_lock: &Path, precise: &[PrecisePin]) -> Result<()> {
+    for pin in precise {
+        info!("Pinning {} to {}...", pin.name, pin.version);
+        cargo_update_packages(
+            cargo_lock,
+            PackageSpec::Precise {
+                name: &pin.name,
+                version: &pin.version,
+            },
+            UpdateType::Online,
+        )?;
+    }
+    Ok(())
+}
 
-    cmd.status()
-        .status_to_result(|| format!("git-checkout {}", file.display()))?;
+/// Confirms every `--precise` pin actually landed on its requested version in `listing`. Pins are
+/// mandatory, so if one isn't where it was asked to be -- e.g. because a revert undid it and it
+/// was never reapplied -- that's a bug, not something to silently let slide.
+fn verify_precise_pins(listing: &[PackageInfo], precise: &[PrecisePin]) -> Result<()> {
+    for pin in precise {
+        let landed = listing
+            .iter()
+            .any(|package| package.name == pin.name && package.version == pin.version);
+        if !landed {
+            bail!(
+                "failed to pin {} to {}: it's not at that version in Cargo.lock after updating",
+                pin.name,
+                pin.version
+            );
+        }
+    }
     Ok(())
 }
 
 /// Updates `cargo_lock` with a soft limit of `max_updates` updates. Update is done destructively,
 /// and it's assumed that the git repo this is being run in is clean of updates.
 ///
-/// Returns the number of packages that were ultimately updated.
-fn perform_cargo_update(cargo_lock: &Path, max_updates: usize) -> Result<usize> {
+/// `precise` pins are mandatory: they're counted against `max_updates` but never allowed to trip
+/// the early-exit that blanket updates do, and they're (re-)applied and verified after the
+/// blanket update and after the revert that precedes the one-at-a-time pass. The single-package
+/// updates of that pass neither re-apply nor re-verify them.
+///
+/// If `dry_run` is set, the full resolution still runs against `cargo_lock`, but the file is
+/// reverted to its original contents via `revert_file_to_head` before returning instead of being
+/// left in its updated state, and the caller must not commit anything afterward.
+///
+/// Returns the transitions for the packages that were ultimately updated (or, for `--dry-run`,
+/// that would have been).
+fn perform_cargo_update(
+    cargo_lock: &Path,
+    max_updates: usize,
+    precise: &[PrecisePin],
+    dry_run: bool,
+) -> Result<Vec<PackageTransition>> {
     let initial_cargo_lock = parse_cargo_lock_packages(cargo_lock)?;
     debug!("Parsed {} Cargo.lock packages.", initial_cargo_lock.len());
 
+    let pinned_names: HashSet<&str> = precise.iter().map(|pin| pin.name.as_str()).collect();
+
     info!("Running initial `cargo update`...");
     cargo_update_packages(cargo_lock, PackageSpec::All, UpdateType::Online)?;
+    apply_precise_pins(cargo_lock, precise)?;
 
     let fully_updated_cargo_lock = parse_cargo_lock_packages(cargo_lock)?;
+    verify_precise_pins(&fully_updated_cargo_lock, precise)?;
     let fully_updated_packages =
         find_updated_packages(&initial_cargo_lock, &fully_updated_cargo_lock);
     info!(
@@ -245,7 +366,12 @@ fn perform_cargo_update(cargo_lock: &Path, max_updates: usize) -> Result<usize>
         fully_updated_packages.len()
     );
     if fully_updated_packages.len() <= max_updates {
-        return Ok(fully_updated_packages.len());
+        let transitions = pair_updated_packages(&fully_updated_packages, &fully_updated_cargo_lock);
+        if dry_run {
+            report_dry_run(&transitions);
+            revert_file_to_head(cargo_lock)?;
+        }
+        return Ok(transitions);
     }
 
     // We have more updates than we'd like. A solution that makes these updates happen should:
@@ -259,9 +385,32 @@ fn perform_cargo_update(cargo_lock: &Path, max_updates: usize) -> Result<usize>
     // Since the speed of this program... does not really matter, just take the simple
     // one-at-a-time approach.
     revert_file_to_head(cargo_lock)?;
+    // The revert above just undid every pin along with the blanket update, so reapply (and
+    // re-verify) them before starting the one-by-one pass.
+    apply_precise_pins(cargo_lock, precise)?;
 
     let mut current_cargo_lock = parse_cargo_lock_packages(cargo_lock)?;
+    verify_precise_pins(&current_cargo_lock, precise)?;
+    // If every candidate is pinned or was already moved by reapplying the pins, the loop below
+    // would update nothing and never reach its limit check, so the pins are the whole result.
+    if fully_updated_packages.iter().all(|package| {
+        pinned_names.contains(package.name.as_str()) || !current_cargo_lock.contains(package)
+    }) {
+        let newly_updated = find_updated_packages(&initial_cargo_lock, &current_cargo_lock);
+        let transitions = pair_updated_packages(&newly_updated, &current_cargo_lock);
+        if dry_run {
+            report_dry_run(&transitions);
+            revert_file_to_head(cargo_lock)?;
+        }
+        return Ok(transitions);
+    }
     for package in &fully_updated_packages {
+        // Pins were just reapplied above; we shouldn't push them past that with an `Only` update
+        // here too.
+        if pinned_names.contains(package.name.as_str()) {
+            debug!("Skipping update of {package}; it's pinned via --precise.");
+            continue;
+        }
         // Crates may disappear if an update of a prior crate required an update of `package`.
         // `cargo-update` will fail if `package` is not in `Cargo.lock`.
         if !current_cargo_lock.contains(package) {
@@ -278,7 +427,12 @@ fn perform_cargo_update(cargo_lock: &Path, max_updates: usize) -> Result<usize>
         // others.
         if newly_updated.len() >= max_updates {
             debug!("Update limit hit; stopping update attempts");
-            return Ok(newly_updated.len());
+            let transitions = pair_updated_packages(&newly_updated, &current_cargo_lock);
+            if dry_run {
+                report_dry_run(&transitions);
+                revert_file_to_head(cargo_lock)?;
+            }
+            return Ok(transitions);
         }
         debug!(
             "{} package{} updated so far; trying again.",
@@ -289,63 +443,6 @@ fn perform_cargo_update(cargo_lock: &Path, max_updates: usize) -> Result<usize>
     unreachable!("somehow `cargo update` updated more things than updating one-by-one?");
 }
 
-/// Convenient struct that cleans up a worktree on drop.
-struct Worktree<'a> {
-    base_path: &'a Path,
-    location: PathBuf,
-}
-
-impl<'a> Worktree<'a> {
-    fn new(git_root: &'a Path, checkout_ref: &str) -> Result<Worktree<'a>> {
-        let temp_dir = tempdir::TempDir::new("cargo-update-worktree")?.into_path();
-        Command::new("git")
-            .args(["worktree", "add", "--force"])
-            .arg(&temp_dir)
-            .arg(checkout_ref)
-            .stdin(Stdio::null())
-            .current_dir(git_root)
-            .status()
-            .status_to_result(|| format!("creating worktree in {}", git_root.display()))?;
-        Ok(Worktree {
-            base_path: git_root,
-            location: temp_dir,
-        })
-    }
-}
-
-impl<'a> Drop for Worktree<'a> {
-    fn drop(&mut self) {
-        let removal_result = Command::new("git")
-            .args(["worktree", "remove", "--force"])
-            .arg(&self.location)
-            .current_dir(self.base_path)
-            .output();
-        match removal_result {
-            Err(x) => error!(
-                "Failed removing worktree at {}: {}",
-                self.location.display(),
-                x
-            ),
-            Ok(output) if !output.status.success() => error!(
-                "Failed removing worktree at {}; stderr: {}",
-                self.location.display(),
-                String::from_utf8_lossy(&output.stderr)
-            ),
-            Ok(_) => (),
-        }
-    }
-}
-
-fn commit_all_changes(git_root: &Path, commit_message: &str) -> Result<()> {
-    Command::new("git")
-        .args(["commit", "-a", "-m", commit_message])
-        .stdin(Stdio::null())
-        .current_dir(git_root)
-        .status()
-        .status_to_result(|| format!("committing changes in {}", git_root.display()))?;
-    Ok(())
-}
-
 #[derive(Parser)]
 struct Args {
     /// Maximum number of packages to allow an update of. Note that this is a soft limit: sometimes
@@ -357,9 +454,33 @@ struct Args {
     #[clap(long)]
     debug: bool,
 
-    /// The Cargo.lock file to update.
+    /// The Cargo.lock file to update. Required unless `--discover` is set.
+    #[clap(long, required_unless_present = "discover")]
+    cargo_lock: Option<PathBuf>,
+
+    /// Instead of updating a single `--cargo-lock`, discover every `Cargo.lock` under the
+    /// enclosing git repo root (honoring `.gitignore`, and always skipping `target/` and
+    /// `vendor/` directories) and update each of them in this one invocation. This parallels
+    /// Cargo's own `workspace: bool` update option, but across an entire multi-crate repo rather
+    /// than one workspace's members.
+    #[clap(long, conflicts_with = "cargo_lock")]
+    discover: bool,
+
+    /// With `--discover`, apply `--max-updates` separately to each discovered lockfile instead of
+    /// sharing one repo-wide budget across all of them.
+    #[clap(long, requires = "discover")]
+    per_lockfile_limit: bool,
+
+    /// Pin a package to an exact version rather than the newest compatible one, as
+    /// `name@version`. May be repeated. Pins are mandatory: they're always applied, even if doing
+    /// so means exceeding `--max-updates`.
+    #[clap(long = "precise")]
+    precise: Vec<PrecisePin>,
+
+    /// Resolve the full update plan, print it, and leave `Cargo.lock` untouched instead of
+    /// applying it.
     #[clap(long)]
-    cargo_lock: PathBuf,
+    dry_run: bool,
 
     /// Rather than changing the git repo in-place, create a worktree checked out to `--branch`,
     /// and commit the changes on top of that. No commit will take place if no updates are
@@ -367,11 +488,33 @@ struct Args {
     #[clap(long, requires = "commit_message")]
     branch: Option<String>,
 
-    /// The commit message to use if `--branch` is specified.
+    /// The commit message to use if `--branch` is specified. A `{updates}` placeholder, if
+    /// present, is replaced with a sorted, newline-separated summary of the version transitions
+    /// that were applied (e.g. `serde 1.0.203 -> 1.0.210`).
     #[clap(long, requires = "branch")]
     commit_message: Option<String>,
 }
 
+/// Recursively finds every `Cargo.lock` under `root` for `--discover`, honoring `.gitignore`, and
+/// always skipping `target/` and `vendor/` directories regardless of what's ignored (build output
+/// and vendored sources aren't repo members with their own updatable lockfile).
+fn discover_cargo_locks(root: &Path) -> Result<Vec<PathBuf>> {
+    let mut builder = ignore::WalkBuilder::new(root);
+    builder.filter_entry(|entry| {
+        !matches!(entry.file_name().to_str(), Some("target" | "vendor"))
+    });
+
+    let mut locks = Vec::new();
+    for entry in builder.build() {
+        let entry = entry.with_context(|| format!("walking {}", root.display()))?;
+        if entry.file_name() == "Cargo.lock" {
+            locks.push(entry.into_path());
+        }
+    }
+    locks.sort_unstable();
+    Ok(locks)
+}
+
 fn main() -> Result<()> {
     let args = Args::parse();
     simple_logger::init_with_level(if args.debug {
@@ -380,39 +523,89 @@ fn main() -> Result<()> {
         log::Level::Info
     })?;
 
-    let non_worktree_git_root = find_git_repo_root(&args.cargo_lock).ok_or_else(|| {
-        anyhow!(
-            "{} doesn't appear to be in a git directory",
-            args.cargo_lock.display()
-        )
-    })?;
+    let anchor = match &args.cargo_lock {
+        Some(cargo_lock) => cargo_lock.clone(),
+        // `--discover` doesn't name a lockfile up front, so anchor the repo-root search at the
+        // current directory instead.
+        None => std::env::current_dir().with_context(|| "determining the current directory")?,
+    };
+    let non_worktree_git_root = find_git_repo_root(&anchor)
+        .ok_or_else(|| anyhow!("{} doesn't appear to be in a git directory", anchor.display()))?;
     debug!("Detected repo at {}.", non_worktree_git_root.display());
 
+    let lockfiles = if args.discover {
+        let lockfiles = discover_cargo_locks(&non_worktree_git_root)?;
+        info!("Discovered {} Cargo.lock file(s).", lockfiles.len());
+        lockfiles
+    } else {
+        vec![args
+            .cargo_lock
+            .clone()
+            .expect("clap requires --cargo-lock unless --discover is set")]
+    };
+
     match (args.branch, args.commit_message) {
         (Some(branch), Some(commit_message)) => {
             debug!("Creating worktree...");
             let worktree = Worktree::new(&non_worktree_git_root, &branch)?;
-            let worktree = &worktree.location;
+            let worktree = worktree.location();
             info!("Created a worktree at {}", worktree.display());
-            let Ok(cargo_lock) = args.cargo_lock.strip_prefix(&non_worktree_git_root) else {
-                bail!(
-                    "{} doesn't start with git repo location at {}",
-                    args.cargo_lock.display(),
-                    worktree.display()
-                );
-            };
 
-            let num_updates = perform_cargo_update(&worktree.join(cargo_lock), args.max_updates)?;
-            info!("{num_updates} packages updated successfully.");
-            if num_updates != 0 {
+            let mut remaining_budget = args.max_updates;
+            let mut all_transitions = Vec::new();
+            for lockfile in &lockfiles {
+                let Ok(relative) = lockfile.strip_prefix(&non_worktree_git_root) else {
+                    bail!(
+                        "{} doesn't start with git repo location at {}",
+                        lockfile.display(),
+                        non_worktree_git_root.display()
+                    );
+                };
+                let budget = if args.per_lockfile_limit {
+                    args.max_updates
+                } else {
+                    remaining_budget
+                };
+                let transitions = perform_cargo_update(
+                    &worktree.join(relative),
+                    budget,
+                    &args.precise,
+                    args.dry_run,
+                )?;
+                if !args.per_lockfile_limit {
+                    remaining_budget = remaining_budget.saturating_sub(transitions.len());
+                }
+                all_transitions.extend(transitions);
+            }
+
+            info!("{} packages updated successfully.", all_transitions.len());
+            if !all_transitions.is_empty() && !args.dry_run {
+                let commit_message = commit_message
+                    .replace("{updates}", &format_transitions_summary(&all_transitions));
                 commit_all_changes(worktree, &commit_message)?;
             }
             Ok(())
         }
         (None, None) => {
             ensure_repo_is_clean(&non_worktree_git_root)?;
-            let num_updates = perform_cargo_update(&args.cargo_lock, args.max_updates)?;
-            info!("{num_updates} packages updated successfully.");
+
+            let mut remaining_budget = args.max_updates;
+            let mut all_transitions = Vec::new();
+            for lockfile in &lockfiles {
+                let budget = if args.per_lockfile_limit {
+                    args.max_updates
+                } else {
+                    remaining_budget
+                };
+                let transitions =
+                    perform_cargo_update(lockfile, budget, &args.precise, args.dry_run)?;
+                if !args.per_lockfile_limit {
+                    remaining_budget = remaining_budget.saturating_sub(transitions.len());
+                }
+                all_transitions.extend(transitions);
+            }
+
+            info!("{} packages updated successfully.", all_transitions.len());
             Ok(())
         }
         // Should be impossible due to clap's `requires` constraints.
@@ -2,6 +2,20 @@ use super::*;
 
 use super::types::*;
 
+/// Reads the `org.freedesktop.DBus.Rust.Cfg` annotation, which carries a raw `cfg(...)`
+/// predicate to gate the corresponding generated item behind, e.g. so a consumer with one large
+/// introspection file can compile only the interfaces relevant to an enabled feature. Returns an
+/// error if the annotation is present but empty, since that can't produce a meaningful `#[cfg]`.
+fn cfg_predicate(a: &HashMap<String, String>) -> Result<Option<&str>, Box<dyn error::Error>> {
+    match a.get("org.freedesktop.DBus.Rust.Cfg") {
+        None => Ok(None),
+        Some(p) if p.trim().is_empty() => {
+            Err("org.freedesktop.DBus.Rust.Cfg annotation must not be empty".into())
+        }
+        Some(p) => Ok(Some(p.as_str())),
+    }
+}
+
 fn make_result(success: &str, opts: &GenOpts) -> String {
     if opts.methodtype.is_some() {
         format!("Result<{}, tree::MethodErr>", success)
@@ -43,6 +57,9 @@ fn write_method_decl(s: &mut String, m: &Method, opts: &GenOpts) -> Result<(), B
         g
     } else { vec!() };
 
+    if let Some(cfg) = cfg_predicate(&m.annotations)? {
+        *s += &format!("    #[cfg({})]\n", cfg);
+    }
     m.annotations.get("org.freedesktop.DBus.Deprecated").iter().for_each(|v| {
         *s += &format!("    #[deprecated(note = \"{}\")]\n", v);
     });
@@ -70,6 +87,9 @@ fn write_method_decl(s: &mut String, m: &Method, opts: &GenOpts) -> Result<(), B
 }
 
 fn write_prop_decl(s: &mut String, p: &Prop, opts: &GenOpts, set: bool) -> Result<(), Box<dyn error::Error>> {
+    if let Some(cfg) = cfg_predicate(&p.annotations)? {
+        *s += &format!("    #[cfg({})]\n", cfg);
+    }
     p.annotations.get("org.freedesktop.DBus.Deprecated").iter().for_each(|v| {
         *s += &format!("    #[deprecated(note = \"{}\")]\n", v);
     });
@@ -91,6 +111,9 @@ pub (super) fn intf_name(s: &mut String, i: &Intf) -> Result<(), Box<dyn error::
 
 pub (super) fn intf(s: &mut String, i: &Intf, opts: &GenOpts) -> Result<(), Box<dyn error::Error>> {
 
+    if let Some(cfg) = cfg_predicate(&i.annotations)? {
+        *s += &format!("\n#[cfg({})]", cfg);
+    }
     i.annotations.get("org.freedesktop.DBus.Deprecated").iter().for_each(|v| {
         *s += &format!("\n#[deprecated(note = \"{}\")]", v);
     });
@@ -117,6 +140,10 @@ pub (super) fn intf(s: &mut String, i: &Intf, opts: &GenOpts) -> Result<(), Box<
 
 fn write_signal(s: &mut String, i: &Intf, ss: &Signal) -> Result<(), Box<dyn error::Error>> {
     let structname = format!("{}{}", make_camel(&i.shortname), make_camel(&ss.name));
+    let cfg = cfg_predicate(&ss.annotations)?;
+    if let Some(cfg) = cfg {
+        *s += &format!("\n#[cfg({})]", cfg);
+    }
     ss.annotations.get("org.freedesktop.DBus.Deprecated").iter().for_each(|v| {
         *s += &format!("\n#[deprecated(note = \"{}\")]", v);
     });
@@ -127,6 +154,9 @@ fn write_signal(s: &mut String, i: &Intf, ss: &Signal) -> Result<(), Box<dyn err
     }
     *s += "}\n\n";
 
+    if let Some(cfg) = cfg {
+        *s += &format!("#[cfg({})]\n", cfg);
+    }
     *s += &format!("impl arg::AppendAll for {} {{\n", structname);
     *s += &format!("    fn append(&self, {}: &mut arg::IterAppend) {{\n", if ss.args.len() > 0 {"i"} else {"_"});
     for a in ss.args.iter() {
@@ -135,6 +165,9 @@ fn write_signal(s: &mut String, i: &Intf, ss: &Signal) -> Result<(), Box<dyn err
     *s += "    }\n";
     *s += "}\n\n";
 
+    if let Some(cfg) = cfg {
+        *s += &format!("#[cfg({})]\n", cfg);
+    }
     *s += &format!("impl arg::ReadAll for {} {{\n", structname);
     *s += &format!("    fn read({}: &mut arg::Iter) -> Result<Self, arg::TypeMismatchError> {{\n", if ss.args.len() > 0 {"i"} else {"_"});
     *s += &format!("        Ok({} {{\n", structname);
@@ -145,6 +178,9 @@ fn write_signal(s: &mut String, i: &Intf, ss: &Signal) -> Result<(), Box<dyn err
     *s += "    }\n";
     *s += "}\n\n";
 
+    if let Some(cfg) = cfg {
+        *s += &format!("#[cfg({})]\n", cfg);
+    }
     *s += &format!("impl dbus::message::SignalArgs for {} {{\n", structname);
     *s += &format!("    const NAME: &'static str = \"{}\";\n", ss.name);
     *s += &format!("    const INTERFACE: &'static str = \"{}\";\n", i.origname);
@@ -198,6 +234,85 @@ impl<'a> {0}<'a> {{
     Ok(())
 }
 
+/// Emits a typed view over the whole reply of `org.freedesktop.DBus.ObjectManager
+/// .GetManagedObjects`, built on top of the per-interface `*Properties` views `prop_struct`
+/// already generates. Gated by `GenOpts::object_manager`, since unlike the rest of this module
+/// it needs every interface at once rather than being emitted per interface.
+pub (super) fn object_manager(s: &mut String, ifaces: &[Intf]) -> Result<(), Box<dyn error::Error>> {
+    let gettable: Vec<&Intf> = ifaces.iter().filter(|i| i.props.iter().any(|p| p.can_get())).collect();
+
+    *s += "\npub struct ManagedObjects(pub ::std::collections::HashMap<dbus::Path<'static>, ::std::collections::HashMap<String, arg::PropMap>>);\n";
+    *s += "\nimpl ManagedObjects {\n";
+    for i in &gettable {
+        let iname = make_camel(&i.shortname);
+        let fn_name = make_snake(&i.shortname, false);
+        *s += &format!(r#"
+    pub fn objects_with_{0}<'a>(&'a self) -> impl Iterator<Item = (&'a dbus::Path<'static>, {1}Properties<'a>)> + 'a {{
+        self.0.iter().filter_map(|(path, interfaces)| {1}Properties::from_interfaces(interfaces).map(|p| (path, p)))
+    }}
+"#, fn_name, iname);
+    }
+    *s += r#"
+    pub fn get<'a>(&'a self, path: &dbus::Path<'static>) -> Option<ManagedObject<'a>> {
+        self.0.get(path).map(ManagedObject)
+    }
+}
+
+pub struct ManagedObject<'a>(&'a ::std::collections::HashMap<String, arg::PropMap>);
+
+impl<'a> ManagedObject<'a> {
+"#;
+    for i in &gettable {
+        let iname = make_camel(&i.shortname);
+        let fn_name = make_snake(&i.shortname, false);
+        *s += &format!(r#"
+    pub fn {0}(&self) -> Option<{1}Properties<'a>> {{
+        {1}Properties::from_interfaces(self.0)
+    }}
+"#, fn_name, iname);
+    }
+    *s += "}\n";
+
+    write_object_manager_signals(s);
+    Ok(())
+}
+
+fn write_object_manager_signals(s: &mut String) {
+    for (name, fields) in [
+        ("InterfacesAdded", "pub interfaces: ::std::collections::HashMap<String, arg::PropMap>,"),
+        ("InterfacesRemoved", "pub interfaces: Vec<String>,"),
+    ] {
+        *s += &format!(r#"
+#[derive(Debug)]
+pub struct {0} {{
+    pub object: dbus::Path<'static>,
+    {1}
+}}
+
+impl arg::AppendAll for {0} {{
+    fn append(&self, i: &mut arg::IterAppend) {{
+        arg::RefArg::append(&self.object, i);
+        arg::RefArg::append(&self.interfaces, i);
+    }}
+}}
+
+impl arg::ReadAll for {0} {{
+    fn read(i: &mut arg::Iter) -> Result<Self, arg::TypeMismatchError> {{
+        Ok({0} {{
+            object: i.read()?,
+            interfaces: i.read()?,
+        }})
+    }}
+}}
+
+impl dbus::message::SignalArgs for {0} {{
+    const NAME: &'static str = "{0}";
+    const INTERFACE: &'static str = "org.freedesktop.DBus.ObjectManager";
+}}
+"#, name, fields);
+    }
+}
+
 fn write_server_access(s: &mut String, i: &Intf, saccess: ServerAccess, minfo_is_ref: bool) {
     let z = if minfo_is_ref {""} else {"&"};
     match saccess {
@@ -217,6 +332,9 @@ pub (super) fn intf_client(s: &mut String, i: &Intf, opts: &GenOpts) -> Result<(
         ConnectionType::Nonblock => ("nonblock", "Proxy"),
     };
 
+    if let Some(cfg) = cfg_predicate(&i.annotations)? {
+        *s += &format!("\n#[cfg({})]", cfg);
+    }
     if module == "nonblock" {
         *s += &format!("\nimpl<'a, T: nonblock::NonblockReply, C: ::std::ops::Deref<Target=T>> {} for {}::{}<'a, C> {{\n",
             make_camel(&i.shortname), module, proxy);
@@ -302,7 +420,19 @@ fn cr_anno(a: &HashMap<String, String>, prefix: &str, suffix: &str) -> String {
     r
 }
 
+/// Reads the standard `org.freedesktop.DBus.Property.EmitsChangedSignal` annotation off a
+/// property, if present. One of `"true"`, `"invalidates"`, `"const"` or `"false"` per the D-Bus
+/// spec; anything else is treated the same as it being absent.
+fn emits_changed_signal(p: &Prop) -> Option<&str> {
+    p.annotations
+        .get("org.freedesktop.DBus.Property.EmitsChangedSignal")
+        .map(|s| s.as_str())
+}
+
 pub (super) fn intf_cr(s: &mut String, i: &Intf) -> Result<(), Box<dyn error::Error>> {
+    if let Some(cfg) = cfg_predicate(&i.annotations)? {
+        *s += &format!("\n#[cfg({})]", cfg);
+    }
     *s += &format!(r#"
 pub fn register_{}<T>(cr: &mut crossroads::Crossroads) -> crossroads::IfaceToken<T>
 where T: {} + Send + 'static
@@ -331,8 +461,25 @@ where T: {} + Send + 'static
             *s += &format!("\n            .get(|_, t| t.{}())", p.get_fn_name);
         }
         if p.can_set() {
-            // TODO: Handle EmitsChangedSignal correctly here.
-            *s += &format!("\n            .set(|_, t, value| t.{}(value).map(|_| None))", p.set_fn_name);
+            match emits_changed_signal(&p) {
+                Some("true") => {
+                    let rust_type = make_type(&p.typ, true, &mut None)?;
+                    if can_copy_type(&rust_type) {
+                        *s += &format!("\n            .set(|_, t, value| t.{}(value).map(|_| Some(value)))", p.set_fn_name);
+                    } else {
+                        *s += &format!("\n            .set(|_, t, value| t.{}(value.clone()).map(|_| Some(value)))", p.set_fn_name);
+                    }
+                }
+                Some("invalidates") => {
+                    *s += &format!("\n            .set(|_, t, value| t.{}(value).map(|_| None))", p.set_fn_name);
+                    *s += "\n            .emits_changed(crossroads::EmitsChangedSignal::InvalidatesProperty)";
+                }
+                _ => {
+                    // "const", "false", and no annotation all keep today's behavior: no
+                    // PropertiesChanged emission for this property.
+                    *s += &format!("\n            .set(|_, t, value| t.{}(value).map(|_| None))", p.set_fn_name);
+                }
+            }
         }
         *s += &cr_anno(&p.annotations, "            ", "");
         *s += ";\n";
@@ -354,6 +501,9 @@ pub (super) fn intf_tree(s: &mut String, i: &Intf, mtype: &str, saccess: ServerA
 
     let treem: String = if hasm { "M".into() } else { format!("tree::{}<D>", mtype) };
 
+    if let Some(cfg) = cfg_predicate(&i.annotations)? {
+        *s += &format!("\n#[cfg({})]", cfg);
+    }
     *s += &format!("\npub fn {}_server<{}{}D>(factory: &tree::Factory<{}, D>, data: D::Interface{}) -> tree::Interface<{}, D>\n",
         make_snake(&i.shortname, false), if hasf {"F, T, "} else {""}, if hasm {"M, "} else {""}, treem, if hasf {", f: F"} else {""}, treem);
 
@@ -434,6 +584,15 @@ pub (super) fn intf_tree(s: &mut String, i: &Intf, mtype: &str, saccess: ServerA
             "write" => "Write",
             _ => return Err(format!("Unexpected access value {}", p.access).into()),
         });
+        if let Some(ann) = emits_changed_signal(&p) {
+            let variant = match ann {
+                "invalidates" => "Invalidates",
+                "const" => "Const",
+                "false" => "False",
+                _ => "True",
+            };
+            *s += &format!("    let p = p.emits_changed(tree::EmitsChangedSignal::{});\n", variant);
+        }
         if p.can_get() {
             if hasf {
                 *s += "    let fclone = f.clone();\n";
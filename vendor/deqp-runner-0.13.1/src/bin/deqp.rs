@@ -1,14 +1,20 @@
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use deqp_runner::deqp_command::DeqpCommand;
+use deqp_runner::gtest_command::GtestTomlConfig;
+use deqp_runner::igt_command::IgtTomlConfig;
 use deqp_runner::mock_deqp::{mock_deqp, MockDeqp};
-use deqp_runner::piglit_command::{PiglitCommand, PiglitTomlConfig};
+use deqp_runner::piglit_command::{CompiledExpectedOutputRule, PiglitCommand, PiglitTomlConfig};
 use deqp_runner::{
-    parallel_test, parse_regex_set, process_results, read_lines, CommandLineRunOptions,
-    RunnerResults, SubRunConfig, TestCase, TestCommand,
+    compare_results, compare_timings, load_shard_timings, parallel_test_with_ndjson,
+    parse_regex_set, print_timings_summary, process_results_with_junit, read_lines, read_timings,
+    select_tests, shard_tests, watch_and_rerun, write_compare_csv, write_compare_junit,
+    CommandLineRunOptions, RunnerResults, RunnerStatus, SubRunConfig, TestCase, TestCommand,
 };
-use deqp_runner::{JunitGeneratorOptions, TestConfiguration};
+use deqp_runner::{JsonGeneratorOptions, JunitGeneratorOptions, TestConfiguration};
 use log::*;
+use regex::Regex;
 use serde::Deserialize;
+use std::collections::HashMap;
 use std::fs::File;
 use std::path::PathBuf;
 use structopt::StructOpt;
@@ -31,6 +37,9 @@ enum SubCommand {
     #[structopt(name = "junit")]
     Junit(Junit),
 
+    #[structopt(name = "json")]
+    Json(Json),
+
     #[structopt(name = "suite")]
     Suite(Suite),
 
@@ -39,6 +48,24 @@ enum SubCommand {
         help = "deqp-runner internal mock deqp binary for testing"
     )]
     MockDeqp(MockDeqp),
+
+    #[structopt(
+        name = "rerun",
+        help = "Re-run only the failing/flaky tests from a previous run's results.csv"
+    )]
+    Rerun(Rerun),
+
+    #[structopt(
+        name = "compare",
+        help = "Run the same caselist against two deqp builds and report regressions/fixes between them"
+    )]
+    Compare(Compare),
+
+    #[structopt(
+        name = "compare-timings",
+        help = "Compare two --timings-output files and report perf regressions past a threshold"
+    )]
+    CompareTimings(CompareTimings),
 }
 
 #[derive(Debug, StructOpt)]
@@ -48,12 +75,6 @@ pub struct DeqpRunnerGlobalOptions {
         help = "Optional path to store the deqp-vk .shader_cache files.  Must not be shared with any other deqp-runner invocations in progress."
     )]
     shader_cache_dir: Option<PathBuf>,
-
-    #[structopt(
-        long,
-        help = "Optional path to executor/testlog-to-xml, for converting QPA files to usable XML"
-    )]
-    testlog_to_xml: Option<PathBuf>,
 }
 
 #[derive(Debug, StructOpt)]
@@ -74,6 +95,90 @@ pub struct Run {
     deqp_config: DeqpRunConfig,
 }
 
+#[derive(Debug, StructOpt)]
+pub struct Rerun {
+    #[structopt(long, help = "path to deqp binary")]
+    deqp: PathBuf,
+
+    #[structopt(
+        long,
+        help = "Path to a prior run's results.csv (or failures.csv) to select failing/flaky tests from"
+    )]
+    results: PathBuf,
+
+    #[structopt(
+        long,
+        default_value = "1",
+        help = "Number of times to re-run each selected test, to characterize intermittency"
+    )]
+    repeat: u32,
+
+    #[structopt(flatten)]
+    common: CommandLineRunOptions,
+
+    #[structopt(flatten)]
+    deqp_global: DeqpRunnerGlobalOptions,
+
+    #[structopt(flatten)]
+    deqp_config: DeqpRunConfig,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct Compare {
+    #[structopt(long, help = "path to the 'before' deqp binary")]
+    deqp_a: PathBuf,
+
+    #[structopt(long, help = "path to the 'after' deqp binary")]
+    deqp_b: PathBuf,
+
+    #[structopt(
+        long,
+        help = "path to deqp caselist (such as *-mustpass.txt), run against both builds"
+    )]
+    caselist: Vec<PathBuf>,
+
+    #[structopt(long, help = "Path to write a CSV of status deltas between the two builds to")]
+    compare_csv: PathBuf,
+
+    #[structopt(long, help = "Path to write a JUnit XML report of only the changed cases to")]
+    compare_junit: Option<PathBuf>,
+
+    #[structopt(flatten)]
+    common: CommandLineRunOptions,
+
+    #[structopt(flatten)]
+    deqp_global: DeqpRunnerGlobalOptions,
+
+    #[structopt(flatten)]
+    deqp_config: DeqpRunConfig,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct CompareTimings {
+    #[structopt(long, help = "Path to a --timings-output file from a prior run")]
+    baseline: PathBuf,
+
+    #[structopt(
+        long,
+        help = "Path to a --timings-output file from the run to check for regressions"
+    )]
+    timings: PathBuf,
+
+    #[structopt(
+        long,
+        default_value = "1.5",
+        help = "Flag a case as a regression if its new/baseline duration ratio exceeds this"
+    )]
+    threshold: f32,
+
+    #[structopt(
+        long,
+        default_value = "25",
+        help = "Number of worst slowdowns to print (0 = no limit)"
+    )]
+    limit: usize,
+}
+
 #[derive(Debug, StructOpt)]
 pub struct Junit {
     #[structopt(long, help = "Path to source results.csv or failures.csv")]
@@ -86,6 +191,18 @@ pub struct Junit {
     junit_generator_options: JunitGeneratorOptions,
 }
 
+#[derive(Debug, StructOpt)]
+pub struct Json {
+    #[structopt(long, help = "Path to source results.csv or failures.csv")]
+    results: PathBuf,
+
+    #[structopt(long, short = "o", help = "Path to write the JSON report to")]
+    output: PathBuf,
+
+    #[structopt(flatten)]
+    json_generator_options: JsonGeneratorOptions,
+}
+
 #[derive(Debug, StructOpt)]
 pub struct Suite {
     #[structopt(long, help = "Path to suite.toml")]
@@ -99,7 +216,7 @@ pub struct Suite {
 }
 
 // CLI/toml options for a dEQP run
-#[derive(Debug, Deserialize, StructOpt)]
+#[derive(Clone, Debug, Deserialize, StructOpt)]
 struct DeqpRunConfig {
     #[structopt(
         long,
@@ -159,11 +276,79 @@ struct DeqpRunConfig {
     #[serde(default)]
     version_check: String,
 
+    // Regex -> replacement pairs, TOML-only (e.g. `normalize_rules = [["0x[0-9a-f]+", "0xNNNN"]]`),
+    // applied to QPA text before the renderer/version/extensions checks above
+    // compare it against an expectation.
+    #[structopt(skip)]
+    #[serde(default)]
+    normalize_rules: Vec<(String, String)>,
+
+    // Suite-only (a single `run` has nothing else to weight against): relative
+    // share of the worker pool this block's test groups should get among the
+    // other `[[deqp]]` blocks in the same suite.toml, so an expensive suite
+    // can be interleaved ahead of smaller ones instead of draining them in
+    // file order. Defaults to 0, meaning "weight 1", same as
+    // tests_per_group's 0-means-default convention above.
+    #[structopt(skip)]
+    #[serde(default)]
+    weight: usize,
+
+    #[structopt(
+        long,
+        default_value = "",
+        help = "text file of \"featureName=true/false\" lines to match against the VkPhysicalDeviceFeatures dEQP-VK.info.device reports enabled (dEQP-VK only)"
+    )]
+    #[serde(default)]
+    vk_features_check: String,
+
     #[structopt(help = "arguments to deqp binary")]
     #[serde(default)]
     deqp_args: Vec<String>,
 }
 
+fn compile_normalize_rules(rules: &[(String, String)]) -> Result<Vec<(Regex, String)>> {
+    rules
+        .iter()
+        .map(|(pattern, replacement)| {
+            let regex = Regex::new(pattern)
+                .with_context(|| format!("Compiling normalize_rules pattern '{}'", pattern))?;
+            Ok((regex, replacement.clone()))
+        })
+        .collect()
+}
+
+// Weaves each `[[deqp]]` block's test groups together by `weight` (a weighted
+// round-robin: the block with the least work dispatched so far relative to
+// its weight goes next), instead of draining one block's groups before
+// moving to the next, so a heavily-weighted expensive suite gets its groups
+// in front of the worker pool sooner rather than at the tail of a long
+// sequential queue.
+fn interleave_weighted_groups<'d>(
+    mut blocks: Vec<(usize, Vec<(&'d dyn TestCommand, Vec<TestCase>)>)>,
+) -> Vec<(&'d dyn TestCommand, Vec<TestCase>)> {
+    let mut dispatched = vec![0.0; blocks.len()];
+    let mut out = Vec::new();
+
+    loop {
+        let next = blocks
+            .iter()
+            .enumerate()
+            .filter(|(_, (_, groups))| !groups.is_empty())
+            .map(|(i, (weight, _))| (dispatched[i] / (*weight).max(1) as f64, i))
+            .min_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+
+        let i = match next {
+            Some((_, i)) => i,
+            None => break,
+        };
+
+        out.push(blocks[i].1.remove(0));
+        dispatched[i] += 1.0;
+    }
+
+    out
+}
+
 // Common structure for configuring a deqp run between Run (single run) and Suite (muliple Runs)
 #[derive(Deserialize)]
 struct DeqpTomlConfig {
@@ -196,8 +381,9 @@ impl DeqpTomlConfig {
                 .as_ref()
                 .unwrap_or(&run.output_dir)
                 .clone(),
-            qpa_to_xml: deqp_global.testlog_to_xml.clone(),
             prefix: self.prefix.to_owned(),
+            normalize_rules: compile_normalize_rules(&self.deqp_config.normalize_rules)
+                .context("compiling normalize_rules")?,
         })
     }
 
@@ -223,18 +409,20 @@ impl DeqpTomlConfig {
             include_filters.push(parse_regex_set(filters).context("compiling include filters")?);
         }
 
+        let shard_timings = load_shard_timings(&self.sub_config)?;
+
         let test_names: Vec<TestCase> = read_lines(&self.caselists)?
             .into_iter()
             .map(TestCase::Deqp)
-            .skip(self.sub_config.fraction_start - 1)
-            .step_by(self.sub_config.fraction)
-            .filter(|test| include_filters.iter().all(|x| x.is_match(test.name())))
-            .collect::<Vec<TestCase>>();
+            .collect();
+        let test_names = shard_tests(test_names, self.sub_config.shard, shard_timings.as_ref());
+        let test_names = select_tests(test_names, &self.sub_config, &include_filters)?;
 
         if !test_names.is_empty()
             && (!self.deqp_config.renderer_check.is_empty()
                 || !self.deqp_config.version_check.is_empty()
-                || !self.deqp_config.extensions_check.is_empty())
+                || !self.deqp_config.extensions_check.is_empty()
+                || !self.deqp_config.vk_features_check.is_empty())
         {
             // Look at the testcases in the caselist to decide how to probe the
             // renderer.  Note that we do some inference, because the caselist
@@ -251,10 +439,22 @@ impl DeqpTomlConfig {
 
             match deqp_version {
                 "dEQP-VK" => {
+                    if !self.deqp_config.version_check.is_empty() {
+                        anyhow::bail!("No version check implemented for VK.");
+                    }
+
                     if !deqp.qpa_vk_device_name_check(&self.deqp_config.renderer_check)? {
                         error!("Renderer mismatch ({})", &self.deqp_config.renderer_check);
                         std::process::exit(1);
                     }
+
+                    if !deqp.qpa_vk_extensions_check(&self.deqp_config.extensions_check)? {
+                        std::process::exit(1);
+                    }
+
+                    if !deqp.qpa_vk_features_check(&self.deqp_config.vk_features_check)? {
+                        std::process::exit(1);
+                    }
                 }
 
                 "dEQP-EGL" => {
@@ -361,6 +561,10 @@ struct SuiteConfig {
     deqp: Vec<DeqpTomlConfig>,
     #[serde(default)]
     piglit: Vec<PiglitTomlConfig>,
+    #[serde(default)]
+    gtest: Vec<GtestTomlConfig>,
+    #[serde(default)]
+    igt: Vec<IgtTomlConfig>,
 }
 
 fn main() -> Result<()> {
@@ -370,22 +574,269 @@ fn main() -> Result<()> {
         SubCommand::Run(run) => {
             run.common.setup()?;
 
+            let watch = run.common.watch;
+            let output_dir = run.common.output_dir.clone();
+            let binary_path = run.deqp.clone();
+            let caselist_paths = run.caselist.clone();
+            let deqp_global = run.deqp_global;
+            let deqp_config = run.deqp_config;
+            let common = run.common;
+            let mut sub_config = common.sub_config.clone();
+
+            let closure_binary_path = binary_path.clone();
+            let closure_caselist_paths = caselist_paths.clone();
+            let run_once = move |sub_config: &SubRunConfig| -> Result<()> {
+                let config = DeqpTomlConfig {
+                    deqp: closure_binary_path.clone(),
+                    caselists: closure_caselist_paths.clone(),
+                    sub_config: sub_config.clone(),
+                    deqp_config: deqp_config.clone(),
+                    prefix: "".to_owned(),
+                };
+                let deqp = config.deqp(&common, &deqp_global)?;
+
+                let results = parallel_test_with_ndjson(
+                    std::io::stdout(),
+                    config.test_groups(&deqp, &[])?,
+                    common.ndjson_writer()?,
+                    common.no_progress,
+                    common.profile.as_deref(),
+                    common.fail_fast,
+                )?;
+                process_results_with_junit(
+                    &results,
+                    &common.output_dir,
+                    common.summary_limit,
+                    common.junit_xml.as_deref(),
+                    common.diff,
+                    common.slow_test_secs,
+                    common.fail_on_flake,
+                    common.timings_output.as_deref(),
+                    watch,
+                )
+            };
+
+            if watch {
+                watch_and_rerun(
+                    &output_dir,
+                    &[binary_path],
+                    &caselist_paths,
+                    &mut sub_config,
+                    run_once,
+                )?;
+            } else {
+                run_once(&sub_config)?;
+            }
+        }
+
+        SubCommand::Rerun(rerun) => {
+            rerun.common.setup()?;
+
+            let prior_results = {
+                let mut file = File::open(&rerun.results).context("Opening --results")?;
+                RunnerResults::from_csv(&mut file).context("Parsing --results")?
+            };
+
+            let rerun_tests: Vec<TestCase> = prior_results
+                .sorted_results()
+                .into_iter()
+                .filter(|result| {
+                    matches!(
+                        result.status,
+                        RunnerStatus::Fail
+                            | RunnerStatus::Crash
+                            | RunnerStatus::Timeout
+                            | RunnerStatus::Flake
+                    )
+                })
+                .map(|result| TestCase::Deqp(result.test.clone()))
+                .collect();
+
+            if rerun_tests.is_empty() {
+                println!(
+                    "No failing or flaky tests found in {}",
+                    rerun.results.display()
+                );
+                return Ok(());
+            }
+
             let config = DeqpTomlConfig {
-                deqp: run.deqp,
-                caselists: run.caselist,
-                sub_config: run.common.sub_config.clone(),
-                deqp_config: run.deqp_config,
+                deqp: rerun.deqp,
+                caselists: Vec::new(),
+                sub_config: rerun.common.sub_config.clone(),
+                deqp_config: rerun.deqp_config,
+                prefix: "".to_owned(),
+            };
+            let deqp = config.deqp(&rerun.common, &rerun.deqp_global)?;
+
+            println!(
+                "Rerunning {} tests from {} x{}",
+                rerun_tests.len(),
+                rerun.results.display(),
+                rerun.repeat
+            );
+
+            // Run the selected tests --repeat times to characterize intermittency.
+            // The final pass is what gets written out as results.csv/failures.csv/junit
+            // (matching the normal single-run behavior when --repeat is left at 1); we
+            // separately tally a pass/attempt count per test across all passes for the
+            // flake-rate summary below.
+            let mut attempt_counts: HashMap<String, (u32, u32)> = HashMap::new();
+            let mut results = RunnerResults::new();
+            for attempt in 1..=rerun.repeat {
+                let groups = deqp.split_tests_to_groups(
+                    rerun_tests.clone(),
+                    config.deqp_config.tests_per_group,
+                    config.deqp_config.min_tests_per_group,
+                )?;
+
+                results = parallel_test_with_ndjson(
+                    std::io::stdout(),
+                    groups,
+                    rerun.common.ndjson_writer()?,
+                    rerun.common.no_progress,
+                    rerun.common.profile.as_deref(),
+                    rerun.common.fail_fast,
+                )?;
+
+                for result in results.sorted_results() {
+                    let entry = attempt_counts.entry(result.test.clone()).or_insert((0, 0));
+                    entry.1 += 1;
+                    if result.status.is_success() {
+                        entry.0 += 1;
+                    }
+                }
+
+                if rerun.repeat > 1 {
+                    println!("Attempt {}/{} complete", attempt, rerun.repeat);
+                }
+            }
+
+            results.write_results(&mut File::create(
+                rerun.common.output_dir.join("results.csv"),
+            )?)?;
+            results.write_failures(&mut File::create(
+                rerun.common.output_dir.join("failures.csv"),
+            )?)?;
+            if let Some(junit_xml) = rerun.common.junit_xml.as_deref() {
+                results
+                    .write_junit_report(&mut File::create(junit_xml)?, "deqp-runner")
+                    .context("writing --junit-xml report")?;
+            }
+
+            results.print_summary(
+                if rerun.common.summary_limit == 0 {
+                    std::usize::MAX
+                } else {
+                    rerun.common.summary_limit
+                },
+                rerun.common.slow_test_secs,
+            );
+
+            if rerun.repeat > 1 {
+                let mut flaky: Vec<_> = attempt_counts
+                    .iter()
+                    .filter(|(_, (passes, attempts))| passes < attempts && *passes > 0)
+                    .collect();
+                if !flaky.is_empty() {
+                    flaky.sort_by_key(|(name, _)| name.as_str());
+                    println!();
+                    println!("Flake rate across {} reruns:", rerun.repeat);
+                    for (name, (passes, attempts)) in flaky {
+                        println!("  {}: {}/{} failed", name, attempts - passes, attempts);
+                    }
+                }
+            }
+
+            if !results.is_success() {
+                std::process::exit(1);
+            }
+        }
+
+        SubCommand::Compare(compare) => {
+            compare.common.setup()?;
+
+            let config_a = DeqpTomlConfig {
+                deqp: compare.deqp_a,
+                caselists: compare.caselist.clone(),
+                sub_config: compare.common.sub_config.clone(),
+                deqp_config: compare.deqp_config.clone(),
                 prefix: "".to_owned(),
             };
-            let deqp = config.deqp(&run.common, &run.deqp_global)?;
+            let config_b = DeqpTomlConfig {
+                deqp: compare.deqp_b,
+                caselists: compare.caselist,
+                sub_config: compare.common.sub_config.clone(),
+                deqp_config: compare.deqp_config,
+                prefix: "".to_owned(),
+            };
+
+            let deqp_a = config_a.deqp(&compare.common, &compare.deqp_global)?;
+            let deqp_b = config_b.deqp(&compare.common, &compare.deqp_global)?;
+
+            println!("Running 'before' build...");
+            let results_a = parallel_test_with_ndjson(
+                std::io::stdout(),
+                config_a.test_groups(&deqp_a, &[])?,
+                None,
+                compare.common.no_progress,
+                compare.common.profile.as_deref(),
+                compare.common.fail_fast,
+            )?;
+
+            println!("Running 'after' build...");
+            let results_b = parallel_test_with_ndjson(
+                std::io::stdout(),
+                config_b.test_groups(&deqp_b, &[])?,
+                None,
+                compare.common.no_progress,
+                compare.common.profile.as_deref(),
+                compare.common.fail_fast,
+            )?;
+
+            let deltas = compare_results(&results_a, &results_b);
+
+            write_compare_csv(
+                &mut File::create(&compare.compare_csv).context("creating --compare-csv file")?,
+                &deltas,
+            )?;
 
-            let results = parallel_test(std::io::stdout(), config.test_groups(&deqp, &[])?)?;
-            process_results(&results, &run.common.output_dir, run.common.summary_limit)?;
+            if let Some(compare_junit) = &compare.compare_junit {
+                write_compare_junit(
+                    &mut File::create(compare_junit).context("creating --compare-junit file")?,
+                    &deltas,
+                    "deqp-runner-compare",
+                )?;
+            }
+
+            let regressions = deltas
+                .iter()
+                .filter(|d| d.direction == deqp_runner::CompareDirection::Regression)
+                .count();
+            let fixes = deltas
+                .iter()
+                .filter(|d| d.direction == deqp_runner::CompareDirection::Fixed)
+                .count();
+            println!(
+                "{} changed tests: {} regressions, {} fixes, {} other churn",
+                deltas.len(),
+                regressions,
+                fixes,
+                deltas.len() - regressions - fixes
+            );
+
+            if regressions != 0 {
+                std::process::exit(1);
+            }
         }
 
         SubCommand::Suite(suite) => {
             suite.common.setup()?;
 
+            if suite.common.watch {
+                bail!("--watch is not yet supported by the suite subcommand, only by `deqp run`");
+            }
+
             let toml_str = std::fs::read_to_string(&suite.suite).context("Reading config TOML")?;
             let suite_config =
                 toml::from_str::<SuiteConfig>(toml_str.as_str()).context("Parsing config TOML")?;
@@ -413,6 +864,24 @@ fn main() -> Result<()> {
                 piglit_configs.push(config);
             }
 
+            let mut gtest_configs = Vec::new();
+            for mut config in suite_config.gtest {
+                config
+                    .sub_config
+                    .apply_suite_top_config(&suite.common.sub_config);
+
+                gtest_configs.push(config);
+            }
+
+            let mut igt_configs = Vec::new();
+            for mut config in suite_config.igt {
+                config
+                    .sub_config
+                    .apply_suite_top_config(&suite.common.sub_config);
+
+                igt_configs.push(config);
+            }
+
             let mut deqp = Vec::new();
             for config in &deqp_configs {
                 deqp.push(config.deqp(&suite.common, &suite.deqp_global)?);
@@ -427,24 +896,67 @@ fn main() -> Result<()> {
                         &config.sub_config,
                     )?,
                     prefix: config.prefix.clone(),
+                    isolate_crashing_multi_shader_groups: config
+                        .piglit_config
+                        .isolate_crashing_multi_shader_groups,
+                    hang_signatures: parse_regex_set(&config.piglit_config.hang_signatures)
+                        .context("compiling --hang-signature regexes")?,
+                    expected_output: config
+                        .expected_output
+                        .iter()
+                        .map(CompiledExpectedOutputRule::compile)
+                        .collect::<Result<_>>()
+                        .context("compiling expected_output rules")?,
                 });
             }
 
-            let mut test_groups = Vec::new();
+            let mut gtest = Vec::new();
+            for config in &gtest_configs {
+                gtest.push(config.gtest(&suite.common)?);
+            }
+
+            let mut igt = Vec::new();
+            for config in &igt_configs {
+                igt.push(config.igt(&suite.common)?);
+            }
+
+            let mut deqp_weighted_groups = Vec::new();
             for (config, deqp) in deqp_configs.iter().zip(deqp.iter()) {
-                let mut groups = config.test_groups(deqp, &suite.common.sub_config.include)?;
-                test_groups.append(&mut groups);
+                let groups = config.test_groups(deqp, &suite.common.sub_config.include)?;
+                deqp_weighted_groups.push((config.deqp_config.weight, groups));
             }
+            let mut test_groups = interleave_weighted_groups(deqp_weighted_groups);
             for (config, piglit) in piglit_configs.iter().zip(piglit.iter()) {
                 let mut groups = config.test_groups(piglit, &suite.common.sub_config.include)?;
                 test_groups.append(&mut groups);
             }
+            for (config, gtest) in gtest_configs.iter().zip(gtest.iter()) {
+                let mut groups = config.test_groups(gtest, &suite.common.sub_config.include)?;
+                test_groups.append(&mut groups);
+            }
+            for (config, igt) in igt_configs.iter().zip(igt.iter()) {
+                let mut groups = config.test_groups(igt, &suite.common.sub_config.include)?;
+                test_groups.append(&mut groups);
+            }
 
-            let results = parallel_test(std::io::stdout(), test_groups)?;
-            process_results(
+            let results = parallel_test_with_ndjson(
+                std::io::stdout(),
+                test_groups,
+                suite.common.ndjson_writer()?,
+                suite.common.no_progress,
+                suite.common.profile.as_deref(),
+                suite.common.fail_fast,
+            )?;
+            process_results_with_junit(
                 &results,
                 &suite.common.output_dir,
                 suite.common.summary_limit,
+                suite.common.junit_xml.as_deref(),
+                suite.common.diff,
+                suite.common.slow_test_secs,
+                suite.common.fail_on_flake,
+                suite.common.timings_output.as_deref(),
+                false,
             )?;
         }
 
@@ -460,11 +972,37 @@ fn main() -> Result<()> {
             )?;
         }
 
+        SubCommand::Json(json) => {
+            stderrlog::new().module(module_path!()).init().unwrap();
+
+            let results = RunnerResults::from_csv(&mut File::open(&json.results)?)
+                .context("Reading in results csv")?;
+
+            results.write_json(
+                &mut File::create(&json.output)?,
+                &json.json_generator_options,
+            )?;
+        }
+
         SubCommand::MockDeqp(mock) => {
             stderrlog::new().module(module_path!()).init().unwrap();
 
             mock_deqp(&mock)?;
         }
+
+        SubCommand::CompareTimings(compare) => {
+            stderrlog::new().module(module_path!()).init().unwrap();
+
+            let baseline = read_timings(&mut File::open(&compare.baseline)?)
+                .context("Reading --baseline timings")?;
+            let timings =
+                read_timings(&mut File::open(&compare.timings)?).context("Reading --timings")?;
+
+            let deltas = compare_timings(&baseline, &timings);
+            if print_timings_summary(&deltas, compare.threshold, compare.limit) {
+                std::process::exit(1);
+            }
+        }
     }
 
     Ok(())
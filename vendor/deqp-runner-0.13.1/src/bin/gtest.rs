@@ -1,8 +1,9 @@
-use anyhow::Result;
+use anyhow::{bail, Result};
 use deqp_runner::gtest_command::GTestCommand;
 use deqp_runner::mock_gtest::MockGTest;
 use deqp_runner::{
-    parallel_test, process_results, CommandLineRunOptions, TestCommand, TestConfiguration,
+    load_shard_timings, parallel_test_with_ndjson, process_results_with_junit, select_tests,
+    shard_tests, CommandLineRunOptions, TestCommand, TestConfiguration,
 };
 use std::path::PathBuf;
 use structopt::StructOpt;
@@ -52,6 +53,18 @@ pub struct Run {
     )]
     min_tests_per_group: usize,
 
+    #[structopt(
+        long,
+        help = "Parse results from a --gtest_output=json report instead of scraping them from stdout, for full failure text and less fragile crash/status attribution"
+    )]
+    json_output: bool,
+
+    #[structopt(
+        long,
+        help = "When a leak or a crash taints a batch run (see above), re-run the affected caselist one test at a time to pin the leak/crash to its actual culprit instead of tainting (or losing track of) every test in the batch"
+    )]
+    isolate_crashes: bool,
+
     #[structopt(help = "arguments to gtest binary")]
     gtest_args: Vec<String>,
 }
@@ -63,21 +76,28 @@ fn main() -> Result<()> {
         SubCommand::Run(run) => {
             run.common.setup()?;
 
+            if run.common.watch {
+                bail!("--watch is not yet supported by gtest-runner");
+            }
+
             let include_filter = run.common.includes_regex()?;
 
             let gtest = GTestCommand {
                 bin: run.gtest,
                 config: TestConfiguration::from_cli(&run.common)?,
                 args: run.gtest_args,
+                json_output: run.json_output,
+                isolate_crashes: run.isolate_crashes,
             };
 
-            let tests = gtest
-                .list_tests()?
-                .into_iter()
-                .skip(run.common.sub_config.fraction_start - 1)
-                .step_by(run.common.sub_config.fraction)
-                .filter(|x| include_filter.is_match(x.name()))
-                .collect();
+            let shard_timings = load_shard_timings(&run.common.sub_config)?;
+
+            let tests = shard_tests(
+                gtest.list_tests()?,
+                run.common.sub_config.shard,
+                shard_timings.as_ref(),
+            );
+            let tests = select_tests(tests, &run.common.sub_config, &[include_filter])?;
 
             let groups =
                 gtest.split_tests_to_groups(tests, run.tests_per_group, run.min_tests_per_group)?;
@@ -92,8 +112,25 @@ fn main() -> Result<()> {
                 }
             );
 
-            let results = parallel_test(std::io::stdout(), groups)?;
-            process_results(&results, &run.common.output_dir, run.common.summary_limit)?;
+            let results = parallel_test_with_ndjson(
+                std::io::stdout(),
+                groups,
+                run.common.ndjson_writer()?,
+                run.common.no_progress,
+                run.common.profile.as_deref(),
+                run.common.fail_fast,
+            )?;
+            process_results_with_junit(
+                &results,
+                &run.common.output_dir,
+                run.common.summary_limit,
+                run.common.junit_xml.as_deref(),
+                run.common.diff,
+                run.common.slow_test_secs,
+                run.common.fail_on_flake,
+                run.common.timings_output.as_deref(),
+                false,
+            )?;
         }
 
         SubCommand::MockGTest(mock) => {
@@ -0,0 +1,110 @@
+use anyhow::{bail, Result};
+use deqp_runner::igt_command::{parse_igt_caselist, IgtCommand};
+use deqp_runner::mock_igt::MockIgt;
+use deqp_runner::{
+    load_shard_timings, parallel_test_with_ndjson, process_results_with_junit, read_lines,
+    select_tests, shard_tests, CommandLineRunOptions, TestCommand, TestConfiguration,
+};
+use std::path::PathBuf;
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+#[structopt(
+    author = "Emma Anholt <emma@anholt.net>",
+    about = "Runs IGT GPU Tools subtests in parallel"
+)]
+struct Opts {
+    #[structopt(subcommand)]
+    subcmd: SubCommand,
+}
+
+#[derive(Debug, StructOpt)]
+enum SubCommand {
+    #[structopt(name = "run")]
+    Run(Run),
+
+    #[structopt(
+        name = "mock-igt",
+        help = "igt-runner internal mock igt binary for testing"
+    )]
+    MockIgt(MockIgt),
+}
+
+#[derive(Debug, StructOpt)]
+pub struct Run {
+    #[structopt(long, help = "path to the folder of igt-gpu-tools binaries")]
+    igt_folder: PathBuf,
+
+    #[structopt(
+        long = "caselist",
+        help = "File of \"binary@subtest\" lines to run, one per line"
+    )]
+    caselist: Vec<PathBuf>,
+
+    #[structopt(flatten)]
+    common: CommandLineRunOptions,
+
+    #[structopt(help = "arguments to the igt binary, ahead of --run-subtest")]
+    igt_args: Vec<String>,
+}
+
+fn main() -> Result<()> {
+    let opts = Opts::from_args();
+
+    match opts.subcmd {
+        SubCommand::Run(run) => {
+            run.common.setup()?;
+
+            if run.common.watch {
+                bail!("--watch is not yet supported by igt-runner");
+            }
+
+            let include_filter = run.common.includes_regex()?;
+
+            let igt = IgtCommand {
+                igt_folder: run.igt_folder,
+                args: run.igt_args,
+                config: TestConfiguration::from_cli(&run.common)?,
+            };
+
+            let shard_timings = load_shard_timings(&run.common.sub_config)?;
+
+            let tests = parse_igt_caselist(&read_lines(&run.caselist)?, "")?;
+            let tests = shard_tests(tests, run.common.sub_config.shard, shard_timings.as_ref());
+            let tests = select_tests(tests, &run.common.sub_config, &[include_filter])?;
+
+            // Each igt binary only runs a single subtest per invocation (so
+            // that a crash in one subtest can't take down the rest of its
+            // group), same as piglit.
+            let groups = igt.split_tests_to_groups(tests, 1, 1)?;
+
+            let results = parallel_test_with_ndjson(
+                std::io::stdout(),
+                groups,
+                run.common.ndjson_writer()?,
+                run.common.no_progress,
+                run.common.profile.as_deref(),
+                run.common.fail_fast,
+            )?;
+            process_results_with_junit(
+                &results,
+                &run.common.output_dir,
+                run.common.summary_limit,
+                run.common.junit_xml.as_deref(),
+                run.common.diff,
+                run.common.slow_test_secs,
+                run.common.fail_on_flake,
+                run.common.timings_output.as_deref(),
+                false,
+            )?;
+        }
+
+        SubCommand::MockIgt(mock) => {
+            stderrlog::new().module(module_path!()).init().unwrap();
+
+            deqp_runner::mock_igt::mock_igt(&mock)?;
+        }
+    }
+
+    Ok(())
+}
@@ -19,10 +19,16 @@
 // OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE
 // USE OR OTHER DEALINGS IN THE SOFTWARE.
 
-use anyhow::Result;
+use anyhow::{bail, Context, Result};
 use deqp_runner::mock_piglit::{mock_piglit, MockPiglit};
-use deqp_runner::piglit_command::{PiglitCommand, PiglitRunConfig, PiglitTomlConfig};
-use deqp_runner::{parallel_test, process_results, CommandLineRunOptions, TestConfiguration};
+use deqp_runner::piglit_command::{PiglitCommand, PiglitReplay, PiglitRunConfig, PiglitTomlConfig};
+use deqp_runner::{
+    parallel_test_with_ndjson, parse_regex_set, process_results_with_junit, CommandLineRunOptions,
+    TestConfiguration,
+};
+use std::fs::File;
+use std::path::PathBuf;
+use std::process::exit;
 use structopt::StructOpt;
 
 #[derive(Debug, StructOpt)]
@@ -41,6 +47,15 @@ enum SubCommand {
     Run(Run),
     #[structopt(name = "mock-piglit")]
     MockPiglit(MockPiglit),
+    #[structopt(name = "replay")]
+    Replay(Replay),
+}
+
+#[derive(Debug, StructOpt)]
+#[structopt(about = "Re-runs a single test from a piglit.<name>.replay.json written by `run`")]
+pub struct Replay {
+    #[structopt(help = "replay file written next to a failing test's piglit.<name>.log")]
+    pub replay_file: PathBuf,
 }
 
 #[derive(Debug, StructOpt)]
@@ -50,6 +65,13 @@ pub struct Run {
 
     #[structopt(flatten)]
     pub piglit_config: PiglitRunConfig,
+
+    #[structopt(
+        long,
+        alias = "update-baseline",
+        help = "Instead of reporting regressions against --baseline, overwrite it with the statuses observed this run and exit 0"
+    )]
+    pub bless: bool,
 }
 
 fn main() -> Result<()> {
@@ -59,19 +81,63 @@ fn main() -> Result<()> {
         SubCommand::Run(run) => {
             run.common.setup()?;
 
+            if run.common.watch {
+                bail!("--watch is not yet supported by piglit-runner");
+            }
+
             let config = PiglitTomlConfig {
                 sub_config: run.common.sub_config.clone(),
                 piglit_config: run.piglit_config,
                 prefix: "".to_owned(),
+                expected_output: Vec::new(),
             };
 
             let piglit = PiglitCommand {
                 piglit_folder: config.piglit_config.piglit_folder.clone(),
                 config: TestConfiguration::from_cli(&run.common)?,
                 prefix: "".to_owned(),
+                isolate_crashing_multi_shader_groups: config
+                    .piglit_config
+                    .isolate_crashing_multi_shader_groups,
+                hang_signatures: parse_regex_set(&config.piglit_config.hang_signatures)
+                    .context("compiling --hang-signature regexes")?,
+                expected_output: Vec::new(),
             };
-            let results = parallel_test(std::io::stdout(), config.test_groups(&piglit, &[])?)?;
-            process_results(&results, &run.common.output_dir, run.common.summary_limit)?;
+            let results = parallel_test_with_ndjson(
+                std::io::stdout(),
+                config.test_groups(&piglit, &[])?,
+                run.common.ndjson_writer()?,
+                run.common.no_progress,
+                run.common.profile.as_deref(),
+                run.common.fail_fast,
+            )?;
+
+            if run.bless {
+                let baseline_path = run.common.sub_config.baseline.as_ref().context(
+                    "--bless requires --baseline to point at the file to rewrite",
+                )?;
+                // Read the prior baseline before truncating the file it came from.
+                let prior_baseline = run.common.baseline()?;
+                results
+                    .write_baseline(
+                        &mut File::create(baseline_path).context("creating --baseline file")?,
+                        &prior_baseline,
+                    )
+                    .context("writing blessed baseline")?;
+                println!("Blessed {}", baseline_path.display());
+            } else {
+                process_results_with_junit(
+                    &results,
+                    &run.common.output_dir,
+                    run.common.summary_limit,
+                    run.common.junit_xml.as_deref(),
+                    run.common.diff,
+                    run.common.slow_test_secs,
+                    run.common.fail_on_flake,
+                    run.common.timings_output.as_deref(),
+                    false,
+                )?;
+            }
         }
 
         SubCommand::MockPiglit(mock) => {
@@ -79,6 +145,12 @@ fn main() -> Result<()> {
 
             mock_piglit(&mock)?;
         }
+
+        SubCommand::Replay(replay) => {
+            let replay = PiglitReplay::load(&replay.replay_file)?;
+            let status = replay.run()?;
+            exit(status.code().unwrap_or(1));
+        }
     }
 
     Ok(())
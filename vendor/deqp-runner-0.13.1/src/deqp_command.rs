@@ -4,7 +4,7 @@ use crate::{runner_thread_index, TestCase, TestCommand, TestConfiguration};
 use anyhow::{Context, Result};
 use log::*;
 use regex::Regex;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::fs::File;
 use std::io::prelude::*;
 use std::io::{BufReader, BufWriter};
@@ -16,8 +16,12 @@ pub struct DeqpCommand {
     pub shader_cache_dir: PathBuf,
     pub args: Vec<String>,
     pub config: TestConfiguration,
-    pub qpa_to_xml: Option<PathBuf>,
     pub prefix: String,
+    /// Regex -> replacement rules applied (in order) to QPA `<Text>` content
+    /// before the renderer/version/extensions checks compare it, so
+    /// expectation files don't have to encode nondeterministic driver noise
+    /// (build hashes, addresses, dates, PCI IDs). See `DeqpCommand::normalize`.
+    pub normalize_rules: Vec<(Regex, String)>,
 }
 
 fn write_caselist_file(filename: &Path, tests: &[&TestCase]) -> Result<()> {
@@ -59,6 +63,31 @@ pub fn qpa_xml_for_testcase<'a>(qpa: &'a str, test: &str) -> Result<&'a str> {
     Ok(xml_until_end)
 }
 
+// dEQP's own testlog-to-xml output carries this stylesheet reference so
+// that opening a converted log in a browser renders it instead of showing
+// raw XML; we point at the same name so existing viewing setups keep working.
+const QPA_XML_STYLESHEET_HREF: &str = "testlog.xsl";
+
+// Wraps a single <TestCaseResult> XML fragment (as extracted by
+// `qpa_xml_for_testcase`) into a standalone, viewer-ready XML document:
+// the XML declaration, the dEQP stylesheet reference, and an enclosing
+// root element, mirroring what the external testlog-to-xml tool produces
+// for a whole QPA file, but done natively so that tool doesn't need to be
+// present. Validates the wrapped document parses before handing it back,
+// so a corrupt QPA fragment is caught here instead of producing a broken
+// XML file on disk.
+pub fn qpa_xml_document(fragment: &str) -> Result<String> {
+    let document = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<?xml-stylesheet type=\"text/xsl\" href=\"{}\"?>\n<BatchResult>\n{}\n</BatchResult>\n",
+        QPA_XML_STYLESHEET_HREF,
+        fragment.trim()
+    );
+
+    roxmltree::Document::parse(&document).context("Validating converted QPA XML document")?;
+
+    Ok(document)
+}
+
 // Returns the text from inside the XML's <Text>...</Text> nodes.
 pub fn qpa_xml_text(xml: &str) -> Result<String> {
     let doc = roxmltree::Document::parse(xml).context("Parsing QPA XML")?;
@@ -82,35 +111,15 @@ impl DeqpCommand {
 
         if !output.is_empty() {
             let out_path = qpa_path.parent().unwrap().join(format!("{}.qpa", test));
-            // Write the extracted QPA contents to an individual file.
-            {
-                let mut out_qpa = BufWriter::new(File::create(&out_path).with_context(|| {
-                    format!("Opening output QPA file {:?}", qpa_path.display())
-                })?);
-                out_qpa.write_all(output.as_bytes())?;
-            }
 
-            // Now that the QPA file is written (and flushed, note the separate
-            // block!), call out to testlog-to-xml to convert it to an XML file
-            // for display.
-            if let Some(qpa_to_xml) = self.qpa_to_xml() {
-                let xml_path = out_path.with_extension("xml");
-                let convert_output = Command::new(qpa_to_xml)
-                    .current_dir(self.deqp.parent().unwrap_or_else(|| Path::new("/")))
-                    .arg(&out_path)
-                    .arg(xml_path)
-                    .output()
-                    .with_context(|| format!("Failed to spawn {}", qpa_to_xml.display()))?;
-                if !convert_output.status.success() {
-                    anyhow::bail!(
-                        "Failed to run {}: {}",
-                        qpa_to_xml.display(),
-                        String::from_utf8_lossy(&convert_output.stderr)
-                    );
-                } else {
-                    std::fs::remove_file(&out_path).context("removing converted QPA")?;
-                }
-            }
+            let fragment = qpa_xml_for_testcase(&output, test)
+                .with_context(|| format!("Extracting QPA XML fragment for {}", test))?;
+            let document = qpa_xml_document(fragment)
+                .with_context(|| format!("Converting QPA XML for {}", test))?;
+
+            let xml_path = out_path.with_extension("xml");
+            std::fs::write(&xml_path, document)
+                .with_context(|| format!("Writing {:?}", xml_path.display()))?;
         }
 
         Ok(())
@@ -168,12 +177,24 @@ impl DeqpCommand {
         Ok(qpa)
     }
 
+    // Applies `normalize_rules` (in order) to QPA text before it's compared
+    // against an expectation, so e.g. a build hash or PCI id in the real
+    // output doesn't have to be spelled out verbatim in the check regex.
+    fn normalize(&self, text: &str) -> String {
+        let mut text = text.to_string();
+        for (pattern, replacement) in &self.normalize_rules {
+            text = pattern.replace_all(&text, replacement.as_str()).into_owned();
+        }
+        text
+    }
+
     pub fn qpa_vk_device_name_check(&self, regex: &str) -> Result<bool> {
         let testcase = "dEQP-VK.info.device";
         let qpa = self.deqp_test_qpa_output(testcase, testcase)?;
         let xml = qpa_xml_for_testcase(&qpa, testcase)?;
+        let text = self.normalize(&qpa_xml_text(xml)?);
 
-        for line in qpa_xml_text(xml)?.lines() {
+        for line in text.lines() {
             if line.starts_with("deviceName: ") {
                 println!("{}", line);
 
@@ -199,8 +220,9 @@ impl DeqpCommand {
         let doc = roxmltree::Document::parse(xml)
             .with_context(|| format!("Parsing QPA XML for {}", testcase))?;
 
-        for text in doc.descendants().filter(|n| n.has_tag_name("Text")) {
-            if let Some(text) = text.text() {
+        for node_text in doc.descendants().filter(|n| n.has_tag_name("Text")) {
+            if let Some(text) = node_text.text() {
+                let text = self.normalize(text);
                 println!("{}: {}", log_name, text);
                 if regex.is_empty() {
                     return Ok(true);
@@ -208,7 +230,7 @@ impl DeqpCommand {
                     let regex = Regex::new(regex).with_context(|| {
                         format!("Compiling QPA renderer/version check RE '{}'", regex)
                     })?;
-                    return Ok(regex.is_match(text));
+                    return Ok(regex.is_match(&text));
                 }
             }
         }
@@ -226,7 +248,8 @@ impl DeqpCommand {
         }
 
         let xml = qpa_xml_for_testcase(qpa, testcase)?;
-        let probed_extensions = qpa_xml_text(xml)?
+        let probed_extensions = self
+            .normalize(&qpa_xml_text(xml)?)
             .lines()
             .map(|x| x.trim().to_string())
             .collect::<HashSet<String>>();
@@ -253,8 +276,113 @@ impl DeqpCommand {
         Ok(true)
     }
 
-    fn qpa_to_xml(&self) -> Option<&PathBuf> {
-        self.qpa_to_xml.as_ref()
+    // dEQP-VK.info.device logs its enumerated device extensions and enabled
+    // VkPhysicalDeviceFeatures as one line per entry, alongside the
+    // "deviceName: " line already consulted by qpa_vk_device_name_check:
+    // "deviceExtension: VK_KHR_..." and "deviceFeature <name> = <true|false>".
+    fn qpa_vk_device_text(&self) -> Result<String> {
+        let testcase = "dEQP-VK.info.device";
+        let qpa = self.deqp_test_qpa_output(testcase, testcase)?;
+        let xml = qpa_xml_for_testcase(&qpa, testcase)?;
+        Ok(self.normalize(&qpa_xml_text(xml)?))
+    }
+
+    fn qpa_vk_probed_extensions(text: &str) -> HashSet<String> {
+        text.lines()
+            .filter_map(|line| line.strip_prefix("deviceExtension: "))
+            .map(|ext| ext.trim().to_string())
+            .collect()
+    }
+
+    fn qpa_vk_probed_features(text: &str) -> HashMap<String, bool> {
+        text.lines()
+            .filter_map(|line| line.strip_prefix("deviceFeature "))
+            .filter_map(|rest| rest.split_once(" = "))
+            .map(|(name, value)| (name.trim().to_string(), value.trim() == "true"))
+            .collect()
+    }
+
+    // Mirrors qpa_extensions_check's exact-set Missing/Unexpected diff, but
+    // against dEQP-VK.info.device's enumerated device extensions instead of
+    // a GL/EGL extension string.
+    pub fn qpa_vk_extensions_check(&self, extensions_check: &str) -> Result<bool> {
+        if extensions_check.is_empty() {
+            return Ok(true);
+        }
+
+        let probed_extensions = Self::qpa_vk_probed_extensions(&self.qpa_vk_device_text()?);
+
+        let expected_extensions = std::fs::read_to_string(extensions_check)
+            .with_context(|| format!("Reading expected VK extensions file {}", extensions_check))?
+            .lines()
+            .map(|x| x.trim().to_string())
+            .filter(|x| !x.is_empty())
+            .collect::<HashSet<String>>();
+
+        if probed_extensions != expected_extensions {
+            error!("VK extensions mismatch:");
+
+            for ext in probed_extensions.difference(&expected_extensions) {
+                error!("Unexpected: {}", ext);
+            }
+            for ext in expected_extensions.difference(&probed_extensions) {
+                error!("Missing: {}", ext);
+            }
+            return Ok(false);
+        }
+
+        Ok(true)
+    }
+
+    // Checks a small table of `featureName=true/false` lines (e.g.
+    // "robustBufferAccess=true") against the VkPhysicalDeviceFeatures dEQP
+    // reports enabled, printing the same Missing/Unexpected shape as
+    // qpa_vk_extensions_check for any feature that's absent or mismatched.
+    // Unlike the extensions check this isn't an exact-set comparison: you
+    // only have to list the features you actually care about asserting.
+    pub fn qpa_vk_features_check(&self, features_check: &str) -> Result<bool> {
+        if features_check.is_empty() {
+            return Ok(true);
+        }
+
+        let probed_features = Self::qpa_vk_probed_features(&self.qpa_vk_device_text()?);
+
+        let expected = std::fs::read_to_string(features_check)
+            .with_context(|| format!("Reading expected VK features file {}", features_check))?;
+
+        let mut ok = true;
+        for (lineno, line) in expected.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let (name, value) = line.split_once('=').with_context(|| {
+                format!(
+                    "{}:{}: expected \"featureName=true/false\"",
+                    features_check,
+                    lineno + 1
+                )
+            })?;
+            let (name, expected_value) = (name.trim(), value.trim() == "true");
+
+            match probed_features.get(name) {
+                Some(&probed_value) if probed_value == expected_value => {}
+                Some(&probed_value) => {
+                    error!(
+                        "Unexpected: {} = {} (expected {})",
+                        name, probed_value, expected_value
+                    );
+                    ok = false;
+                }
+                None => {
+                    error!("Missing: {} not reported by deqp", name);
+                    ok = false;
+                }
+            }
+        }
+
+        Ok(ok)
     }
 }
 
@@ -318,6 +446,7 @@ impl TestCommand for DeqpCommand {
             // thrown (you're running deqp!  Of course it makes GL errors!)
             .env("MESA_DEBUG", "silent")
             .envs(self.config.env.iter());
+        crate::set_process_group(&mut command);
 
         let command_line = format!("{:?}", command);
 
@@ -328,9 +457,10 @@ impl TestCommand for DeqpCommand {
         let stdout = child.stdout.take().context("opening stdout")?;
         let caselist_results = parse_deqp_results_with_timeout(stdout, self.config.timeout);
 
-        // The child should have run to completion based on parse_deqp_results() consuming its output,
-        // but if we had a timeout or parse failure then we want to kill this run.
-        let _ = child.kill();
+        // The child should have run to completion based on parse_deqp_results() consuming its
+        // output, but if we had a timeout or parse failure then we want to kill this run,
+        // process group and all, so any helper process it forked doesn't outlive it.
+        crate::kill_child_process_group(&mut child);
 
         // Make sure we reap the child process.
         let child_status = child.wait().context("waiting for child")?;
@@ -344,10 +474,16 @@ impl TestCommand for DeqpCommand {
             }
         }
 
-        let stderr: Vec<String> = BufReader::new(child.stderr.as_mut().context("opening stderr")?)
-            .lines()
-            .flatten()
-            .collect();
+        let (stderr, stderr_truncated) = crate::read_bounded_lines(
+            child.stderr.as_mut().context("opening stderr")?,
+            crate::CAPTURE_BYTE_LIMIT,
+        );
+        if stderr_truncated {
+            warn!(
+                "stderr for caselist c{}.r{} exceeded the capture limit and was truncated",
+                caselist_state.caselist_id, caselist_state.run_id
+            );
+        }
 
         for line in &stderr {
             // If the driver has ASan enabled and it detected leaks, then mark
@@ -387,6 +523,7 @@ impl TestCommand for DeqpCommand {
                 status,
                 duration: result.duration.as_secs_f32(),
                 subtest: false,
+                flake_retries: 0,
             });
         }
 
@@ -0,0 +1,59 @@
+// Raises the process's open-file-descriptor soft limit to its hard limit at
+// startup, mirroring rustc compiletest's raise_fd_limit. At high -j
+// concurrency, each running test consumes pipe fds for its captured
+// stdout/stderr, and the default soft limit (often 1024) is exhausted well
+// before the CPU is saturated.
+use log::*;
+
+#[cfg(unix)]
+pub fn raise_fd_limit() {
+    unsafe {
+        let mut limits: libc::rlimit = std::mem::zeroed();
+        if libc::getrlimit(libc::RLIMIT_NOFILE, &mut limits) != 0 {
+            warn!("Failed to read RLIMIT_NOFILE, leaving the fd limit unchanged");
+            return;
+        }
+
+        // On macOS the kernel rejects raising rlim_cur above
+        // kern.maxfilesperproc, even though rlim_max may report
+        // RLIM_INFINITY, so clamp to it.
+        #[cfg(target_os = "macos")]
+        {
+            let mut maxfilesperproc: libc::c_int = 0;
+            let mut size = std::mem::size_of::<libc::c_int>();
+            let mut mib = [libc::CTL_KERN, libc::KERN_MAXFILESPERPROC];
+            let ret = libc::sysctl(
+                mib.as_mut_ptr(),
+                mib.len() as libc::c_uint,
+                &mut maxfilesperproc as *mut _ as *mut _,
+                &mut size,
+                std::ptr::null_mut(),
+                0,
+            );
+            if ret == 0 {
+                limits.rlim_max = limits.rlim_max.min(maxfilesperproc as libc::rlim_t);
+            }
+        }
+
+        if limits.rlim_cur >= limits.rlim_max {
+            return;
+        }
+
+        let old_cur = limits.rlim_cur;
+        limits.rlim_cur = limits.rlim_max;
+        if libc::setrlimit(libc::RLIMIT_NOFILE, &limits) != 0 {
+            warn!(
+                "Failed to raise RLIMIT_NOFILE from {} to {}",
+                old_cur, limits.rlim_max
+            );
+        } else {
+            debug!(
+                "Raised RLIMIT_NOFILE soft limit from {} to {}",
+                old_cur, limits.rlim_cur
+            );
+        }
+    }
+}
+
+#[cfg(not(unix))]
+pub fn raise_fd_limit() {}
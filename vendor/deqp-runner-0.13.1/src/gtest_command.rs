@@ -1,9 +1,14 @@
 use crate::parse_deqp::{DeqpStatus, DeqpTestResult};
 use crate::runner_results::*;
-use crate::{runner_thread_index, TestCase, TestCommand, TestConfiguration};
+use crate::{
+    load_shard_timings, parse_regex_set, runner_thread_index, select_tests, shard_tests,
+    CommandLineRunOptions, SubRunConfig, TestCase, TestCommand, TestConfiguration,
+};
 use anyhow::{Context, Result};
 use log::*;
 use regex::Regex;
+use serde::Deserialize;
+use std::collections::HashMap;
 use std::fs::File;
 use std::io::prelude::*;
 use std::io::BufReader;
@@ -16,6 +21,95 @@ pub struct GTestCommand {
     pub bin: PathBuf,
     pub config: TestConfiguration,
     pub args: Vec<String>,
+
+    // Parse results from gtest's --gtest_output=json report instead of
+    // scraping [ RUN ]/[ OK ] lines from stdout, for full failure text and
+    // less fragile status/crash attribution. See parse_gtest_json_report.
+    pub json_output: bool,
+
+    // When a leak or a crash taints a batch run (see the LeakSanitizer
+    // handling and the missing-results case below), re-run the affected
+    // caselist one test at a time with a single-test --gtest_filter to pin
+    // the leak/crash to its actual culprit instead of tainting (or losing
+    // track of) every test in the batch. See GTestCommand::isolate_rerun.
+    pub isolate_crashes: bool,
+}
+
+// Cap on the number of individual --gtest_filter re-runs a single
+// isolate_crashes pass will perform, so a caselist that's entirely failing
+// or leaking doesn't turn into one process spawn per test.
+const MAX_ISOLATE_RERUNS: usize = 64;
+
+// Common structure for configuring a gtest run as part of a deqp-runner Suite.
+#[derive(Deserialize)]
+pub struct GtestTomlConfig {
+    pub bin: PathBuf,
+
+    #[serde(flatten)]
+    pub sub_config: SubRunConfig,
+
+    #[serde(default = "default_tests_per_group")]
+    pub tests_per_group: usize,
+
+    #[serde(default)]
+    pub min_tests_per_group: usize,
+
+    #[serde(default)]
+    pub gtest_args: Vec<String>,
+
+    #[serde(default)]
+    pub json_output: bool,
+
+    #[serde(default)]
+    pub isolate_crashes: bool,
+}
+
+fn default_tests_per_group() -> usize {
+    500
+}
+
+impl GtestTomlConfig {
+    pub fn gtest(&self, run: &CommandLineRunOptions) -> Result<GTestCommand> {
+        Ok(GTestCommand {
+            bin: self.bin.clone(),
+            config: TestConfiguration::from_suite_config(run, &self.sub_config)?,
+            args: self.gtest_args.clone(),
+            json_output: self.json_output,
+            isolate_crashes: self.isolate_crashes,
+        })
+    }
+
+    pub fn test_groups<'d>(
+        &self,
+        gtest: &'d GTestCommand,
+        filters: &[String],
+    ) -> Result<Vec<(&'d dyn TestCommand, Vec<TestCase>)>> {
+        let mut include_filters = Vec::new();
+        if !self.sub_config.include.is_empty() {
+            include_filters.push(
+                parse_regex_set(&self.sub_config.include).context("compiling include filters")?,
+            );
+        }
+        if !filters.is_empty() {
+            include_filters.push(parse_regex_set(filters).context("compiling include filters")?);
+        }
+
+        let shard_timings = load_shard_timings(&self.sub_config)?;
+        let tests = shard_tests(
+            gtest.list_tests()?,
+            self.sub_config.shard,
+            shard_timings.as_ref(),
+        );
+        let tests = select_tests(tests, &self.sub_config, &include_filters)?;
+
+        println!(
+            "Running {} gtest tests on {} threads",
+            tests.len(),
+            rayon::current_num_threads()
+        );
+
+        gtest.split_tests_to_groups(tests, self.tests_per_group, self.min_tests_per_group)
+    }
 }
 
 impl DeqpStatus {
@@ -129,6 +223,78 @@ pub fn parse_gtest_results_with_timeout(
     parse_gtest_results(TimeoutReader::new(gtest_output, timeout))
 }
 
+// Structure of a --gtest_output=json:<path> report.
+#[derive(Deserialize)]
+struct GtestJsonReport {
+    testsuites: Vec<GtestJsonSuite>,
+}
+
+#[derive(Deserialize)]
+struct GtestJsonSuite {
+    testsuite: Vec<GtestJsonCase>,
+}
+
+#[derive(Deserialize)]
+struct GtestJsonCase {
+    name: String,
+    classname: String,
+    time: String,
+    status: String,
+    #[serde(default)]
+    failures: Vec<GtestJsonFailure>,
+}
+
+#[derive(Deserialize)]
+struct GtestJsonFailure {
+    failure: String,
+}
+
+// Parses gtest's --gtest_output=json:<path> report, a structured alternative
+// to scraping [ RUN ]/[ OK ] lines from stdout with parse_gtest_results().
+// Each case's `classname` + `name` are joined into the same dotted test name
+// used everywhere else; NOTRUN cases are NotSupported, cases with any
+// `failures` entry are Fail, and everything else is Pass. Failure messages
+// are captured into GTestResults::stdout (in place of the raw process
+// stdout) so the saved log focuses on what actually went wrong.
+pub fn parse_gtest_json_report(report: &str) -> Result<GTestResults> {
+    let report: GtestJsonReport =
+        serde_json::from_str(report).context("parsing gtest JSON report")?;
+
+    let mut results = GTestResults::new();
+    for suite in report.testsuites {
+        for case in suite.testsuite {
+            let name = format!("{}.{}", case.classname, case.name);
+
+            let status = if case.status == "NOTRUN" {
+                DeqpStatus::NotSupported
+            } else if !case.failures.is_empty() {
+                DeqpStatus::Fail
+            } else {
+                DeqpStatus::Pass
+            };
+
+            if !case.failures.is_empty() {
+                results.stdout.push(format!("{}:", name));
+                for failure in &case.failures {
+                    results.stdout.extend(failure.failure.lines().map(String::from));
+                }
+            }
+
+            let duration = Duration::from_secs_f32(case.time.parse().with_context(|| {
+                format!("parsing gtest JSON report time '{}' for {}", case.time, name)
+            })?);
+
+            results.results.push(DeqpTestResult {
+                name,
+                status,
+                duration,
+            });
+        }
+    }
+
+    Ok(results)
+}
+
 impl GTestCommand {
     pub fn list_tests(&self) -> Result<Vec<TestCase>> {
         let output = Command::new(&self.bin)
@@ -153,6 +319,48 @@ impl GTestCommand {
             std::str::from_utf8(&output.stdout).context("Parsing gtest output as UTF8")?,
         )
     }
+
+    // Re-runs each of `tests` alone (a single-test --gtest_filter), so a
+    // leak or crash seen across a batch run can be pinned to its actual
+    // culprit instead of tainting (or losing track of) the whole caselist.
+    // Capped at MAX_ISOLATE_RERUNS per call.
+    fn isolate_rerun(&self, tests: &[&TestCase]) -> Result<Vec<DeqpTestResult>> {
+        tests
+            .iter()
+            .take(MAX_ISOLATE_RERUNS)
+            .map(|test| self.run_single_test(test))
+            .collect()
+    }
+
+    fn run_single_test(&self, test: &TestCase) -> Result<DeqpTestResult> {
+        let mut command = Command::new(&self.bin);
+        command
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .stdin(Stdio::null())
+            .env("DEQP_RUNNER_THREAD", runner_thread_index()?.to_string())
+            .args(&self.args)
+            .arg(format!("--gtest_filter={}", test.name()));
+        crate::set_process_group(&mut command);
+
+        let mut child = command
+            .spawn()
+            .with_context(|| format!("Failed to spawn {}", &self.bin.display()))?;
+
+        let stdout = child.stdout.take().context("opening stdout")?;
+        let result = parse_gtest_results_with_timeout(stdout, self.config.timeout);
+
+        crate::kill_child_process_group(&mut child);
+        let _ = child.wait();
+
+        let GTestResults { results, .. } = result.context("parsing isolated results")?;
+
+        Ok(results.into_iter().next().unwrap_or(DeqpTestResult {
+            name: test.name().to_owned(),
+            status: DeqpStatus::Crash,
+            duration: Duration::new(0, 0),
+        }))
+    }
 }
 
 impl TestCommand for GTestCommand {
@@ -173,6 +381,15 @@ impl TestCommand for GTestCommand {
             tests_arg.push_str(test.name());
         }
 
+        let json_output_path = if self.json_output {
+            Some(
+                self.caselist_file_path(caselist_state, "gtest.json")
+                    .context("gtest json output path")?,
+            )
+        } else {
+            None
+        };
+
         let mut command = Command::new(&self.bin);
         command
             .stdout(Stdio::piped())
@@ -181,6 +398,10 @@ impl TestCommand for GTestCommand {
             .env("DEQP_RUNNER_THREAD", runner_thread_index()?.to_string())
             .args(&self.args)
             .arg(tests_arg);
+        if let Some(path) = &json_output_path {
+            command.arg(format!("--gtest_output=json:{}", path.display()));
+        }
+        crate::set_process_group(&mut command);
 
         let command_line = format!("{:?}", command);
 
@@ -191,23 +412,54 @@ impl TestCommand for GTestCommand {
         let stdout = child.stdout.take().context("opening stdout")?;
         let gtest_results = parse_gtest_results_with_timeout(stdout, self.config.timeout);
 
-        // The child should have run to completion based on parse_gtest_results() consuming its output,
-        // but if we had a timeout or parse failure then we want to kill this run.
-        let _ = child.kill();
+        // The child should have run to completion based on parse_gtest_results() consuming its
+        // output, but if we had a timeout or parse failure then we want to kill this run,
+        // process group and all, so any helper process it forked doesn't outlive it.
+        crate::kill_child_process_group(&mut child);
 
         // Make sure we reap the child process.
         let status = child.wait().context("waiting for child")?;
 
         let GTestResults {
             results: mut gtest_results,
-            stdout,
+            stdout: mut stdout,
         } = gtest_results.context("parsing results")?;
 
-        let stderr: Vec<String> = BufReader::new(child.stderr.as_mut().context("opening stderr")?)
-            .lines()
-            .flatten()
-            .collect();
+        // Prefer the structured JSON report over the stdout-scraped results
+        // when it's there: it has full failure text and isn't fooled by
+        // unusual console output. A missing or truncated file means the
+        // process crashed or timed out before writing it, so we keep
+        // whatever the stdout scrape above already attributed to the
+        // in-progress test.
+        if let Some(path) = &json_output_path {
+            match std::fs::read_to_string(path)
+                .context("reading --gtest_output=json report")
+                .and_then(|json| parse_gtest_json_report(&json))
+            {
+                Ok(json_results) => {
+                    gtest_results = json_results.results;
+                    stdout = json_results.stdout;
+                }
+                Err(e) => warn!(
+                    "Falling back to stdout-scraped gtest results for {}: {:?}",
+                    self.bin.display(),
+                    e
+                ),
+            }
+        }
 
+        let (stderr, stderr_truncated) = crate::read_bounded_lines(
+            child.stderr.as_mut().context("opening stderr")?,
+            crate::CAPTURE_BYTE_LIMIT,
+        );
+        if stderr_truncated {
+            warn!(
+                "stderr for {} exceeded the capture limit and was truncated",
+                self.bin.display()
+            );
+        }
+
+        let mut leak_detected = false;
         for line in &stderr {
             // If the driver has ASan enabled and it detected leaks, then mark
             // all the tests in the caselist as failed (since we don't know who
@@ -217,6 +469,7 @@ impl TestCommand for GTestCommand {
                     "gtest-runner: Leak detected, marking caselist as failed ({})",
                     self.see_more("", caselist_state)
                 );
+                leak_detected = true;
                 for result in gtest_results.iter_mut() {
                     result.status = DeqpStatus::Fail;
                 }
@@ -224,6 +477,40 @@ impl TestCommand for GTestCommand {
             error!("gtest error: {}", line);
         }
 
+        // A leak taints every test in the batch above, and a crash only
+        // leaves the process's current test attributed (the rest of the
+        // caselist simply has no result at all). With isolate_crashes, redo
+        // the affected tests one at a time so the leak/crash lands on its
+        // actual culprit and the others keep their real Pass/Skip result.
+        if self.isolate_crashes && (leak_detected || gtest_results.len() < tests.len()) {
+            let isolate_count = tests.len().min(MAX_ISOLATE_RERUNS);
+            warn!(
+                "gtest-runner: isolating {} test(s) one at a time to pin the leak/crash ({})",
+                isolate_count,
+                self.see_more("", caselist_state)
+            );
+
+            let isolated = self
+                .isolate_rerun(&tests[..isolate_count])
+                .context("isolating leak/crash")?;
+
+            let mut index_by_name: HashMap<String, usize> = gtest_results
+                .iter()
+                .enumerate()
+                .map(|(i, result)| (result.name.clone(), i))
+                .collect();
+
+            for result in isolated {
+                match index_by_name.get(&result.name) {
+                    Some(&i) => gtest_results[i] = result,
+                    None => {
+                        index_by_name.insert(result.name.clone(), gtest_results.len());
+                        gtest_results.push(result);
+                    }
+                }
+            }
+        }
+
         let mut save_log = false;
         let mut results: Vec<RunnerResult> = Vec::new();
         for result in gtest_results {
@@ -238,6 +525,7 @@ impl TestCommand for GTestCommand {
                 status,
                 duration: result.duration.as_secs_f32(),
                 subtest: false,
+                flake_retries: 0,
             });
         }
 
@@ -0,0 +1,267 @@
+use crate::parse_deqp::{DeqpStatus, DeqpTestResult};
+use crate::runner_results::*;
+use crate::{
+    load_shard_timings, parse_regex_set, read_lines, runner_thread_index, select_tests,
+    shard_tests, CommandLineRunOptions, SubRunConfig, TestCase, TestCommand, TestConfiguration,
+};
+use anyhow::{Context, Result};
+use log::*;
+use regex::Regex;
+use serde::Deserialize;
+use std::io::prelude::*;
+use std::io::BufReader;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::time::Instant;
+use timeout_readwrite::TimeoutReader;
+
+pub struct IgtCommand {
+    pub igt_folder: PathBuf,
+
+    // Extra arguments passed ahead of --run-subtest, e.g. to select the
+    // mock-igt mode of a shared binary in tests (see GTestCommand::args).
+    pub args: Vec<String>,
+
+    pub config: TestConfiguration,
+}
+
+// An IGT test is a single subtest of one of the small per-feature binaries
+// that make up intel-gpu-tools, so (unlike dEQP/gtest) each TestCase needs to
+// know which binary it came from in addition to its own name.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct IgtTest {
+    pub name: String,
+    pub binary: String,
+    pub subtest: String,
+}
+
+impl DeqpStatus {
+    pub fn from_igt_str(input: &str) -> Result<DeqpStatus> {
+        match input {
+            "SUCCESS" => Ok(DeqpStatus::Pass),
+            "FAIL" => Ok(DeqpStatus::Fail),
+            "SKIP" => Ok(DeqpStatus::NotSupported),
+            "CRASH" => Ok(DeqpStatus::Crash),
+            "TIMEOUT" => Ok(DeqpStatus::Timeout),
+            _ => anyhow::bail!("unknown igt status '{}'", input),
+        }
+    }
+}
+
+// Parses a caselist file of "binary@subtest" lines (the convention used by
+// igt_runner's own resume files) into the (binary, subtest) pairs we need to
+// invoke each test.
+pub fn parse_igt_caselist(lines: &[String], prefix: &str) -> Result<Vec<TestCase>> {
+    lines
+        .iter()
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            let (binary, subtest) = line
+                .split_once('@')
+                .with_context(|| format!("igt caselist line '{}' missing '@'", line))?;
+
+            Ok(TestCase::Igt(IgtTest {
+                name: format!("{}{}", prefix, line),
+                binary: binary.to_owned(),
+                subtest: subtest.to_owned(),
+            }))
+        })
+        .collect()
+}
+
+// Parses the "Starting subtest: x" / "Subtest x: SUCCESS (0.123s)" lines that
+// igt_core prints to stdout for a single-subtest invocation.
+pub fn parse_igt_results(igt_output: impl Read, subtest: &str) -> Result<DeqpTestResult> {
+    lazy_static! {
+        static ref RESULT_RE: Regex =
+            Regex::new(r#"^Subtest (\S+): (\S+)"#).unwrap();
+    }
+
+    let igt_output = BufReader::new(igt_output);
+    let start = Instant::now();
+
+    for line in igt_output.lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(e) => {
+                if let std::io::ErrorKind::TimedOut = e.kind() {
+                    return Ok(DeqpTestResult {
+                        name: subtest.to_owned(),
+                        status: DeqpStatus::Timeout,
+                        duration: start.elapsed(),
+                    });
+                }
+                return Err(e).context("Reading from igt test");
+            }
+        };
+
+        if let Some(cap) = RESULT_RE.captures(&line) {
+            if &cap[1] == subtest {
+                return Ok(DeqpTestResult {
+                    name: subtest.to_owned(),
+                    status: DeqpStatus::from_igt_str(&cap[2])?,
+                    duration: start.elapsed(),
+                });
+            }
+        }
+    }
+
+    // The binary exited without ever reporting a result for our subtest
+    // (most likely it crashed partway through).
+    Ok(DeqpTestResult {
+        name: subtest.to_owned(),
+        status: DeqpStatus::Crash,
+        duration: start.elapsed(),
+    })
+}
+
+impl TestCommand for IgtCommand {
+    fn run(
+        &self,
+        caselist_state: &CaselistState,
+        tests: &[&TestCase],
+    ) -> Result<Vec<RunnerResult>> {
+        let test = match tests {
+            [test] => match test {
+                TestCase::Igt(test) => test,
+                _ => anyhow::bail!("igt-runner can only run TestCase::Igt tests"),
+            },
+            _ => anyhow::bail!("igt-runner only supports one test per invocation"),
+        };
+
+        let bin_path = self.igt_folder.join(&test.binary);
+
+        let mut command = Command::new(&bin_path);
+        command
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .stdin(Stdio::null())
+            .env("DEQP_RUNNER_THREAD", runner_thread_index()?.to_string())
+            .args(&self.args)
+            .arg("--run-subtest")
+            .arg(&test.subtest);
+        crate::set_process_group(&mut command);
+
+        let command_line = format!("{:?}", command);
+
+        let mut child = command
+            .spawn()
+            .with_context(|| format!("Failed to spawn {}", bin_path.display()))?;
+
+        let stdout = child.stdout.take().context("opening stdout")?;
+        let igt_result = parse_igt_results(
+            TimeoutReader::new(stdout, self.config.timeout),
+            &test.subtest,
+        );
+
+        // The child should have exited once parse_igt_results() got its
+        // result line, but if we hit a timeout or parse failure then make
+        // sure we don't leave it (or any subtest helper process it forked)
+        // running.
+        crate::kill_child_process_group(&mut child);
+        let status = child.wait().context("waiting for child")?;
+
+        let igt_result = igt_result.context("parsing results")?;
+
+        let (stderr, stderr_truncated) = crate::read_bounded_lines(
+            child.stderr.as_mut().context("opening stderr")?,
+            crate::CAPTURE_BYTE_LIMIT,
+        );
+        if stderr_truncated {
+            warn!(
+                "stderr for {} exceeded the capture limit and was truncated",
+                test.subtest
+            );
+        }
+
+        for line in &stderr {
+            error!("igt error: {}", line);
+        }
+
+        let result_status = self.translate_result(&igt_result, caselist_state);
+
+        if result_status.should_save_logs(self.config.save_xfail_logs) {
+            let log_path = self
+                .caselist_file_path(caselist_state, "log")
+                .context("log path")?;
+            let mut file = std::fs::File::create(log_path).context("opening log file")?;
+            writeln!(file, "command: {}", command_line)?;
+            writeln!(file, "exit status: {}", status)?;
+            writeln!(file, "stderr:")?;
+            for line in &stderr {
+                writeln!(file, "{}", line)?;
+            }
+        }
+
+        Ok(vec![RunnerResult {
+            test: test.name.clone(),
+            status: result_status,
+            duration: igt_result.duration.as_secs_f32(),
+            subtest: false,
+            flake_retries: 0,
+        }])
+    }
+
+    fn config(&self) -> &TestConfiguration {
+        &self.config
+    }
+}
+
+// Common structure for configuring an igt run as part of a deqp-runner Suite.
+#[derive(Deserialize)]
+pub struct IgtTomlConfig {
+    pub igt_folder: PathBuf,
+
+    pub caselists: Vec<PathBuf>,
+
+    #[serde(default)]
+    pub igt_args: Vec<String>,
+
+    #[serde(flatten)]
+    pub sub_config: SubRunConfig,
+
+    #[serde(default)]
+    pub prefix: String,
+}
+
+impl IgtTomlConfig {
+    pub fn igt(&self, run: &CommandLineRunOptions) -> Result<IgtCommand> {
+        Ok(IgtCommand {
+            igt_folder: self.igt_folder.clone(),
+            args: self.igt_args.clone(),
+            config: TestConfiguration::from_suite_config(run, &self.sub_config)?,
+        })
+    }
+
+    pub fn test_groups<'d>(
+        &self,
+        igt: &'d IgtCommand,
+        filters: &[String],
+    ) -> Result<Vec<(&'d dyn TestCommand, Vec<TestCase>)>> {
+        let mut include_filters = Vec::new();
+        if !self.sub_config.include.is_empty() {
+            include_filters.push(
+                parse_regex_set(&self.sub_config.include).context("compiling include filters")?,
+            );
+        }
+        if !filters.is_empty() {
+            include_filters.push(parse_regex_set(filters).context("compiling include filters")?);
+        }
+
+        let tests = parse_igt_caselist(&read_lines(&self.caselists)?, &self.prefix)?;
+        let shard_timings = load_shard_timings(&self.sub_config)?;
+        let tests = shard_tests(tests, self.sub_config.shard, shard_timings.as_ref());
+        let tests = select_tests(tests, &self.sub_config, &include_filters)?;
+
+        println!(
+            "Running {} igt tests on {} threads",
+            tests.len(),
+            rayon::current_num_threads()
+        );
+
+        // Each igt binary only runs a single subtest per invocation (so that
+        // a crash in one subtest can't take down the rest of its group), so
+        // groups are always of size 1, same as piglit.
+        igt.split_tests_to_groups(tests, 1, 1)
+    }
+}
@@ -1,37 +1,49 @@
 #[macro_use]
 extern crate lazy_static;
 pub mod deqp_command;
+mod fd_limit;
 pub mod gtest_command;
+pub mod igt_command;
+pub mod lock;
 pub mod mock_deqp;
 pub mod mock_gtest;
+pub mod mock_igt;
 pub mod mock_piglit;
 mod parse_deqp;
 pub mod parse_piglit;
 pub mod piglit_command;
+pub mod result_cache;
 mod runner_results;
+pub mod trace;
 
 use anyhow::bail;
 pub use runner_results::*;
 
 use anyhow::{Context, Result};
+use igt_command::*;
 use log::*;
-use parse_deqp::DeqpTestResult;
+use parse_deqp::{DeqpStatus, DeqpTestResult};
 use piglit_command::*;
-use rand::rngs::StdRng;
+use rand::rngs::SmallRng;
 use rand::seq::SliceRandom;
 use rand::SeedableRng;
 use rayon::prelude::*;
 use regex::RegexSet;
+use result_cache::{CachedResult, FilesystemResultCache, ResultCacheStore};
 use serde::Deserialize;
 use std::collections::HashMap;
 use std::fs::File;
 use std::io::prelude::*;
 use std::io::BufReader;
 use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc::{channel, Receiver};
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 use std::time::Instant;
 use structopt::StructOpt;
+use trace::Profiler;
 
 pub fn parse_key_val<T, U>(s: &str) -> Result<(T, U), Box<dyn std::error::Error>>
 where
@@ -46,6 +58,23 @@ where
     Ok((s[..pos].parse()?, s[pos + 1..].parse()?))
 }
 
+// Parses a `--shard` value of the form "M/N" into its 1-indexed shard index
+// and total shard count.
+fn parse_shard(s: &str) -> Result<(usize, usize), Box<dyn std::error::Error>> {
+    let pos = s
+        .find('/')
+        .ok_or_else(|| format!("--shard must be of the form M/N, got '{}'", s))?;
+    let m: usize = s[..pos].parse()?;
+    let n: usize = s[pos + 1..].parse()?;
+    if n == 0 {
+        return Err(format!("--shard N must be >= 1, got '{}'", s).into());
+    }
+    if m < 1 || m > n {
+        return Err(format!("--shard M must be between 1 and N ({}), got '{}'", n, s).into());
+    }
+    Ok((m, n))
+}
+
 // Cross test-type CLI/toml options
 #[derive(Clone, Debug, Deserialize, StructOpt)]
 pub struct SubRunConfig {
@@ -105,6 +134,76 @@ pub struct SubRunConfig {
     )]
     #[serde(with = "tuple_vec_map", default)]
     pub env: Vec<(String, String)>,
+
+    #[structopt(
+        long = "flake-retries",
+        default_value = "0",
+        help = "If a test fails, crashes, or times out, rerun it alone up to N more times and reclassify it as Flake (rather than a stable failure) unless all N+1 attempts agree on the same status"
+    )]
+    #[serde(default)]
+    pub flake_retries: u32,
+
+    #[structopt(
+        long,
+        help = "When --flake-retries reclassifies a test as Flake, append its name to this file (creating it if needed), so future runs pick it up as a known flake via --flakes"
+    )]
+    #[serde(default)]
+    pub record_flakes: Option<PathBuf>,
+
+    #[structopt(
+        long,
+        help = "Shuffle the test order with a seeded Fisher-Yates shuffle before grouping, to spread expensive tests across bins and surface order-dependent flakes. A seed is picked from the clock (and printed for reproducibility) unless --shuffle-seed is also given."
+    )]
+    #[serde(default)]
+    pub shuffle: bool,
+
+    #[structopt(long, help = "Seed to use for --shuffle, as a u64 (implies --shuffle)")]
+    #[serde(default)]
+    pub shuffle_seed: Option<u64>,
+
+    #[structopt(
+        long,
+        parse(try_from_str = parse_shard),
+        help = "Split the caselist into N shards and run only shard M of them, as \"M/N\" (1-indexed), so a large caselist can be split across machines. Cases are assigned by a greedy longest-processing-time bin-pack over --shard-timings if given, else by a stable hash of the case name"
+    )]
+    #[serde(default)]
+    pub shard: Option<(usize, usize)>,
+
+    #[structopt(
+        long,
+        help = "Path to a --timings-output file from a prior run, used to load-balance --shard assignment so each shard takes about the same wall clock instead of just splitting the caselist evenly"
+    )]
+    #[serde(default)]
+    pub shard_timings: Option<PathBuf>,
+
+    #[structopt(
+        long,
+        help = "Directory to cache stable pass results in, keyed by test name + --environment-fingerprint, so unchanged tests can be skipped on a later run instead of re-spawned"
+    )]
+    #[serde(default)]
+    pub result_cache_dir: Option<PathBuf>,
+
+    #[structopt(
+        long,
+        help = "When a caselist comes back with fewer results than tests (a crash or leak we can't attribute to a specific test), re-run it in halves, recursing into whichever half reproduces the failure, until a single culprit test is isolated"
+    )]
+    #[serde(default)]
+    pub bisect_crashes: bool,
+
+    #[structopt(
+        long,
+        default_value = "",
+        help = "Fingerprint (driver version, GPU PCI id, build hash, etc.) mixed into the --result-cache-dir cache key, so a cached result is only reused for the exact environment that produced it"
+    )]
+    #[serde(default)]
+    pub environment_fingerprint: String,
+
+    #[structopt(
+        long,
+        help = "Path to a results.csv/failures.csv from a prior run; restrict this run to exactly the Fail/Crash/Timeout/Flake test names it contains, bypassing --fraction/--include-tests (--skips is still honored), so a broken subset can be reproduced standalone"
+    )]
+    #[serde(default)]
+    pub replay: Option<PathBuf>,
 }
 
 impl SubRunConfig {
@@ -143,6 +242,46 @@ impl SubRunConfig {
         for (var, data) in &top.env {
             self.env.push((var.to_owned(), data.to_owned()));
         }
+
+        if self.flake_retries == 0 {
+            self.flake_retries = top.flake_retries;
+        }
+
+        if self.record_flakes.is_none() {
+            self.record_flakes = top.record_flakes.clone();
+        }
+
+        if !self.shuffle {
+            self.shuffle = top.shuffle;
+        }
+        if self.shuffle_seed.is_none() {
+            self.shuffle_seed = top.shuffle_seed;
+        }
+        if self.shard.is_none() {
+            self.shard = top.shard;
+        }
+        if self.shard_timings.is_none() {
+            self.shard_timings = top.shard_timings.clone();
+        }
+
+        if self.result_cache_dir.is_none() {
+            self.result_cache_dir = top.result_cache_dir.clone();
+        }
+        if self.environment_fingerprint.is_empty() {
+            self.environment_fingerprint = top.environment_fingerprint.clone();
+        }
+
+        if !self.bisect_crashes {
+            self.bisect_crashes = top.bisect_crashes;
+        }
+
+        if let Some(run_replay) = top.replay.as_ref() {
+            if self.replay.is_some() {
+                eprintln!("replay may only be set on either the command line or per-deqp.");
+                std::process::exit(1);
+            }
+            self.replay = Some(run_replay.clone());
+        }
     }
 }
 
@@ -187,6 +326,73 @@ pub struct CommandLineRunOptions {
         help = "Saves log files for expected failures along with new ones"
     )]
     pub save_xfail_logs: bool,
+
+    #[structopt(
+        long,
+        help = "Path to also write a JUnit XML report of the run to, for CI systems that ingest JUnit (one <testcase> per result, including passes)"
+    )]
+    pub junit_xml: Option<PathBuf>,
+
+    #[structopt(
+        long,
+        help = "Path to write a newline-delimited JSON event stream to as results complete, for dashboards that want to tail a long run instead of waiting on results.csv"
+    )]
+    pub results_json: Option<PathBuf>,
+
+    #[structopt(
+        long,
+        help = "Path to write a test,duration_ms CSV of this run's timings to, for a later run's \"compare-timings\" to check for perf regressions"
+    )]
+    pub timings_output: Option<PathBuf>,
+
+    #[structopt(
+        long,
+        help = "Write the --results-json event stream to stdout instead of a file"
+    )]
+    pub ndjson: bool,
+
+    #[structopt(
+        long,
+        help = "Disable the live progress status line (it's always a plain, infrequent line instead of carriage-return updates when stdout isn't a terminal)"
+    )]
+    pub no_progress: bool,
+
+    #[structopt(
+        long,
+        help = "Path to write a chrome://tracing/Perfetto-compatible JSON trace of the run's scheduling and per-group timing to, for diagnosing tail latency and tuning tests_per_group"
+    )]
+    pub profile: Option<PathBuf>,
+
+    #[structopt(
+        long,
+        help = "Print and write (to output/diff.json) a triage-friendly regressions/fixes/flakes delta against --baseline, instead of just pass/fail counts"
+    )]
+    pub diff: bool,
+
+    #[structopt(
+        long,
+        default_value = "0",
+        help = "Report non-subtest results slower than this many seconds in a dedicated summary section (0 = disabled)"
+    )]
+    pub slow_test_secs: f32,
+
+    #[structopt(
+        long,
+        help = "Stop dispatching new test groups once this many Fail/Crash/Timeout results have accumulated (in-flight groups still finish), so a broken driver doesn't burn the whole run"
+    )]
+    pub fail_fast: Option<u32>,
+
+    #[structopt(
+        long,
+        help = "Exit with a failing status if any test was reclassified as Flake (by --flake-retries or a --flakes match), instead of treating flakes as non-fatal"
+    )]
+    pub fail_on_flake: bool,
+
+    #[structopt(
+        long,
+        help = "After the initial run, watch the test binary, caselist files, and skips/flakes files for changes and automatically re-run instead of exiting (a caselist change re-runs the full suite, any other change re-runs just the previous failures); a failing run is not treated as fatal while watching. Currently only supported by the deqp binary's run subcommand"
+    )]
+    pub watch: bool,
 }
 
 impl CommandLineRunOptions {
@@ -198,6 +404,8 @@ impl CommandLineRunOptions {
             .init()
             .unwrap();
 
+        fd_limit::raise_fd_limit();
+
         if self.jobs > 0 {
             rayon::ThreadPoolBuilder::new()
                 .num_threads(self.jobs)
@@ -213,8 +421,16 @@ impl CommandLineRunOptions {
             eprintln!("--fraction_start must be >= 1.");
             std::process::exit(1);
         }
+        if let Some((m, n)) = self.sub_config.shard {
+            if n == 0 || m < 1 || m > n {
+                eprintln!("--shard must be \"M/N\" with 1 <= M <= N.");
+                std::process::exit(1);
+            }
+        }
 
         std::fs::create_dir_all(&self.output_dir).context("creating output directory")?;
+        crate::lock::lock_output_dir(&self.output_dir)
+            .context("locking output directory (is another run already using it?)")?;
 
         Ok(())
     }
@@ -231,6 +447,19 @@ impl CommandLineRunOptions {
         parse_regex_set(read_lines(&self.sub_config.flakes)?).context("compiling flakes regexes")
     }
 
+    // Builds the writer for --results-json/--ndjson, if either was requested.
+    pub fn ndjson_writer(&self) -> Result<Option<Box<dyn Write + Send>>> {
+        if self.ndjson {
+            Ok(Some(Box::new(std::io::stdout())))
+        } else if let Some(path) = &self.results_json {
+            Ok(Some(Box::new(
+                File::create(path).context("creating --results-json file")?,
+            )))
+        } else {
+            Ok(None)
+        }
+    }
+
     pub fn includes_regex(&self) -> Result<RegexSet> {
         if self.sub_config.include.is_empty() {
             RegexSet::new(vec![""]).context("compiling all-tests include RE")
@@ -249,6 +478,36 @@ pub struct TestConfiguration {
     pub timeout: Duration,
     pub env: HashMap<String, String>,
     pub save_xfail_logs: bool,
+    pub flake_retries: u32,
+    /// File to append newly-detected `--flake-retries` flakes to, for a
+    /// later run's `--flakes` to pick up. `None` disables recording.
+    pub record_flakes: Option<Arc<Mutex<File>>>,
+    /// Seed for the Fisher-Yates shuffle `split_tests_to_groups` applies to
+    /// the test list before grouping. `None` means don't shuffle at all.
+    pub shuffle_seed: Option<u64>,
+    /// Store to skip re-running a test whose last recorded result (for this
+    /// `environment_fingerprint`) was a stable pass. `None` disables result
+    /// caching entirely.
+    pub result_cache: Option<Arc<dyn ResultCacheStore>>,
+    /// Mixed into the result cache key alongside the test name (see
+    /// `result_cache::cache_key`), so a cached result is only reused for the
+    /// exact driver/GPU/build combination that produced it.
+    pub environment_fingerprint: String,
+    /// Whether to bisect a caselist that comes back with fewer results than
+    /// tests (see `TestCommand::bisect_caselist`) to attribute the crash/leak
+    /// to a single test instead of leaving the whole caselist unaccounted for.
+    pub bisect_crashes: bool,
+}
+
+// Picks a shuffle seed from the clock when --shuffle is given without an
+// explicit --shuffle-seed, so the run is still reproducible if it's printed.
+fn random_seed_from_clock() -> u64 {
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or(0)
 }
 
 impl TestConfiguration {
@@ -270,8 +529,163 @@ impl TestConfiguration {
             timeout: Duration::from_secs_f32(sub_config.timeout),
             env: sub_config.env.iter().cloned().collect(),
             save_xfail_logs: run.save_xfail_logs,
+            flake_retries: sub_config.flake_retries,
+            record_flakes: sub_config
+                .record_flakes
+                .as_ref()
+                .map(|path| -> Result<Arc<Mutex<File>>> {
+                    Ok(Arc::new(Mutex::new(
+                        std::fs::OpenOptions::new()
+                            .create(true)
+                            .append(true)
+                            .open(path)?,
+                    )))
+                })
+                .transpose()
+                .context("opening --record-flakes file")?,
+            shuffle_seed: if sub_config.shuffle || sub_config.shuffle_seed.is_some() {
+                Some(sub_config.shuffle_seed.unwrap_or_else(|| {
+                    let seed = random_seed_from_clock();
+                    println!(
+                        "No --shuffle-seed given; shuffling with seed {} (pass --shuffle-seed {} to reproduce this test order)",
+                        seed, seed
+                    );
+                    seed
+                }))
+            } else {
+                None
+            },
+            result_cache: sub_config
+                .result_cache_dir
+                .as_ref()
+                .map(|dir| -> Result<Arc<dyn ResultCacheStore>> {
+                    Ok(Arc::new(FilesystemResultCache::new(dir)?))
+                })
+                .transpose()
+                .context("setting up --result-cache-dir")?,
+            environment_fingerprint: sub_config.environment_fingerprint.clone(),
+            bisect_crashes: sub_config.bisect_crashes,
+        })
+    }
+}
+
+// Splits `tests` into shard `m` of `n` (1-indexed). If `timings` (see
+// --shard-timings / read_timings) has durations for these cases, they're
+// assigned by a greedy longest-processing-time bin-pack: sort cases by
+// duration descending, then drop each one into whichever shard currently has
+// the least accumulated time, so all shards finish in about the same wall
+// clock instead of being skewed by a run of expensive tests landing
+// together. Cases missing from `timings` (or when no timings are given at
+// all) are assigned by a stable hash of the case name instead, so shard
+// membership is deterministic and reproducible across machines without
+// having to share a seed. Must run before --fraction/--include-tests, on the
+// full caselist, so shard assignment doesn't shift if those are also applied.
+pub fn shard_tests(
+    tests: Vec<TestCase>,
+    shard: Option<(usize, usize)>,
+    timings: Option<&HashMap<String, f32>>,
+) -> Vec<TestCase> {
+    let (m, n) = match shard {
+        Some(shard) => shard,
+        None => return tests,
+    };
+
+    let mut by_shard: Vec<Vec<TestCase>> = (0..n).map(|_| Vec::new()).collect();
+
+    if let Some(timings) = timings {
+        let mut tests = tests;
+        tests.sort_by(|a, b| {
+            let a_ms = timings.get(a.name()).copied().unwrap_or(0.0);
+            let b_ms = timings.get(b.name()).copied().unwrap_or(0.0);
+            b_ms.partial_cmp(&a_ms).unwrap()
+        });
+
+        let mut shard_totals = vec![0.0f32; n];
+        for test in tests {
+            let (i, _) = shard_totals
+                .iter()
+                .enumerate()
+                .min_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap())
+                .unwrap();
+            shard_totals[i] += timings.get(test.name()).copied().unwrap_or(0.0);
+            by_shard[i].push(test);
+        }
+    } else {
+        for test in tests {
+            let i = stable_hash(test.name()) as usize % n;
+            by_shard[i].push(test);
+        }
+    }
+
+    by_shard.into_iter().nth(m - 1).unwrap()
+}
+
+// Reads the --shard-timings file, if one was given, for shard_tests() to
+// load-balance against.
+pub fn load_shard_timings(sub_config: &SubRunConfig) -> Result<Option<HashMap<String, f32>>> {
+    sub_config
+        .shard_timings
+        .as_ref()
+        .map(|path| {
+            read_timings(&mut File::open(path).with_context(|| format!("opening {:?}", path))?)
+                .context("Reading --shard-timings")
         })
+        .transpose()
+}
+
+// Loads the failing/flaky test names out of a prior run's results.csv/
+// failures.csv (as pointed to by --replay) and compiles an exact-match
+// RegexSet selecting just those tests.
+fn replay_include_regex(path: &Path) -> Result<RegexSet> {
+    let mut file =
+        File::open(path).with_context(|| format!("opening --replay file {:?}", path))?;
+    let results = RunnerResults::from_csv(&mut file)
+        .with_context(|| format!("parsing --replay file {:?}", path))?;
+    let names = results.failing_test_names();
+    if names.is_empty() {
+        warn!("--replay file {:?} has no failing/flaky tests to replay", path);
     }
+
+    RegexSet::new(names.iter().map(|name| format!("^{}$", regex::escape(name))))
+        .context("compiling --replay test names")
+}
+
+// Applies `--replay` if set (restricting `tests` to exactly its failing/
+// flaky test names, bypassing --fraction/--include-tests), else the normal
+// --fraction/--include-tests filtering chain. Shared by every TestCommand's
+// test_groups() so the replay behavior is identical across dEQP/gtest/
+// piglit/igt.
+pub fn select_tests(
+    tests: Vec<TestCase>,
+    sub_config: &SubRunConfig,
+    include_filters: &[RegexSet],
+) -> Result<Vec<TestCase>> {
+    if let Some(replay) = &sub_config.replay {
+        let replay_filter = replay_include_regex(replay)?;
+        Ok(tests
+            .into_iter()
+            .filter(|test| replay_filter.is_match(test.name()))
+            .collect())
+    } else {
+        Ok(tests
+            .into_iter()
+            .skip(sub_config.fraction_start - 1)
+            .step_by(sub_config.fraction)
+            .filter(|test| include_filters.iter().all(|x| x.is_match(test.name())))
+            .collect())
+    }
+}
+
+// A hash of `s` that's stable across runs and machines (unlike the default
+// HashMap RandomState, which reseeds per-process), for deterministically
+// assigning a test to one of --shard's N buckets.
+fn stable_hash(s: &str) -> u64 {
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
+
+    let mut hasher = DefaultHasher::new();
+    s.hash(&mut hasher);
+    hasher.finish()
 }
 
 pub trait TestCommand: Send + Sync {
@@ -300,6 +714,27 @@ pub trait TestCommand: Send + Sync {
         self.baseline().get(test).map(|x| x.status)
     }
 
+    // Appends `test` to the --record-flakes file, if one is configured, so a
+    // future run's --flakes picks it up without anyone having to hand-edit
+    // the flakes list. The originally observed failing status and the number
+    // of --flake-retries attempts that disagreed with it are recorded as a
+    // "#" comment above the regex line (parse_regex_set already skips "#"
+    // lines), for triage.
+    fn record_flake(&self, test: &str, original_status: RunnerStatus, retries: u32) {
+        if let Some(file) = &self.config().record_flakes {
+            let mut file = file.lock().unwrap();
+            let result = writeln!(
+                file,
+                "# was {} but gave inconsistent results across {} retries",
+                original_status, retries
+            )
+            .and_then(|_| writeln!(file, "{}", regex::escape(test)));
+            if let Err(e) = result {
+                error!("writing --record-flakes file for {}: {:?}", test, e);
+            }
+        }
+    }
+
     fn translate_result(
         &self,
         result: &DeqpTestResult,
@@ -328,6 +763,170 @@ pub trait TestCommand: Send + Sync {
         self.skips().is_match(test)
     }
 
+    // Consults the configured result cache (see `TestConfiguration::result_cache`)
+    // for each test, splitting `caselist` into results that can be served from a
+    // stable-pass cache hit and the tests that still need to actually run (cache
+    // misses, and anything whose last recorded result wasn't a clean pass).
+    fn split_cached_tests<'a>(
+        &self,
+        caselist: Vec<&'a TestCase>,
+        caselist_state: &CaselistState,
+    ) -> (Vec<RunnerResult>, Vec<&'a TestCase>) {
+        let cache = match self.config().result_cache.as_deref() {
+            Some(cache) => cache,
+            None => return (Vec::new(), caselist),
+        };
+
+        let mut cached_results = Vec::new();
+        let mut to_run = Vec::new();
+
+        for test in caselist {
+            let key = result_cache::cache_key(test.name(), &self.config().environment_fingerprint);
+            match cache.get(&key) {
+                Ok(Some(cached)) if cached.status == DeqpStatus::Pass => {
+                    let deqp_result = DeqpTestResult {
+                        name: test.name().to_owned(),
+                        status: cached.status,
+                        duration: cached.duration,
+                    };
+                    cached_results.push(RunnerResult {
+                        test: format!("{}{}", self.prefix(), test.name()),
+                        status: self.translate_result(&deqp_result, caselist_state),
+                        duration: cached.duration.as_secs_f32(),
+                        subtest: false,
+                        flake_retries: 0,
+                    });
+                }
+                Ok(_) => to_run.push(test),
+                Err(e) => {
+                    error!("reading result cache for {}: {:?}", test.name(), e);
+                    to_run.push(test);
+                }
+            }
+        }
+
+        (cached_results, to_run)
+    }
+
+    // Persists every non-subtest, stable-pass result from a fresh run into the
+    // configured result cache (if any), so a later invocation can serve it from
+    // `split_cached_tests` instead of re-spawning the process for it.
+    fn save_results_to_cache(&self, results: &[RunnerResult]) {
+        let cache = match self.config().result_cache.as_deref() {
+            Some(cache) => cache,
+            None => return,
+        };
+
+        for result in results {
+            if result.subtest || result.flake_retries > 0 || result.status != RunnerStatus::Pass {
+                continue;
+            }
+
+            let test_name = result.test.trim_start_matches(self.prefix());
+            let key = result_cache::cache_key(test_name, &self.config().environment_fingerprint);
+            let cached = CachedResult {
+                status: DeqpStatus::Pass,
+                duration: Duration::from_secs_f32(result.duration),
+            };
+            if let Err(e) = cache.put(&key, &cached) {
+                error!("writing result cache for {}: {:?}", test_name, e);
+            }
+        }
+    }
+
+    // Builds a Crash result for each of `tests`, for the bisection base cases
+    // below where a culprit (or suspect subset) has been isolated but never
+    // actually completed a run of its own.
+    fn crash_results(&self, tests: &[&TestCase]) -> Vec<RunnerResult> {
+        tests
+            .iter()
+            .map(|test| RunnerResult {
+                test: format!("{}{}", self.prefix(), test.name()),
+                status: RunnerStatus::Crash,
+                duration: 0.0,
+                subtest: false,
+                flake_retries: 0,
+            })
+            .collect()
+    }
+
+    // Re-runs `tests` in halves, recursing into whichever half still comes
+    // back short of a full result set, until a single test is isolated as
+    // the one whose crash/leak swallowed the rest of the caselist's results.
+    // Only called once `run_caselist_and_flake_detect` has already seen the
+    // full caselist come back truncated, so a single `tests` element that
+    // still runs short here is the confirmed culprit. A single isolated test
+    // is re-run once more before being blamed, since dropping to a group of
+    // one can coincide with an unrelated flake; and if bisection ever
+    // bottoms out with both halves of a pair completing cleanly on their
+    // own despite the pair together losing results, the crash only
+    // reproduces in combination, so the whole pair is reported as the
+    // suspect subset instead of silently waving both tests through clean.
+    fn bisect_caselist(
+        &self,
+        caselist_state: &mut CaselistState,
+        tests: &[&TestCase],
+    ) -> Result<Vec<RunnerResult>> {
+        caselist_state.run_id += 1;
+        let results = self.run(caselist_state, tests)?;
+
+        if results.len() >= tests.len() {
+            return Ok(results);
+        }
+
+        if tests.len() == 1 {
+            caselist_state.run_id += 1;
+            let confirm_results = self.run(caselist_state, tests)?;
+            if confirm_results.len() >= tests.len() {
+                warn!(
+                    "deqp-runner: {} lost its result once but ran clean on a second attempt; not blaming it for the crash",
+                    tests[0].name()
+                );
+                return Ok(confirm_results);
+            }
+
+            error!(
+                "deqp-runner: bisection isolated a crash/leak to {} (reproduced over 2 runs, {})",
+                tests[0].name(),
+                self.see_more("", caselist_state)
+            );
+            return Ok(self.crash_results(tests));
+        }
+
+        let mid = tests.len() / 2;
+        let (left, right) = tests.split_at(mid);
+
+        warn!(
+            "deqp-runner: caselist c{} lost {} result(s); bisecting {} tests into halves of {}/{} to isolate the culprit",
+            caselist_state.caselist_id,
+            tests.len() - results.len(),
+            tests.len(),
+            left.len(),
+            right.len()
+        );
+
+        let bisected_left = self.bisect_caselist(caselist_state, left)?;
+        let bisected_right = self.bisect_caselist(caselist_state, right)?;
+
+        if bisected_left.len() >= left.len() && bisected_right.len() >= right.len() {
+            // Neither half lost a result on its own, yet this level's combined
+            // run did: the crash only reproduces in combination, so there's no
+            // single culprit to converge on. Report the whole suspect subset
+            // as Crash rather than returning the clean per-half results, which
+            // would silently drop the crash we know is real.
+            warn!(
+                "deqp-runner: crash in {} tests only reproduces in combination, not from either half alone; reporting the whole subset as Crash ({})",
+                tests.len(),
+                self.see_more("", caselist_state)
+            );
+            return Ok(self.crash_results(tests));
+        }
+
+        let mut bisected = bisected_left;
+        bisected.extend(bisected_right);
+        Ok(bisected)
+    }
+
     fn run_caselist_and_flake_detect(
         &self,
         caselist: &[TestCase],
@@ -339,19 +938,83 @@ pub trait TestCommand: Send + Sync {
         let mut caselist: Vec<_> = caselist.iter().collect();
         caselist.sort_by(|x, y| x.name().cmp(y.name()));
 
+        let (cached_results, caselist) = self.split_cached_tests(caselist, caselist_state);
+
         caselist_state.run_id += 1;
-        let mut results = self.run(caselist_state, caselist.as_slice())?;
+        let mut results = if caselist.is_empty() {
+            Vec::new()
+        } else {
+            self.run(caselist_state, caselist.as_slice())?
+        };
         // If we made no more progress on the whole caselist,
         // then dEQP doesn't know about some of our tests and they'll report Missing.
-        if results.is_empty() {
+        if results.is_empty() && cached_results.is_empty() {
             anyhow::bail!(
                 "No results parsed.  Is your caselist out of sync with your deqp binary?"
             );
         }
 
-        // If any results came back with an unexpected failure, run the caselist again
-        // to see if we get the same results, and mark any changing results as flaky tests.
-        if results.iter().any(|x| !x.status.is_success()) {
+        // A caselist that came back short of a full result set crashed or
+        // leaked somewhere inside it, with no way to tell which test did it.
+        // Bisect to isolate the culprit instead of leaving the whole caselist
+        // unaccounted for.
+        if self.config().bisect_crashes && !caselist.is_empty() && results.len() < caselist.len() {
+            results = self.bisect_caselist(caselist_state, caselist.as_slice())?;
+        }
+
+        let flake_retries = self.config().flake_retries;
+        if flake_retries > 0 {
+            // Isolate and individually rerun each unexpected failure, up to
+            // flake_retries times, so a flake in a big caselist doesn't force us to
+            // rerun the whole (possibly slow) group just to characterize it. The
+            // test's status across all flake_retries+1 attempts (the original plus
+            // each retry) must agree unanimously or it's reclassified as Flake;
+            // this catches a test bouncing between different non-success statuses
+            // (e.g. Fail then Crash) that a "did any retry pass?" check would miss,
+            // and avoids a two-run Fail/Fail tie being mistaken for a flake.
+            for result in results.iter_mut() {
+                if result.status.is_success() || result.subtest {
+                    continue;
+                }
+
+                let test = match caselist
+                    .iter()
+                    .find(|x| x.name() == result.test.trim_start_matches(self.prefix()))
+                {
+                    Some(test) => *test,
+                    None => continue,
+                };
+
+                let mut statuses = vec![result.status];
+                for _ in 1..=flake_retries {
+                    caselist_state.run_id += 1;
+                    let retry_results = self.run(caselist_state, &[test])?;
+                    if let Some(retry_result) = retry_results.iter().find(|x| !x.subtest) {
+                        statuses.push(retry_result.status);
+                    }
+                }
+
+                if statuses.iter().any(|status| *status != statuses[0]) {
+                    let original_status = result.status;
+                    info!(
+                        "{} gave inconsistent results across {} attempts ({}), reclassified as Flake",
+                        test.name(),
+                        statuses.len(),
+                        statuses
+                            .iter()
+                            .map(|status| status.to_string())
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    );
+                    result.status = RunnerStatus::Flake;
+                    result.flake_retries = flake_retries;
+                    self.record_flake(test.name(), original_status, flake_retries);
+                }
+            }
+        } else if results.iter().any(|x| !x.status.is_success()) {
+            // If any results came back with an unexpected failure, run the caselist
+            // again to see if we get the same results, and mark any changing
+            // results as flaky tests.
             caselist_state.run_id += 1;
             let retest_results = self.run(caselist_state, caselist.as_slice())?;
             for pair in results.iter_mut().zip(retest_results.iter()) {
@@ -361,9 +1024,18 @@ pub trait TestCommand: Send + Sync {
             }
         }
 
+        self.save_results_to_cache(&results);
+        results.extend(cached_results);
+
         Ok(results)
     }
 
+    // Drives a caselist to completion, split into a cache-served half and a
+    // freshly-run half. The --result-cache-dir lookup/write (see
+    // `split_cached_tests`/`save_results_to_cache`) lives in
+    // `run_caselist_and_flake_detect` below, so it's shared by every
+    // TestCommand impl (dEQP, gtest, piglit, igt) rather than needing to be
+    // reimplemented per test type.
     fn process_caselist(
         &self,
         tests: Vec<TestCase>,
@@ -385,6 +1057,7 @@ pub trait TestCommand: Send + Sync {
                     status: RunnerStatus::Skip,
                     duration: Default::default(),
                     subtest: false,
+                    flake_retries: 0,
                 });
             } else {
                 remaining_tests.push(test);
@@ -433,6 +1106,7 @@ pub trait TestCommand: Send + Sync {
                             status: RunnerStatus::Missing,
                             duration: Default::default(),
                             subtest: false,
+                            flake_retries: 0,
                         });
                     }
                     break;
@@ -473,8 +1147,13 @@ pub trait TestCommand: Send + Sync {
             ),
         );
 
-        // Shuffle the test groups using a deterministic RNG so that every run gets the same shuffle.
-        tests.shuffle(&mut StdRng::from_seed([0x3bu8; 32]));
+        // Shuffle the test order with a seeded RNG (opt-in via --shuffle/--shuffle-seed)
+        // so that every run using the same seed gets the same shuffle. SmallRng
+        // rather than StdRng: this isn't security-sensitive, and we shuffle
+        // potentially large caselists on every run.
+        if let Some(seed) = self.config().shuffle_seed {
+            tests.shuffle(&mut SmallRng::seed_from_u64(seed));
+        }
 
         // Make test groups of tests_per_group() (512) tests, or if
         // min_tests_per_group() is lower than that, then 1/32nd of the
@@ -517,6 +1196,7 @@ pub enum TestCase {
     Deqp(String),
     GTest(String),
     Piglit(PiglitTest),
+    Igt(IgtTest),
 }
 
 impl TestCase {
@@ -525,6 +1205,7 @@ impl TestCase {
             TestCase::Deqp(name) => name,
             TestCase::GTest(name) => name,
             TestCase::Piglit(test) => &test.name,
+            TestCase::Igt(test) => &test.name,
         }
     }
 }
@@ -541,21 +1222,28 @@ impl AsRef<TestCase> for TestCase {
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn results_collection<W: Write>(
     status_output: &mut W,
+    ndjson: &mut Option<Box<dyn Write + Send>>,
     run_results: &mut RunnerResults,
     total_tests: u32,
-    receiver: Receiver<Result<Vec<RunnerResult>>>,
+    progress: &ProgressMeter,
+    receiver: Receiver<(u32, Result<Vec<RunnerResult>>)>,
+    fail_fast: Option<u32>,
+    abort: &AtomicBool,
 ) {
-    let update_interval = Duration::new(2, 0);
+    let mut truncated = false;
 
-    run_results.status_update(status_output, total_tests);
-    let mut last_status_update = Instant::now();
-
-    for group_results in receiver {
+    for (group, group_results) in receiver {
         match group_results {
             Ok(group_results) => {
                 for result in group_results {
+                    if let Some(ndjson) = ndjson {
+                        if let Err(e) = write_ndjson_result(ndjson, &result, group) {
+                            error!("Error writing --results-json event: {}", e);
+                        }
+                    }
                     run_results.record_result(result);
                 }
             }
@@ -563,14 +1251,41 @@ fn results_collection<W: Write>(
                 println!("Error: {}", e);
             }
         }
-        if last_status_update.elapsed() >= update_interval {
-            run_results.status_update(status_output, total_tests);
-            last_status_update = Instant::now();
+        progress.maybe_print(run_results.result_counts.fail_count(), status_output);
+
+        if !truncated {
+            if let Some(n) = fail_fast {
+                let counts = &run_results.result_counts;
+                if counts.fail + counts.crash + counts.timeout >= n {
+                    truncated = true;
+                    abort.store(true, Ordering::Relaxed);
+                    writeln!(
+                        status_output,
+                        "\n--fail-fast: {} failures reached, not dispatching further test groups",
+                        n
+                    )
+                    .ok();
+                }
+            }
         }
     }
 
+    // Leave the last carriage-return progress line in place instead of overwriting it.
+    if progress.is_tty() {
+        writeln!(status_output).ok();
+    }
+
     // Always print the final results
     run_results.status_update(status_output, total_tests);
+    if truncated {
+        write!(status_output, " (truncated by --fail-fast)").ok();
+    }
+
+    if let Some(ndjson) = ndjson {
+        if let Err(e) = write_ndjson_summary(ndjson, &run_results.result_counts) {
+            error!("Error writing --results-json summary event: {}", e);
+        }
+    }
 }
 
 // Splits the list of tests to groups and parallelize them across all cores, collecting results in
@@ -578,21 +1293,72 @@ fn results_collection<W: Write>(
 pub fn parallel_test(
     status_output: impl Write + Sync + Send,
     test_groups: Vec<(&dyn TestCommand, Vec<TestCase>)>,
+) -> Result<RunnerResults> {
+    parallel_test_with_ndjson(status_output, test_groups, None, false, None, None)
+}
+
+// As parallel_test, but additionally streams one JSON event per completed result (plus a final
+// summary event) to `ndjson`, for --results-json/--ndjson, lets --no-progress suppress the live
+// progress line, (if `profile` is given) records a chrome://tracing trace of the run to it, and
+// (if `fail_fast` is given) stops dispatching further test groups once that many Fail/Crash/
+// Timeout results have accumulated.
+#[allow(clippy::too_many_arguments)]
+pub fn parallel_test_with_ndjson(
+    status_output: impl Write + Sync + Send,
+    test_groups: Vec<(&dyn TestCommand, Vec<TestCase>)>,
+    mut ndjson: Option<Box<dyn Write + Send>>,
+    no_progress: bool,
+    profile: Option<&Path>,
+    fail_fast: Option<u32>,
 ) -> Result<RunnerResults> {
     let test_count = test_groups.iter().map(|x| x.1.len() as u32).sum();
+    let abort = AtomicBool::new(false);
 
     let mut run_results = RunnerResults::new();
+    let progress = ProgressMeter::new(
+        test_count as usize,
+        !no_progress && atty::is(atty::Stream::Stdout),
+    );
 
     // Make a channel for the parallel iterator to send results to, which is what will be
     // printing the console status output but also computing the run_results.
-    let (sender, receiver) = channel::<Result<Vec<RunnerResult>>>();
+    let (sender, receiver) = channel::<(u32, Result<Vec<RunnerResult>>)>();
+
+    // If --profile was given, workers also report group timing down this
+    // second channel, which a dedicated thread drains into a chrome trace.
+    let (profiler, profile_receiver) = match profile {
+        Some(_) => {
+            let (profiler, receiver) = Profiler::new();
+            (Some(profiler), Some(receiver))
+        }
+        None => (None, None),
+    };
 
     let mut status_output = status_output;
 
     crossbeam_utils::thread::scope(|s| {
         // Spawn the results collection in a crossbeam scope, so that it doesn't
         // take a slot in rayon's thread pool.
-        s.spawn(|_| results_collection(&mut status_output, &mut run_results, test_count, receiver));
+        s.spawn(|_| {
+            results_collection(
+                &mut status_output,
+                &mut ndjson,
+                &mut run_results,
+                test_count,
+                &progress,
+                receiver,
+                fail_fast,
+                &abort,
+            )
+        });
+
+        if let (Some(path), Some(profile_receiver)) = (profile, profile_receiver) {
+            s.spawn(move |_| {
+                if let Err(e) = trace::write_chrome_trace(path, profile_receiver) {
+                    error!("Error writing --profile trace: {}", e);
+                }
+            });
+        }
 
         // Rayon parallel iterator takes our vector and runs it on its thread
         // pool.
@@ -601,13 +1367,47 @@ pub fn parallel_test(
             .enumerate()
             .par_bridge()
             .try_for_each_with(sender, |sender, (i, (deqp, tests))| {
-                sender.send(deqp.process_caselist(tests, i as u32))
+                if abort.load(Ordering::Relaxed) {
+                    return Ok(());
+                }
+
+                let group_len = tests.len();
+                let group_start = Instant::now();
+                let group_name = profiler
+                    .is_some()
+                    .then(|| match (tests.first(), tests.last()) {
+                        (Some(first), Some(last)) if first.name() != last.name() => {
+                            format!("group {}: {}..{}", i, first.name(), last.name())
+                        }
+                        (Some(first), _) => format!("group {}: {}", i, first.name()),
+                        _ => format!("group {}", i),
+                    });
+
+                let result = deqp.process_caselist(tests, i as u32);
+
+                progress.record_completed(group_len);
+                if let (Some(profiler), Some(group_name)) = (&profiler, group_name) {
+                    profiler.record_complete(
+                        group_name,
+                        runner_thread_index().unwrap_or(0) as u32,
+                        group_start,
+                        group_start.elapsed(),
+                    );
+                }
+
+                sender.send((i as u32, result))
             })
             .unwrap();
 
+        // Drop our half of the profiling channel now that every group has
+        // reported in, so the trace-writing thread's receiver sees the
+        // channel close and can finish and be joined below.
+        drop(profiler);
+
         // As we leave this scope, crossbeam will join the results collection
-        // thread.  Note that it terminates cleanly because we moved the sender
-        // into the rayon iterator.
+        // (and, if enabled, trace-writing) threads.  Note that they terminate
+        // cleanly because we moved/dropped the sending halves of their
+        // channels into the rayon iterator (or just above).
     })
     .unwrap();
 
@@ -662,27 +1462,262 @@ pub fn read_lines<I: IntoIterator<Item = impl AsRef<Path>>>(files: I) -> Result<
     Ok(lines)
 }
 
+/// Default cap (in bytes) on how much of a single test's stdout/stderr we keep
+/// in memory, via `read_bounded_lines`.
+pub const CAPTURE_BYTE_LIMIT: usize = 1024 * 1024;
+
+// Collects the lines of `reader`, applying `truncate_lines` so a runaway test
+// that spews unbounded output can't grow the runner's memory use without
+// bound across thousands of concurrent test invocations.
+//
+// Reads raw bytes via `read_until` and lossily converts each line instead of
+// `BufRead::lines()`, since a crashing test driver is exactly the kind of
+// thing that emits binary or mis-encoded output on stdout/stderr, and
+// `lines()` would silently drop such a line rather than capture it. Strips a
+// trailing `\r` as well as the `\n`, matching `lines()`'s CRLF handling, so
+// captured stderr isn't left with a stray `\r` that can break exact/`$`-
+// anchored `expected_output` regex matching.
+pub fn read_bounded_lines(reader: impl Read, limit: usize) -> (Vec<String>, bool) {
+    let mut reader = BufReader::new(reader);
+    let mut lines = Vec::new();
+    let mut buf = Vec::new();
+    loop {
+        buf.clear();
+        match reader.read_until(b'\n', &mut buf) {
+            Ok(0) => break,
+            Ok(_) => {
+                if buf.last() == Some(&b'\n') {
+                    buf.pop();
+                }
+                if buf.last() == Some(&b'\r') {
+                    buf.pop();
+                }
+                lines.push(String::from_utf8_lossy(&buf).into_owned());
+            }
+            Err(_) => break,
+        }
+    }
+    truncate_lines(lines, limit)
+}
+
+// If `lines` is under `limit` bytes total, returns it unchanged with `false`.
+// Otherwise keeps as many whole lines as fit in each half of `limit` from the
+// start and end of `lines`, drops everything in between, and replaces it with
+// a single "... N bytes skipped ..." marker line, returning `true`. This
+// preserves the head (test banner) and tail (panic/backtrace) most useful
+// for diagnosing a failure while keeping memory bounded.
+fn truncate_lines(lines: Vec<String>, limit: usize) -> (Vec<String>, bool) {
+    let total: usize = lines.iter().map(|line| line.len() + 1).sum();
+    if total <= limit {
+        return (lines, false);
+    }
+
+    let half = limit / 2;
+
+    let mut head_end = 0;
+    let mut bytes = 0;
+    for line in &lines {
+        if bytes + line.len() + 1 > half {
+            break;
+        }
+        bytes += line.len() + 1;
+        head_end += 1;
+    }
+
+    let mut tail_start = lines.len();
+    let mut bytes = 0;
+    for line in lines.iter().rev() {
+        if tail_start - 1 < head_end || bytes + line.len() + 1 > half {
+            break;
+        }
+        bytes += line.len() + 1;
+        tail_start -= 1;
+    }
+
+    if tail_start <= head_end {
+        return (lines, false);
+    }
+
+    let skipped_bytes: usize = lines[head_end..tail_start]
+        .iter()
+        .map(|line| line.len() + 1)
+        .sum();
+
+    let mut truncated: Vec<String> = lines[..head_end].to_vec();
+    truncated.push(format!("... {} bytes skipped ...", skipped_bytes));
+    truncated.extend_from_slice(&lines[tail_start..]);
+
+    (truncated, true)
+}
+
+// Puts a soon-to-be-spawned test process into its own new process group
+// (pgid == its own pid), so `kill_child_process_group` below can signal the
+// whole subtree it forked (compiler servers, GPU daemons, wrapper shells)
+// instead of just the direct child. No-op on non-Unix, where there's no
+// process-group concept to set up.
+#[cfg(unix)]
+pub(crate) fn set_process_group(command: &mut Command) {
+    use std::os::unix::process::CommandExt;
+    command.process_group(0);
+}
+
+#[cfg(not(unix))]
+pub(crate) fn set_process_group(_command: &mut Command) {}
+
+// Kills `child`'s entire process group rather than just the direct child, so
+// a timed-out test can't leave orphaned helper processes (or GPU contexts)
+// behind to corrupt whatever runs next in the same thread. Only effective if
+// the child was spawned with `set_process_group` above; falls back to
+// killing just the child on non-Unix platforms.
+#[cfg(unix)]
+pub(crate) fn kill_child_process_group(child: &mut std::process::Child) {
+    // Safe: passing the negative of our own child's pid to kill(2) signals
+    // its whole process group rather than dereferencing any memory.
+    unsafe {
+        libc::kill(-(child.id() as libc::pid_t), libc::SIGKILL);
+    }
+}
+
+#[cfg(not(unix))]
+pub(crate) fn kill_child_process_group(child: &mut std::process::Child) {
+    let _ = child.kill();
+}
+
 pub fn process_results(
     results: &RunnerResults,
     output_dir: &Path,
     summary_limit: usize,
+) -> Result<()> {
+    process_results_with_junit(
+        results,
+        output_dir,
+        summary_limit,
+        None,
+        false,
+        0.0,
+        false,
+        None,
+        false,
+    )
+}
+
+pub fn process_results_with_junit(
+    results: &RunnerResults,
+    output_dir: &Path,
+    summary_limit: usize,
+    junit_xml: Option<&Path>,
+    diff: bool,
+    slow_test_secs: f32,
+    fail_on_flake: bool,
+    timings_output: Option<&Path>,
+    watch: bool,
 ) -> Result<()> {
     results.write_results(&mut File::create(&output_dir.join("results.csv"))?)?;
     results.write_failures(&mut File::create(&output_dir.join("failures.csv"))?)?;
 
-    results.print_summary(if summary_limit == 0 {
-        std::usize::MAX
-    } else {
-        summary_limit
-    });
+    if let Some(junit_xml) = junit_xml {
+        results
+            .write_junit_report(&mut File::create(junit_xml)?, "deqp-runner")
+            .context("writing --junit-xml report")?;
+    }
+
+    if let Some(timings_output) = timings_output {
+        results
+            .write_timings(&mut File::create(timings_output)?)
+            .context("writing --timings-output report")?;
+    }
+
+    if diff {
+        results
+            .write_diff_json(&mut File::create(&output_dir.join("diff.json"))?)
+            .context("writing --diff report")?;
+        results.diff_report().print_summary();
+    }
+
+    results.print_summary(
+        if summary_limit == 0 {
+            std::usize::MAX
+        } else {
+            summary_limit
+        },
+        slow_test_secs,
+    );
 
-    if !results.is_success() {
+    if !watch && (!results.is_success() || (fail_on_flake && results.result_counts.flake > 0)) {
         std::process::exit(1);
     }
 
     Ok(())
 }
 
+// Re-runs `run_once` under `--watch`, importing Deno's "run once, then watch
+// and restart on change" test loop into this crate's batch-oriented runner.
+// After the initial run, blocks on a debounced (~300ms) filesystem watch of
+// `binary_paths`, `caselist_paths`, and `sub_config`'s skips/flakes files. A
+// caselist change re-runs the full suite (tests may have been added or
+// removed), while any other change instead narrows the re-run to just the
+// previous run's failures, by pointing `sub_config.replay` at the
+// failures.csv the prior `run_once` just wrote -- reusing the --replay
+// machinery (see `select_tests`) rather than a separate mechanism. Only
+// returns on a filesystem-watcher setup error or a `run_once` error;
+// `run_once` itself must not treat a failing run as fatal (see the `watch`
+// parameter on `process_results_with_junit`), or it would exit out from
+// under this loop on the first red run.
+pub fn watch_and_rerun(
+    output_dir: &Path,
+    binary_paths: &[PathBuf],
+    caselist_paths: &[PathBuf],
+    sub_config: &mut SubRunConfig,
+    mut run_once: impl FnMut(&SubRunConfig) -> Result<()>,
+) -> Result<()> {
+    run_once(sub_config)?;
+
+    let (tx, rx) = channel();
+    let mut watcher: notify::RecommendedWatcher =
+        notify::Watcher::new(tx, Duration::from_millis(300))
+            .context("setting up --watch filesystem watcher")?;
+    for path in binary_paths
+        .iter()
+        .chain(caselist_paths.iter())
+        .chain(sub_config.skips.iter())
+        .chain(sub_config.flakes.iter())
+    {
+        watcher
+            .watch(path, notify::RecursiveMode::NonRecursive)
+            .with_context(|| format!("--watch: watching {:?}", path))?;
+    }
+
+    eprintln!("--watch: watching for changes, Ctrl-C to exit...");
+    loop {
+        let changed = match rx
+            .recv()
+            .context("--watch filesystem watcher disconnected")?
+        {
+            notify::DebouncedEvent::Write(path)
+            | notify::DebouncedEvent::Create(path)
+            | notify::DebouncedEvent::Chmod(path) => path,
+            _ => continue,
+        };
+
+        if caselist_paths.contains(&changed) {
+            eprintln!(
+                "--watch: caselist {:?} changed, re-running full suite",
+                changed
+            );
+            sub_config.replay = None;
+        } else {
+            eprintln!(
+                "--watch: {:?} changed, re-running previous failures",
+                changed
+            );
+            sub_config.replay = Some(output_dir.join("failures.csv"));
+        }
+
+        run_once(sub_config)?;
+        eprintln!("--watch: watching for changes, Ctrl-C to exit...");
+    }
+}
+
 pub fn read_baseline(path: Option<&PathBuf>) -> Result<RunnerResults> {
     match path {
         Some(path) => {
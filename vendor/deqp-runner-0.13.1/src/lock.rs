@@ -0,0 +1,34 @@
+// Advisory locking of the --output directory, so two runs that accidentally
+// target the same artifacts directory (e.g. a CI misconfiguration) fail fast
+// instead of both writing results.csv/failures.csv and interleaving or
+// corrupting them. This is purely advisory: it never blocks other tools from
+// reading or writing in the directory, only other deqp-runner/piglit-runner
+// processes that also call this function.
+use anyhow::{Context, Result};
+use fd_lock::FdLock;
+use std::fs::{File, OpenOptions};
+use std::path::Path;
+
+// Acquires a lock on a `.lock` file inside `output_dir`, held for the
+// lifetime of the process (there's no natural point to release it before
+// exit, so the guard and the lock it borrows from are both leaked).
+pub fn lock_output_dir(output_dir: &Path) -> Result<()> {
+    let lock_path = output_dir.join(".lock");
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .open(&lock_path)
+        .with_context(|| format!("opening {}", lock_path.display()))?;
+
+    let fd_lock: &'static mut FdLock<File> = Box::leak(Box::new(FdLock::new(file)));
+    let guard = fd_lock.try_lock().with_context(|| {
+        format!(
+            "{} is locked by another deqp-runner/piglit-runner process",
+            lock_path.display()
+        )
+    })?;
+    std::mem::forget(guard);
+
+    Ok(())
+}
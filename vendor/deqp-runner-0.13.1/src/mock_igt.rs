@@ -0,0 +1,59 @@
+use anyhow::{Context, Result};
+use structopt::StructOpt;
+
+/// Mock IGT GPU Tools binary that uses conventions in the subtest name to
+/// control behavior.  We use this for integration testing of the igt runner.
+#[derive(Debug, StructOpt)]
+pub struct MockIgt {
+    #[structopt(long)]
+    list_subtests: bool,
+
+    #[structopt(long = "run-subtest")]
+    run_subtest: Option<String>,
+}
+
+fn subtest_result(name: &str, status: &str) -> ! {
+    println!("Subtest {}: {} (0.002s)", name, status);
+
+    std::process::exit(match status {
+        "SUCCESS" => 0,
+        "SKIP" => 77,
+        _ => 1,
+    });
+}
+
+pub fn mock_igt(mock: &MockIgt) -> Result<()> {
+    if mock.list_subtests {
+        println!("pass");
+        println!("skip");
+        println!("fail");
+        println!("warn");
+        println!("crash");
+        println!("timeout");
+        return Ok(());
+    }
+
+    let name = mock
+        .run_subtest
+        .as_deref()
+        .context("--run-subtest is required")?;
+    println!("Starting subtest: {}", name);
+
+    if name.contains("pass") {
+        subtest_result(name, "SUCCESS");
+    } else if name.contains("skip") {
+        subtest_result(name, "SKIP");
+    } else if name.contains("fail") {
+        subtest_result(name, "FAIL");
+    } else if name.contains("warn") {
+        subtest_result(name, "WARN");
+    } else if name.contains("crash") {
+        panic!("crashing!")
+    } else if name.contains("timeout") {
+        // Simulate a subtest that doesn't return in time by infinite looping.
+        #[allow(clippy::empty_loop)]
+        loop {}
+    }
+
+    anyhow::bail!("Unknown subtest name '{}'", name)
+}
@@ -1,5 +1,6 @@
 use anyhow::{Context, Result};
 use regex::Regex;
+use serde::{Deserialize, Serialize};
 use std::io::prelude::*;
 use std::io::{BufRead, BufReader};
 use std::str::FromStr;
@@ -7,7 +8,15 @@ use std::time::{Duration, Instant};
 use timeout_readwrite::TimeoutReader;
 
 // See s_qpTestResultMap in qpTestLog.c
-#[derive(Clone, Copy, Debug, PartialEq)]
+//
+// The variant names match dEQP's own status names (see FromStr below), which
+// also gives us a stable, human-readable serde representation for free.
+//
+// DmesgWarning/DmesgFail/Incomplete don't come from dEQP itself; they exist
+// so piglit's `dmesg-warn`/`dmesg-fail`/`incomplete` results (see
+// `PiglitStatus::to_deqp_status`) have a home in the one status type the
+// rest of deqp-runner (baseline comparison, CSV/JUnit output) deals in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum DeqpStatus {
     Pass,
     Fail,
@@ -20,6 +29,9 @@ pub enum DeqpStatus {
     Crash,
     Timeout,
     Waiver,
+    DmesgWarning,
+    DmesgFail,
+    Incomplete,
 }
 
 impl FromStr for DeqpStatus {
@@ -44,6 +56,44 @@ impl FromStr for DeqpStatus {
     }
 }
 
+impl DeqpStatus {
+    // Severity rank used for baseline-regression comparisons and for folding
+    // subtest outcomes into an overall status: higher is worse. Mirrors
+    // piglit's own intuition (pass < warn < dmesg-warn < fail < dmesg-fail <
+    // crash < timeout < incomplete), extended to place dEQP's own statuses
+    // among them.
+    fn severity(self) -> u8 {
+        match self {
+            DeqpStatus::Pending => 0,
+            DeqpStatus::NotSupported => 1,
+            DeqpStatus::Waiver => 2,
+            DeqpStatus::Pass => 3,
+            DeqpStatus::QualityWarning => 4,
+            DeqpStatus::CompatibilityWarning => 5,
+            DeqpStatus::DmesgWarning => 6,
+            DeqpStatus::Fail => 7,
+            DeqpStatus::DmesgFail => 8,
+            DeqpStatus::ResourceError => 9,
+            DeqpStatus::InternalError => 10,
+            DeqpStatus::Crash => 11,
+            DeqpStatus::Timeout => 12,
+            DeqpStatus::Incomplete => 13,
+        }
+    }
+}
+
+impl PartialOrd for DeqpStatus {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for DeqpStatus {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.severity().cmp(&other.severity())
+    }
+}
+
 #[derive(Debug)]
 pub struct DeqpTestResult {
     pub name: String,
@@ -22,8 +22,9 @@
 use anyhow::{Context, Result};
 use flate2::read::GzDecoder;
 use log::*;
-use regex::Regex;
+use regex::{Regex, RegexSet};
 use roxmltree::Document;
+use serde::Deserialize;
 use std::fs::File;
 use std::io::prelude::*;
 use std::io::{BufRead, BufReader};
@@ -34,19 +35,55 @@ use timeout_readwrite::TimeoutReader;
 use crate::parse_deqp::{DeqpStatus, DeqpTestResult};
 use crate::TestCase;
 
-impl DeqpStatus {
+/// Piglit's own result vocabulary, as seen in `PIGLIT: {"result": ...}` and
+/// `PIGLIT: {"subtest": {...}}` lines, kept distinct from `DeqpStatus` so that this
+/// module doesn't have to invent dEQP-flavored names for piglit-only concepts.
+/// Translated to a `DeqpStatus` at the edge via [`PiglitStatus::to_deqp_status`] so
+/// the rest of deqp-runner (translation against baselines, CSV output, etc.) only
+/// has to deal with one status type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PiglitStatus {
+    Pass,
+    Fail,
+    Warn,
+    Crash,
+    Skip,
+    Timeout,
+    DmesgWarn,
+    DmesgFail,
+    Incomplete,
+}
+
+impl PiglitStatus {
     // Parses the status name from piglit's output.
-    fn from_piglit_str(input: &str) -> Result<DeqpStatus, anyhow::Error> {
+    fn from_piglit_str(input: &str) -> Result<PiglitStatus, anyhow::Error> {
         match input {
-            "pass" => Ok(DeqpStatus::Pass),
-            "fail" => Ok(DeqpStatus::Fail),
-            "warn" => Ok(DeqpStatus::CompatibilityWarning),
-            "crash" => Ok(DeqpStatus::Crash),
-            "skip" => Ok(DeqpStatus::NotSupported),
-            "timeout" => Ok(DeqpStatus::Timeout),
+            "pass" => Ok(PiglitStatus::Pass),
+            "fail" => Ok(PiglitStatus::Fail),
+            "warn" => Ok(PiglitStatus::Warn),
+            "crash" => Ok(PiglitStatus::Crash),
+            "skip" => Ok(PiglitStatus::Skip),
+            "timeout" => Ok(PiglitStatus::Timeout),
+            "dmesg-warn" => Ok(PiglitStatus::DmesgWarn),
+            "dmesg-fail" => Ok(PiglitStatus::DmesgFail),
+            "incomplete" => Ok(PiglitStatus::Incomplete),
             _ => anyhow::bail!("unknown piglit status '{}'", input),
         }
     }
+
+    pub fn to_deqp_status(self) -> DeqpStatus {
+        match self {
+            PiglitStatus::Pass => DeqpStatus::Pass,
+            PiglitStatus::Fail => DeqpStatus::Fail,
+            PiglitStatus::Warn => DeqpStatus::CompatibilityWarning,
+            PiglitStatus::Crash => DeqpStatus::Crash,
+            PiglitStatus::Skip => DeqpStatus::NotSupported,
+            PiglitStatus::Timeout => DeqpStatus::Timeout,
+            PiglitStatus::DmesgWarn => DeqpStatus::DmesgWarning,
+            PiglitStatus::DmesgFail => DeqpStatus::DmesgFail,
+            PiglitStatus::Incomplete => DeqpStatus::Incomplete,
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -56,6 +93,12 @@ pub struct PiglitTestResult {
 
     pub subtests: Vec<DeqpTestResult>,
     pub stdout: Vec<String>,
+
+    /// Set by `apply_hang_signatures` when one of the configured GPU-hang/crash
+    /// regexes matched a line of `stdout`; names which signature fired so CI can
+    /// tell a genuine timeout apart from a GPU lockup that will poison
+    /// subsequent tests run by the same process.
+    pub hang_signature: Option<String>,
 }
 
 // For comparing equality, we ignore the test runtime (particularly of use for the unit tests )
@@ -64,6 +107,22 @@ impl PartialEq for PiglitTestResult {
         self.status == other.status
             && self.subtests == other.subtests
             && self.stdout == other.stdout
+            && self.hang_signature == other.hang_signature
+    }
+}
+
+impl PiglitTestResult {
+    /// The status to report for the test as a whole, folding in subtest
+    /// outcomes. Piglit can print a stale top-level "pass" on the final
+    /// `PIGLIT: {"result": ...}` line even after logging a subtest failure
+    /// earlier in the same run, so a failing/crashing/etc. subtest always
+    /// drags the parent down to its level; we never let a later subtest
+    /// paper over an already-worse parent or sibling status.
+    pub fn overall_status(&self) -> DeqpStatus {
+        self.subtests
+            .iter()
+            .map(|s| s.status)
+            .fold(self.status.unwrap_or(DeqpStatus::Crash), DeqpStatus::max)
     }
 }
 
@@ -76,6 +135,7 @@ pub fn parse_piglit_results(piglit_output: impl Read) -> PiglitTestResult {
         static ref STATUS_RE: Regex = Regex::new(r#"PIGLIT: \{"result": "(.*)" \}"#).unwrap();
         static ref SUBTEST_RE: Regex =
             Regex::new(r#"PIGLIT: \{"subtest": *\{"(.*)" *: *"(.*)"\}\}"#).unwrap();
+        static ref TEST_MARKER_RE: Regex = Regex::new(r"^PIGLIT TEST: \d+ - (.*)$").unwrap();
     }
 
     let mut stdout: Vec<String> = Vec::new();
@@ -88,6 +148,13 @@ pub fn parse_piglit_results(piglit_output: impl Read) -> PiglitTestResult {
 
     let mut subtests = Vec::new();
 
+    // Stdout carries no timestamps of its own, but `piglit_output.lines()`
+    // blocks on each read, so the wall-clock time between the `PIGLIT TEST: N
+    // - name` marker that starts a subtest and the `PIGLIT: {"subtest": ...}`
+    // line that reports its result is a real (if coarse) measure of how long
+    // that subtest took, same as `startup` already is for the test overall.
+    let mut subtest_start: Option<(String, Instant)> = None;
+
     for line in piglit_output.lines() {
         let line = match line {
             Ok(line) => line,
@@ -103,7 +170,15 @@ pub fn parse_piglit_results(piglit_output: impl Read) -> PiglitTestResult {
             }
         };
 
-        if let Some(cap) = STATUS_RE.captures(&line) {
+        if let Some(cap) = TEST_MARKER_RE.captures(&line) {
+            let name = cap[1].to_owned();
+            // shader_runner prints this marker twice per subtest (once before
+            // running it, once after); only the first occurrence should start
+            // the clock.
+            if subtest_start.as_ref().map(|(n, _)| n.as_str()) != Some(name.as_str()) {
+                subtest_start = Some((name, Instant::now()));
+            }
+        } else if let Some(cap) = STATUS_RE.captures(&line) {
             if let Some(old_status) = status {
                 error!(
                     "Second piglit status result found (was {:?}, new result {})",
@@ -111,17 +186,28 @@ pub fn parse_piglit_results(piglit_output: impl Read) -> PiglitTestResult {
                 );
                 status = Some(DeqpStatus::Crash);
             } else {
-                status = Some(DeqpStatus::from_piglit_str(&cap[1]).unwrap_or_else(|e| {
-                    error!("{:?}", e);
-                    DeqpStatus::Crash
-                }));
+                status = Some(
+                    PiglitStatus::from_piglit_str(&cap[1])
+                        .map(PiglitStatus::to_deqp_status)
+                        .unwrap_or_else(|e| {
+                            error!("{:?}", e);
+                            DeqpStatus::Crash
+                        }),
+                );
             }
         } else if let Some(cap) = SUBTEST_RE.captures(&line) {
             let sub_name = &cap[1];
-            let sub_status = DeqpStatus::from_piglit_str(&cap[2]).unwrap_or_else(|e| {
-                error!("{:?}", e);
-                DeqpStatus::Crash
-            });
+            let sub_status = PiglitStatus::from_piglit_str(&cap[2])
+                .map(PiglitStatus::to_deqp_status)
+                .unwrap_or_else(|e| {
+                    error!("{:?}", e);
+                    DeqpStatus::Crash
+                });
+
+            let duration = match subtest_start.take() {
+                Some((name, start)) if name == sub_name => start.elapsed(),
+                _ => Duration::from_secs_f32(0.0),
+            };
 
             if let Some(pos) = subtests
                 .iter()
@@ -133,7 +219,7 @@ pub fn parse_piglit_results(piglit_output: impl Read) -> PiglitTestResult {
                 subtests.push(DeqpTestResult {
                     name: sub_name.to_owned(),
                     status: sub_status,
-                    duration: Duration::from_secs_f32(0.0),
+                    duration,
                 });
             }
         }
@@ -141,11 +227,114 @@ pub fn parse_piglit_results(piglit_output: impl Read) -> PiglitTestResult {
         stdout.push(line);
     }
 
+    // Some binaries, rather than interleaving `PIGLIT: {...}` lines with their
+    // own logging, write a single native structured result object covering
+    // the whole test (see `parse_piglit_json_result`). That's unambiguous
+    // where scraping PIGLIT: lines out of arbitrary stdout isn't, so prefer
+    // it whenever the captured output parses as one.
+    if let Ok(json_result) = parse_piglit_json_result(&stdout.join("\n")) {
+        return json_result;
+    }
+
     PiglitTestResult {
         status,
         duration: startup.elapsed(),
         subtests,
         stdout,
+        hang_signature: None,
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct PiglitJsonTiming {
+    start: f64,
+    end: f64,
+}
+
+// Mirrors piglit's native per-test result object, as written to results.json:
+// {"result": "pass", "subtests": {"name": "pass", ...}, "out": "...",
+//  "err": "...", "returncode": 0, "time": {"start": 0.0, "end": 1.2}}
+#[derive(Debug, Deserialize)]
+struct PiglitJsonResult {
+    result: String,
+    #[serde(default)]
+    subtests: std::collections::HashMap<String, String>,
+    #[serde(default)]
+    out: String,
+    #[serde(default)]
+    #[allow(dead_code)] // Not surfaced separately yet; see PiglitTestResult's doc comment.
+    err: String,
+    #[serde(default)]
+    #[allow(dead_code)]
+    returncode: Option<i32>,
+    time: Option<PiglitJsonTiming>,
+}
+
+/// Parses piglit's native structured per-test result object directly into a
+/// `PiglitTestResult`, as an alternative to scraping `PIGLIT:` lines out of
+/// interleaved stdout. Gives an exact overall `duration` from the recorded
+/// `time` window instead of whatever wall-clock we happened to measure
+/// around the subprocess; subtest durations aren't in this object (piglit
+/// only timestamps the test as a whole) so they stay `Duration::new(0, 0)`,
+/// same as the stdout-scraping path.
+pub fn parse_piglit_json_result(json: &str) -> Result<PiglitTestResult> {
+    let parsed: PiglitJsonResult =
+        serde_json::from_str(json.trim()).context("parsing piglit JSON result")?;
+
+    let status = PiglitStatus::from_piglit_str(&parsed.result)
+        .map(PiglitStatus::to_deqp_status)
+        .unwrap_or_else(|e| {
+            error!("{:?}", e);
+            DeqpStatus::Crash
+        });
+
+    let mut subtest_names: Vec<&String> = parsed.subtests.keys().collect();
+    subtest_names.sort();
+    let subtests = subtest_names
+        .into_iter()
+        .map(|name| {
+            let sub_status = PiglitStatus::from_piglit_str(&parsed.subtests[name])
+                .map(PiglitStatus::to_deqp_status)
+                .unwrap_or_else(|e| {
+                    error!("{:?}", e);
+                    DeqpStatus::Crash
+                });
+            DeqpTestResult {
+                name: name.clone(),
+                status: sub_status,
+                duration: Duration::new(0, 0),
+            }
+        })
+        .collect();
+
+    let duration = match &parsed.time {
+        Some(t) if t.end >= t.start => Duration::from_secs_f64(t.end - t.start),
+        _ => Duration::new(0, 0),
+    };
+
+    Ok(PiglitTestResult {
+        status: Some(status),
+        duration,
+        subtests,
+        stdout: parsed.out.lines().map(str::to_owned).collect(),
+        hang_signature: None,
+    })
+}
+
+/// Scans already-captured stdout for a configured GPU-hang/crash signature
+/// (a kernel ring-reset message, a driver assertion, etc.) and, when one
+/// matches, forces the status to `Crash` and records which signature fired on
+/// `result.hang_signature`. Applied even when `stdout` also contains a
+/// trailing `PIGLIT: {"result": "pass"}` line, since a hang can corrupt
+/// driver/kernel state after that line was printed but before the process
+/// actually died.
+pub fn apply_hang_signatures(result: &mut PiglitTestResult, signatures: &RegexSet) {
+    for line in &result.stdout {
+        if let Some(idx) = signatures.matches(line).into_iter().next() {
+            result.status = Some(DeqpStatus::Crash);
+            result.hang_signature = Some(signatures.patterns()[idx].clone());
+            return;
+        }
     }
 }
 
@@ -156,6 +345,39 @@ pub fn parse_piglit_results_with_timeout(
     parse_piglit_results(TimeoutReader::new(deqp_output, timeout))
 }
 
+/// gzip's 2-byte magic number, used to detect compression by content rather
+/// than trusting a `.gz` extension (some profile caches are fetched/renamed
+/// in ways that drop or mislabel the extension).
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// Reads `path` as a profile file, transparently gunzipping it if its
+/// content starts with the gzip magic number, regardless of extension.
+fn read_profile_contents(path: &Path) -> Result<String> {
+    let mut file = File::open(path).with_context(|| format!("opening {:?}", path))?;
+
+    let mut magic = [0u8; 2];
+    let bytes_read = file
+        .read(&mut magic)
+        .with_context(|| format!("reading {:?}", path))?;
+    let mut rest = Vec::new();
+    file.read_to_end(&mut rest)
+        .with_context(|| format!("reading {:?}", path))?;
+
+    let mut contents = magic[..bytes_read].to_vec();
+    contents.extend(rest);
+
+    let mut s = String::new();
+    if contents.starts_with(&GZIP_MAGIC) {
+        GzDecoder::new(contents.as_slice())
+            .read_to_string(&mut s)
+            .with_context(|| format!("decompressing {:?}", path))?;
+    } else {
+        s = String::from_utf8(contents).with_context(|| format!("reading {:?}", path))?;
+    }
+
+    Ok(s)
+}
+
 pub fn read_profile_file(
     piglit_folder: &std::path::Path,
     profile: &str,
@@ -168,7 +390,7 @@ pub fn read_profile_file(
         let path = piglit_folder.join(Path::new(profile).with_extension("no_isolation.meta.xml"));
         if path.exists() {
             info!("... using {:?}", &path);
-            return std::fs::read_to_string(&path).with_context(|| format!("reading {:?}", path));
+            return read_profile_contents(&path);
         }
     }
 
@@ -176,7 +398,7 @@ pub fn read_profile_file(
         let path = piglit_folder.join(Path::new(profile).with_extension("meta.xml"));
         if path.exists() {
             info!("... using {:?}", path);
-            return std::fs::read_to_string(&path).with_context(|| format!("reading {:?}", path));
+            return read_profile_contents(&path);
         }
     }
 
@@ -185,12 +407,7 @@ pub fn read_profile_file(
         let path = piglit_folder.join(Path::new(profile).with_extension("no_isolation.xml.gz"));
         if path.exists() {
             info!("... using {:?}", path);
-            let file = File::open(&path).with_context(|| format!("opening {:?}", path))?;
-            let mut s = String::new();
-            GzDecoder::new(file)
-                .read_to_string(&mut s)
-                .with_context(|| format!("reading {:?}", path))?;
-            return Ok(s);
+            return read_profile_contents(&path);
         }
     }
 
@@ -199,7 +416,7 @@ pub fn read_profile_file(
         let path = piglit_folder.join(Path::new(profile).with_extension("no_isolation.xml"));
         if path.exists() {
             info!("... using {:?}", path);
-            return std::fs::read_to_string(&path).with_context(|| format!("reading {:?}", path));
+            return read_profile_contents(&path);
         }
     }
 
@@ -208,18 +425,13 @@ pub fn read_profile_file(
         let path = piglit_folder.join(Path::new(profile).with_extension("xml.gz"));
         if path.exists() {
             info!("... using {:?}", path);
-            let file = File::open(&path).with_context(|| format!("opening {:?}", path))?;
-            let mut s = String::new();
-            GzDecoder::new(file)
-                .read_to_string(&mut s)
-                .with_context(|| format!("reading {:?}", path))?;
-            return Ok(s);
+            return read_profile_contents(&path);
         }
     }
 
     {
         let path = piglit_folder.join(Path::new(profile).with_extension("xml"));
-        std::fs::read_to_string(&path).with_context(|| format!("reading {:?}", path))
+        read_profile_contents(&path)
     }
 }
 
@@ -287,10 +499,16 @@ pub fn piglit_sanitize_test_name(test: &str) -> String {
     test.replace(',', "-")
 }
 
+/// Default number of `.shader_test` files batched into a single
+/// `shader_runner` invocation by a `multi_shader` test group, matching
+/// piglit's own upstream default.
+pub const DEFAULT_MULTI_SHADER_GROUP_SIZE: usize = 100;
+
 pub fn parse_piglit_xml_testlist(
     folder: &Path,
     file_content: &str,
     process_isolation: bool,
+    multi_shader_group_size: usize,
 ) -> Result<Vec<crate::TestCase>> {
     let doc = Document::parse(file_content).context("reading caselist")?;
 
@@ -302,7 +520,12 @@ pub fn parse_piglit_xml_testlist(
         if let Some(name) = test.text() {
             info!("Found subprofile: {:?}", name);
             let content = read_profile_file(folder, name, process_isolation)?;
-            for t in parse_piglit_xml_testlist(folder, &content, process_isolation)? {
+            for t in parse_piglit_xml_testlist(
+                folder,
+                &content,
+                process_isolation,
+                multi_shader_group_size,
+            )? {
                 tests.push(t);
             }
         }
@@ -336,7 +559,7 @@ pub fn parse_piglit_xml_testlist(
                     let mut remaining = args.len();
                     let mut i = 0u32;
                     while remaining != 0 {
-                        let group_len = usize::min(100, remaining);
+                        let group_len = usize::min(multi_shader_group_size, remaining);
                         remaining -= group_len;
 
                         let mut a = args.split_off(remaining);
@@ -392,6 +615,34 @@ pub fn parse_piglit_xml_testlist(
     Ok(tests)
 }
 
+/// Convenience wrapper around [`read_profile_file`] and
+/// [`parse_piglit_xml_testlist`] for callers that just want the flat list of
+/// `.shader_test`/`shader_runner`/etc. invocations a profile (and any
+/// sub-profiles it references) expands to, without going through
+/// `PiglitTomlConfig::test_groups`. Non-piglit `TestCase` variants can't
+/// occur here since `parse_piglit_xml_testlist` only ever constructs
+/// `TestCase::Piglit`.
+pub fn parse_piglit_profile(
+    piglit_folder: &Path,
+    profile: &str,
+    process_isolation: bool,
+    multi_shader_group_size: usize,
+) -> Result<Vec<crate::PiglitTest>> {
+    let content = read_profile_file(piglit_folder, profile, process_isolation)?;
+    parse_piglit_xml_testlist(
+        piglit_folder,
+        &content,
+        process_isolation,
+        multi_shader_group_size,
+    )?
+    .into_iter()
+    .map(|test| match test {
+        TestCase::Piglit(test) => Ok(test),
+        _ => anyhow::bail!("unexpected non-piglit TestCase from piglit profile parsing"),
+    })
+    .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -400,7 +651,7 @@ mod tests {
 
     fn parse_immediate_xml(xml: &str) -> Result<Vec<TestCase>> {
         let dummy_path = PathBuf::from(".");
-        parse_piglit_xml_testlist(&dummy_path, xml, false)
+        parse_piglit_xml_testlist(&dummy_path, xml, false, DEFAULT_MULTI_SHADER_GROUP_SIZE)
     }
 
     #[test]
@@ -426,6 +677,28 @@ mod tests {
         );
     }
 
+    #[test]
+    fn parse_multi_shader_group_size() {
+        let xml = r#"
+        <Test type="multi_shader" name="shaders@glsl-1.10">
+        <option name="files" value="['a.shader_test', 'b.shader_test', 'c.shader_test', 'd.shader_test', 'e.shader_test']" />
+        </Test>"#;
+
+        let dummy_path = PathBuf::from(".");
+        let tests = parse_piglit_xml_testlist(&dummy_path, xml, false, 2).unwrap();
+
+        assert_eq!(tests.len(), 3);
+        let names: Vec<&str> = tests.iter().map(|t| t.name()).collect();
+        assert_eq!(
+            names,
+            vec![
+                "shaders@glsl-1.10|0",
+                "shaders@glsl-1.10|1",
+                "shaders@glsl-1.10|2"
+            ]
+        );
+    }
+
     #[test]
     fn parse_asmparsertest() {
         let xml = r#"
@@ -527,6 +800,7 @@ mod tests {
             duration: Duration::new(0, 0),
             subtests: Vec::new(),
             stdout: output_as_lines(orig_output),
+            hang_signature: None,
         }
     }
 
@@ -541,6 +815,34 @@ PIGLIT: {\"result\": \"pass\" }";
         );
     }
 
+    #[test]
+    fn parse_dmesg_and_incomplete_statuses() {
+        for (piglit_status, deqp_status) in [
+            ("dmesg-warn", DeqpStatus::DmesgWarning),
+            ("dmesg-fail", DeqpStatus::DmesgFail),
+            ("incomplete", DeqpStatus::Incomplete),
+        ] {
+            let output = format!("\nPIGLIT: {{\"result\": \"{}\" }}", piglit_status);
+
+            assert_eq!(
+                parse_piglit_results(output.as_bytes()),
+                result(deqp_status, &output),
+            );
+        }
+    }
+
+    #[test]
+    fn deqp_status_severity_ordering() {
+        assert!(DeqpStatus::Pass < DeqpStatus::CompatibilityWarning);
+        assert!(DeqpStatus::CompatibilityWarning < DeqpStatus::DmesgWarning);
+        assert!(DeqpStatus::DmesgWarning < DeqpStatus::Fail);
+        assert!(DeqpStatus::Fail < DeqpStatus::DmesgFail);
+        assert!(DeqpStatus::DmesgFail < DeqpStatus::Crash);
+        assert!(DeqpStatus::Crash < DeqpStatus::Timeout);
+        assert!(DeqpStatus::Timeout < DeqpStatus::Incomplete);
+        assert!(DeqpStatus::NotSupported < DeqpStatus::Pass);
+    }
+
     #[test]
     fn parse_subtests() {
         let output = "
@@ -637,6 +939,7 @@ PIGLIT: {\"result\": \"pass\" }";
                     }
                 ],
                 duration: Duration::new(0, 0),
+                hang_signature: None,
             }
         );
 
@@ -690,4 +993,266 @@ PIGLIT: {"subtest": {"vs-sign-neg" : "pass"}}
         );
         Ok(())
     }
+
+    // Feeds `text` to a reader one line at a time, sleeping `delay` before
+    // each line so that code reading from it (like `parse_piglit_results`)
+    // observes real wall-clock time passing between lines, the way it would
+    // reading from an actual piglit subprocess's stdout pipe.
+    struct SlowReader {
+        lines: std::vec::IntoIter<String>,
+        delay: Duration,
+    }
+
+    impl SlowReader {
+        fn new(text: &str, delay: Duration) -> Self {
+            SlowReader {
+                lines: text
+                    .lines()
+                    .map(|l| format!("{}\n", l))
+                    .collect::<Vec<_>>()
+                    .into_iter(),
+                delay,
+            }
+        }
+    }
+
+    impl Read for SlowReader {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            match self.lines.next() {
+                Some(line) => {
+                    std::thread::sleep(self.delay);
+                    let n = line.len().min(buf.len());
+                    buf[..n].copy_from_slice(&line.as_bytes()[..n]);
+                    Ok(n)
+                }
+                None => Ok(0),
+            }
+        }
+    }
+
+    #[test]
+    fn parse_subtest_duration_from_timing_markers() {
+        let output = "
+PIGLIT TEST: 1 - glsl-fs-swizzle-1
+PIGLIT TEST: 1 - glsl-fs-swizzle-1
+PIGLIT: {\"subtest\": {\"glsl-fs-swizzle-1\" : \"pass\"}}
+PIGLIT TEST: 2 - vs-sign-neg
+PIGLIT: {\"subtest\": {\"vs-sign-neg\" : \"pass\"}}
+";
+        let delay = Duration::from_millis(20);
+        let results = parse_piglit_results(SlowReader::new(output, delay));
+
+        // Each subtest spans at least 2 delayed line-reads (the marker(s),
+        // then the result line), so its measured duration should be at least
+        // that much wall-clock time, not the placeholder zero.
+        assert_eq!(results.subtests.len(), 2);
+        for subtest in &results.subtests {
+            assert!(
+                subtest.duration >= delay,
+                "{} had implausibly short duration {:?}",
+                subtest.name,
+                subtest.duration
+            );
+        }
+    }
+
+    #[test]
+    fn overall_status_all_subtests_pass() {
+        let output = "
+PIGLIT: {\"subtest\": {\"a\" : \"pass\"}}
+PIGLIT: {\"subtest\": {\"b\" : \"pass\"}}
+PIGLIT: {\"result\": \"pass\" }";
+
+        assert_eq!(
+            parse_piglit_results(output.as_bytes()).overall_status(),
+            DeqpStatus::Pass
+        );
+    }
+
+    #[test]
+    fn overall_status_failing_subtest_overrides_final_pass() {
+        // piglit can print a stale top-level "pass" even though one of the
+        // subtests it ran along the way failed.
+        let output = "
+PIGLIT: {\"subtest\": {\"a\" : \"pass\"}}
+PIGLIT: {\"subtest\": {\"b\" : \"fail\"}}
+PIGLIT: {\"result\": \"pass\" }";
+
+        assert_eq!(
+            parse_piglit_results(output.as_bytes()).overall_status(),
+            DeqpStatus::Fail
+        );
+    }
+
+    #[test]
+    fn overall_status_keeps_worse_parent_status() {
+        // A crashing parent shouldn't be downgraded by a subtest that merely warned.
+        let output = "
+PIGLIT: {\"subtest\": {\"a\" : \"warn\"}}";
+
+        let mut r = parse_piglit_results(output.as_bytes());
+        r.status = Some(DeqpStatus::Crash);
+        assert_eq!(r.overall_status(), DeqpStatus::Crash);
+    }
+
+    #[test]
+    fn duplicate_subtest_marks_fail() {
+        let output = "
+PIGLIT: {\"subtest\": {\"a\" : \"pass\"}}
+PIGLIT: {\"subtest\": {\"a\" : \"pass\"}}
+PIGLIT: {\"result\": \"pass\" }";
+
+        let results = parse_piglit_results(output.as_bytes());
+        assert_eq!(
+            results.subtests,
+            vec![DeqpTestResult {
+                name: "a".to_owned(),
+                status: DeqpStatus::Fail,
+                duration: Duration::new(0, 0),
+            }]
+        );
+        assert_eq!(results.overall_status(), DeqpStatus::Fail);
+    }
+
+    #[test]
+    fn hang_signature_overrides_pass() {
+        let output =
+            "Some driver output\n[drm] GPU HANG: ring reset\nPIGLIT: {\"result\": \"pass\" }";
+        let mut results = parse_piglit_results(output.as_bytes());
+        assert_eq!(results.status, Some(DeqpStatus::Pass));
+
+        let signatures = RegexSet::new(["GPU HANG", "Assertion .* failed"]).unwrap();
+        apply_hang_signatures(&mut results, &signatures);
+
+        assert_eq!(results.status, Some(DeqpStatus::Crash));
+        assert_eq!(results.hang_signature.as_deref(), Some("GPU HANG"));
+    }
+
+    #[test]
+    fn hang_signature_no_match_is_untouched() {
+        let output = "PIGLIT: {\"result\": \"pass\" }";
+        let mut results = parse_piglit_results(output.as_bytes());
+
+        let signatures = RegexSet::new(["GPU HANG"]).unwrap();
+        apply_hang_signatures(&mut results, &signatures);
+
+        assert_eq!(results.status, Some(DeqpStatus::Pass));
+        assert_eq!(results.hang_signature, None);
+    }
+
+    #[test]
+    fn parse_json_result() -> Result<()> {
+        let json = r#"{
+            "result": "fail",
+            "subtests": {"b": "pass", "a": "fail"},
+            "out": "line one\nline two",
+            "err": "",
+            "returncode": 1,
+            "time": {"start": 1.0, "end": 3.5}
+        }"#;
+
+        let result = parse_piglit_json_result(json)?;
+        assert_eq!(result.status, Some(DeqpStatus::Fail));
+        assert_eq!(result.duration, Duration::from_secs_f64(2.5));
+        assert_eq!(
+            result.stdout,
+            vec!["line one".to_string(), "line two".to_string()]
+        );
+        assert_eq!(
+            result.subtests,
+            vec![
+                DeqpTestResult {
+                    name: "a".to_owned(),
+                    status: DeqpStatus::Fail,
+                    duration: Duration::new(0, 0),
+                },
+                DeqpTestResult {
+                    name: "b".to_owned(),
+                    status: DeqpStatus::Pass,
+                    duration: Duration::new(0, 0),
+                },
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn parse_results_prefers_json_when_output_is_json() {
+        let json = r#"{"result": "pass", "time": {"start": 0.0, "end": 1.0}}"#;
+        let result = parse_piglit_results(json.as_bytes());
+        assert_eq!(result.status, Some(DeqpStatus::Pass));
+        assert_eq!(result.duration, Duration::from_secs_f64(1.0));
+    }
+
+    fn temp_profile_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("deqp-runner-test-{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn read_profile_file_sniffs_gzip_with_xml_extension() {
+        use flate2::write::GzEncoder;
+        use flate2::Compression;
+
+        let dir = temp_profile_dir("sniff-gz-as-xml");
+        let xml = r#"<Test type="gl" name="foo"><option name="command" value="['foo']" /><option name="run_concurrent" value="True" /></Test>"#;
+
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(xml.as_bytes()).unwrap();
+        let compressed = encoder.finish().unwrap();
+
+        // Deliberately given a plain ".xml" extension, even though its content is gzipped.
+        std::fs::write(dir.join("quick.xml"), compressed).unwrap();
+
+        let content = read_profile_file(&dir, "quick", false).unwrap();
+        assert_eq!(content, xml);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn read_profile_file_sniffs_plain_text_with_gz_extension() {
+        let dir = temp_profile_dir("sniff-plain-as-gz");
+        let xml = r#"<Test type="gl" name="foo"><option name="command" value="['foo']" /><option name="run_concurrent" value="True" /></Test>"#;
+
+        // Deliberately given a ".xml.gz" extension despite being uncompressed.
+        std::fs::write(dir.join("quick.xml.gz"), xml).unwrap();
+
+        let content = read_profile_file(&dir, "quick", false).unwrap();
+        assert_eq!(content, xml);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn parse_piglit_profile_extracts_flat_test_list() {
+        let dir = temp_profile_dir("profile-wrapper");
+        let xml = r#"
+        <Test type="gl" name="fast_color_clear@fcc-read-after-clear blit rb">
+        <option name="command" value="['fcc-read-after-clear', 'blit', 'rb']" />
+        <option name="run_concurrent" value="True" />
+        </Test>"#;
+        std::fs::write(dir.join("quick.xml"), xml).unwrap();
+
+        let tests =
+            parse_piglit_profile(&dir, "quick", false, DEFAULT_MULTI_SHADER_GROUP_SIZE).unwrap();
+
+        assert_eq!(
+            tests,
+            vec![crate::PiglitTest {
+                name: "fast_color_clear@fcc-read-after-clear blit rb".to_string(),
+                binary: "fcc-read-after-clear".to_string(),
+                args: vec![
+                    "blit".to_string(),
+                    "rb".to_string(),
+                    "-auto".to_string(),
+                    "-fbo".to_string(),
+                ],
+            }]
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
 }
@@ -1,18 +1,22 @@
 use crate::parse_deqp::{DeqpStatus, DeqpTestResult};
 use crate::parse_piglit::{
-    parse_piglit_results_with_timeout, piglit_sanitize_test_name, PiglitTestResult,
+    apply_hang_signatures, parse_piglit_results_with_timeout, piglit_sanitize_test_name,
+    PiglitTestResult,
+};
+use crate::parse_piglit::{
+    parse_piglit_xml_testlist, read_profile_file, DEFAULT_MULTI_SHADER_GROUP_SIZE,
 };
-use crate::parse_piglit::{parse_piglit_xml_testlist, read_profile_file};
 use crate::{
-    parse_regex_set, runner_results::*, runner_thread_index, SubRunConfig, TestConfiguration,
+    load_shard_timings, parse_regex_set, runner_results::*, runner_thread_index, select_tests,
+    shard_tests, SubRunConfig, TestConfiguration,
 };
 use crate::{TestCase, TestCommand};
 use anyhow::{Context, Result};
 use log::*;
-use serde::Deserialize;
+use regex::{Regex, RegexSet};
+use serde::{Deserialize, Serialize};
 use std::fs::File;
 use std::io::prelude::*;
-use std::io::BufReader;
 use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
 use structopt::StructOpt;
@@ -21,6 +25,9 @@ pub struct PiglitCommand {
     pub config: TestConfiguration,
     pub piglit_folder: PathBuf,
     pub prefix: String,
+    pub isolate_crashing_multi_shader_groups: bool,
+    pub hang_signatures: RegexSet,
+    pub expected_output: Vec<CompiledExpectedOutputRule>,
 }
 
 // Common structure for configuring a piglit run between Run (single run) and deqp-runner Suite (muliple Runs)
@@ -35,6 +42,158 @@ pub struct PiglitRunConfig {
     #[structopt(long = "process-isolation")]
     #[serde(default)]
     pub process_isolation: bool,
+
+    #[structopt(
+        long,
+        default_value = "100",
+        help = "Number of .shader_test files batched into each shader_runner invocation by a multi_shader test group (smaller isolates crashes better, larger amortizes process startup)"
+    )]
+    #[serde(default = "default_multi_shader_group_size")]
+    pub multi_shader_group_size: usize,
+
+    #[structopt(
+        long,
+        help = "When a multi_shader group crashes, re-run its shader_test files one at a time to attribute the crash to a specific shader, instead of just failing the whole group"
+    )]
+    #[serde(default)]
+    pub isolate_crashing_multi_shader_groups: bool,
+
+    #[structopt(
+        long = "hang-signature",
+        help = "Regex to scan captured test stdout for a GPU-hang/crash signature (e.g. a kernel ring-reset message or driver assertion); a match forces the test to report Crash and records which signature fired. May be repeated."
+    )]
+    #[serde(default)]
+    pub hang_signatures: Vec<String>,
+}
+
+fn default_multi_shader_group_size() -> usize {
+    DEFAULT_MULTI_SHADER_GROUP_SIZE
+}
+
+// Recovers the list of .shader_test files batched into a multi_shader group's
+// shader_runner invocation (see `parse_piglit_xml_testlist`'s multi_shader
+// branch, which always appends exactly "-auto" and "-fbo" after the files).
+// Returns None for anything that isn't a multi-file shader_runner group,
+// since isolating a single-shader group wouldn't attribute anything new.
+fn multi_shader_files(test: &PiglitTest) -> Option<Vec<&str>> {
+    if test.binary != "shader_runner" {
+        return None;
+    }
+
+    let files: Vec<&str> = test
+        .args
+        .iter()
+        .map(String::as_str)
+        .filter(|a| *a != "-auto" && *a != "-fbo")
+        .collect();
+
+    if files.len() > 1 {
+        Some(files)
+    } else {
+        None
+    }
+}
+
+// A single `[[piglit.expected_output]]` rule: applies to any test whose name
+// matches `test`, and forces the test to report Fail if a required pattern
+// never appeared in its captured stdout/stderr, or a forbidden one did. Lets
+// CI gate on diagnostic output (e.g. a driver fallback warning) that a
+// test's own exit status/PIGLIT: result line doesn't capture.
+#[derive(Debug, Deserialize)]
+pub struct ExpectedOutputRule {
+    pub test: String,
+
+    #[serde(default)]
+    pub stdout_required: Vec<String>,
+    #[serde(default)]
+    pub stdout_forbidden: Vec<String>,
+    #[serde(default)]
+    pub stderr_required: Vec<String>,
+    #[serde(default)]
+    pub stderr_forbidden: Vec<String>,
+}
+
+// Compiled form of `ExpectedOutputRule`, resolved once per `PiglitCommand`
+// instead of recompiling every test's regexes on every invocation.
+pub struct CompiledExpectedOutputRule {
+    test: Regex,
+    stdout_required: RegexSet,
+    stdout_forbidden: RegexSet,
+    stderr_required: RegexSet,
+    stderr_forbidden: RegexSet,
+}
+
+impl CompiledExpectedOutputRule {
+    pub fn compile(rule: &ExpectedOutputRule) -> Result<CompiledExpectedOutputRule> {
+        Ok(CompiledExpectedOutputRule {
+            test: Regex::new(&rule.test)
+                .with_context(|| format!("compiling expected_output test regex '{}'", rule.test))?,
+            stdout_required: parse_regex_set(&rule.stdout_required)
+                .context("compiling expected_output stdout_required")?,
+            stdout_forbidden: parse_regex_set(&rule.stdout_forbidden)
+                .context("compiling expected_output stdout_forbidden")?,
+            stderr_required: parse_regex_set(&rule.stderr_required)
+                .context("compiling expected_output stderr_required")?,
+            stderr_forbidden: parse_regex_set(&rule.stderr_forbidden)
+                .context("compiling expected_output stderr_forbidden")?,
+        })
+    }
+}
+
+// Patterns in `set` that never matched any line of `output` -- what a
+// "required" rule is still missing.
+fn unmatched_patterns<'a>(set: &'a RegexSet, output: &[String]) -> Vec<&'a str> {
+    let mut seen = vec![false; set.len()];
+    for line in output {
+        for idx in set.matches(line) {
+            seen[idx] = true;
+        }
+    }
+    set.patterns()
+        .iter()
+        .zip(seen)
+        .filter(|(_, seen)| !seen)
+        .map(|(pattern, _)| pattern.as_str())
+        .collect()
+}
+
+// The first pattern in `set` (a "forbidden" rule) that matched a line of `output`.
+fn first_matched_pattern<'a>(set: &'a RegexSet, output: &[String]) -> Option<&'a str> {
+    output
+        .iter()
+        .find_map(|line| set.matches(line).into_iter().next())
+        .map(|idx| set.patterns()[idx].as_str())
+}
+
+// Checks `stdout`/`stderr` against every rule whose `test` regex matches
+// `test_name`, returning a description of the first violation found, or None
+// if every matching rule was satisfied.
+fn check_expected_output(
+    rules: &[CompiledExpectedOutputRule],
+    test_name: &str,
+    stdout: &[String],
+    stderr: &[String],
+) -> Option<String> {
+    for rule in rules {
+        if !rule.test.is_match(test_name) {
+            continue;
+        }
+
+        if let Some(pattern) = unmatched_patterns(&rule.stdout_required, stdout).first() {
+            return Some(format!("required stdout pattern '{}' not found", pattern));
+        }
+        if let Some(pattern) = first_matched_pattern(&rule.stdout_forbidden, stdout) {
+            return Some(format!("forbidden stdout pattern '{}' matched", pattern));
+        }
+        if let Some(pattern) = unmatched_patterns(&rule.stderr_required, stderr).first() {
+            return Some(format!("required stderr pattern '{}' not found", pattern));
+        }
+        if let Some(pattern) = first_matched_pattern(&rule.stderr_forbidden, stderr) {
+            return Some(format!("forbidden stderr pattern '{}' matched", pattern));
+        }
+    }
+
+    None
 }
 
 #[derive(Deserialize)]
@@ -47,9 +206,16 @@ pub struct PiglitTomlConfig {
 
     #[serde(default)]
     pub prefix: String,
+
+    #[serde(default)]
+    pub expected_output: Vec<ExpectedOutputRule>,
 }
 
 impl PiglitTomlConfig {
+    // The profile's fixed test order is shuffled with a seeded Fisher-Yates
+    // shuffle inside `split_tests_to_groups` below when --shuffle/
+    // --shuffle-seed is set (see `SubRunConfig::shuffle_seed`), so
+    // order-dependent flakes across piglit binaries can be reproduced.
     pub fn test_groups<'d>(
         &self,
         piglit: &'d PiglitCommand,
@@ -71,16 +237,16 @@ impl PiglitTomlConfig {
             &self.piglit_config.profile,
             self.piglit_config.process_isolation,
         )?;
-        let tests: Vec<TestCase> =
-            parse_piglit_xml_testlist(&test_folder, &text, self.piglit_config.process_isolation)
-                .with_context(|| {
-                    format!("reading piglit profile '{}'", &self.piglit_config.profile)
-                })?
-                .into_iter()
-                .skip(self.sub_config.fraction_start - 1)
-                .step_by(self.sub_config.fraction)
-                .filter(|test| include_filters.iter().all(|x| x.is_match(test.name())))
-                .collect();
+        let tests = parse_piglit_xml_testlist(
+            &test_folder,
+            &text,
+            self.piglit_config.process_isolation,
+            self.piglit_config.multi_shader_group_size,
+        )
+        .with_context(|| format!("reading piglit profile '{}'", &self.piglit_config.profile))?;
+        let shard_timings = load_shard_timings(&self.sub_config)?;
+        let tests = shard_tests(tests, self.sub_config.shard, shard_timings.as_ref());
+        let tests = select_tests(tests, &self.sub_config, &include_filters)?;
 
         println!(
             "Running {} piglit tests on {} threads",
@@ -101,7 +267,42 @@ pub struct PiglitTest {
     pub args: Vec<String>,
 }
 
+// A standalone reproduction of a single `PiglitCommand::run()` invocation,
+// written next to `piglit.<name>.log` whenever that log is saved. Turns the
+// opaque "See ...log" hint into a one-command repro: `piglit replay
+// <file>` reconstructs this exact `Command` and runs it in the foreground
+// with inherited stdio, so a developer can attach a debugger to it.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PiglitReplay {
+    pub binary: PathBuf,
+    pub args: Vec<String>,
+    pub current_dir: PathBuf,
+    pub env: Vec<(String, String)>,
+}
+
+impl PiglitReplay {
+    pub fn load(path: &Path) -> Result<PiglitReplay> {
+        let data = std::fs::read_to_string(path)
+            .with_context(|| format!("reading replay file {}", path.display()))?;
+        serde_json::from_str(&data)
+            .with_context(|| format!("parsing replay file {}", path.display()))
+    }
+
+    pub fn run(&self) -> Result<std::process::ExitStatus> {
+        Command::new(&self.binary)
+            .args(&self.args)
+            .current_dir(&self.current_dir)
+            .envs(self.env.iter().cloned())
+            .status()
+            .with_context(|| format!("Failed to spawn {}", self.binary.display()))
+    }
+}
+
 impl TestCommand for PiglitCommand {
+    // Note: a stable-pass result recorded under --result-cache-dir (see
+    // `TestCommand::split_cached_tests`) is served before `run` is ever
+    // called for a given test, so this spawn is skipped entirely on a cache
+    // hit against an unchanged --environment-fingerprint.
     fn run(
         &self,
         caselist_state: &CaselistState,
@@ -126,6 +327,8 @@ impl TestCommand for PiglitCommand {
             .output_dir
             .join(format!("piglit.{}.log", test.name).as_str());
 
+        let thread_index = runner_thread_index()?.to_string();
+
         let mut command = Command::new(bin_path.join(Path::new(&test.binary)));
         command
             .current_dir(&self.piglit_folder)
@@ -134,18 +337,40 @@ impl TestCommand for PiglitCommand {
             .stdin(Stdio::null())
             .args(&test.args)
             .env("MESA_DEBUG", "silent")
-            .env("DEQP_RUNNER_THREAD", runner_thread_index()?.to_string())
+            .env("DEQP_RUNNER_THREAD", &thread_index)
             .env("PIGLIT_SOURCE_DIR", &self.piglit_folder)
             .envs(self.config.env.iter());
+        crate::set_process_group(&mut command);
 
         let command_line = format!("{:?}", command);
 
+        let replay = PiglitReplay {
+            binary: bin_path.join(Path::new(&test.binary)),
+            args: test.args.clone(),
+            current_dir: self.piglit_folder.clone(),
+            env: vec![
+                ("MESA_DEBUG".to_owned(), "silent".to_owned()),
+                ("DEQP_RUNNER_THREAD".to_owned(), thread_index),
+                (
+                    "PIGLIT_SOURCE_DIR".to_owned(),
+                    self.piglit_folder.display().to_string(),
+                ),
+            ]
+            .into_iter()
+            .chain(self.config.env.iter().map(|(k, v)| (k.clone(), v.clone())))
+            .collect(),
+        };
+        let replay_path = self
+            .config
+            .output_dir
+            .join(format!("piglit.{}.replay.json", test.name).as_str());
+
         let mut stderr = Vec::new();
         let mut status = None;
 
         debug!("Begin test {}", test.name);
 
-        let piglit_result = match command
+        let mut piglit_result = match command
             .spawn()
             .with_context(|| format!("Failed to spawn {}", &test.binary))
         {
@@ -155,8 +380,10 @@ impl TestCommand for PiglitCommand {
                 let mut r = parse_piglit_results_with_timeout(stdout, self.config.timeout);
 
                 // The child should have run to completion based on parse_piglit_results()
-                // consuming its output, but if we had a timeout then we want to kill this run.
-                let _ = child.kill();
+                // consuming its output, but if we had a timeout then we want to kill this
+                // run, process group and all, so any helper process it forked doesn't
+                // outlive it.
+                crate::kill_child_process_group(&mut child);
 
                 // Make sure we reap the child process.
                 let child_status = child.wait();
@@ -177,11 +404,16 @@ impl TestCommand for PiglitCommand {
                     };
                 }
 
-                for line in BufReader::new(child.stderr.as_mut().context("opening stderr")?)
-                    .lines()
-                    .flatten()
-                {
-                    stderr.push(line);
+                let (captured_stderr, stderr_truncated) = crate::read_bounded_lines(
+                    child.stderr.as_mut().context("opening stderr")?,
+                    crate::CAPTURE_BYTE_LIMIT,
+                );
+                stderr = captured_stderr;
+                if stderr_truncated {
+                    warn!(
+                        "stderr for {} exceeded the capture limit and was truncated",
+                        test.name
+                    );
                 }
 
                 r
@@ -191,19 +423,111 @@ impl TestCommand for PiglitCommand {
                 duration: std::time::Duration::new(0, 0),
                 subtests: Vec::new(),
                 stdout: vec![format!("Error spawning piglit command: {:?}", e)],
+                hang_signature: None,
             },
         };
 
+        if !self.hang_signatures.is_empty() {
+            apply_hang_signatures(&mut piglit_result, &self.hang_signatures);
+            if let Some(signature) = &piglit_result.hang_signature {
+                error!(
+                    "Test {} matched hang signature '{}', reporting Crash",
+                    test.name, signature
+                );
+            }
+        }
+
         let mut results = Vec::new();
-        let translated_result = self.translate_result(
+        let mut translated_result = self.translate_result(
             &DeqpTestResult {
                 name: test.name.to_owned(),
-                status: piglit_result.status.unwrap_or(DeqpStatus::Crash),
+                status: piglit_result.overall_status(),
                 duration: piglit_result.duration,
             },
             caselist_state,
         );
 
+        let expected_output_violation = check_expected_output(
+            &self.expected_output,
+            &test.name,
+            &piglit_result.stdout,
+            &stderr,
+        );
+        if let Some(violation) = &expected_output_violation {
+            error!(
+                "Test {} violated an expected_output rule ({}), reporting Fail",
+                test.name, violation
+            );
+            translated_result = RunnerStatus::Fail;
+        }
+
+        // A multi_shader group is a single shader_runner invocation batching many
+        // .shader_test files together; a crash there only tells us the group was bad,
+        // not which shader caused it. Re-running one shader at a time is slower, but
+        // turns "the batch crashed" into "this specific shader crashed".
+        if self.isolate_crashing_multi_shader_groups
+            && piglit_result.overall_status() == DeqpStatus::Crash
+        {
+            if let Some(files) = multi_shader_files(test) {
+                info!(
+                    "{} crashed as a group of {} shaders; re-running them individually to attribute the crash",
+                    test.name,
+                    files.len()
+                );
+
+                for file in files {
+                    let sub_name = format!("{}@{}", test.name, file);
+                    let mut sub_command = Command::new(bin_path.join(Path::new(&test.binary)));
+                    sub_command
+                        .current_dir(&self.piglit_folder)
+                        .stdout(Stdio::piped())
+                        .stderr(Stdio::null())
+                        .stdin(Stdio::null())
+                        .args(&[file, "-auto", "-fbo"])
+                        .env("MESA_DEBUG", "silent")
+                        .env("DEQP_RUNNER_THREAD", runner_thread_index()?.to_string())
+                        .env("PIGLIT_SOURCE_DIR", &self.piglit_folder)
+                        .envs(self.config.env.iter());
+                    crate::set_process_group(&mut sub_command);
+
+                    let sub_status = match sub_command.spawn() {
+                        Ok(mut child) => match child.stdout.take() {
+                            Some(stdout) => {
+                                let r =
+                                    parse_piglit_results_with_timeout(stdout, self.config.timeout);
+                                crate::kill_child_process_group(&mut child);
+                                let mut status = r.overall_status();
+                                if let Ok(s) = child.wait() {
+                                    match s.code() {
+                                        Some(0) | Some(1) => {}
+                                        _ => status = DeqpStatus::Crash,
+                                    }
+                                }
+                                status
+                            }
+                            None => DeqpStatus::Crash,
+                        },
+                        Err(_) => DeqpStatus::Crash,
+                    };
+
+                    results.push(RunnerResult {
+                        test: sub_name.clone(),
+                        status: self.translate_result(
+                            &DeqpTestResult {
+                                name: sub_name,
+                                status: sub_status,
+                                duration: std::time::Duration::new(0, 0),
+                            },
+                            caselist_state,
+                        ),
+                        duration: 0.0,
+                        subtest: true,
+                        flake_retries: 0,
+                    });
+                }
+            }
+        }
+
         for subtest in &piglit_result.subtests {
             let subtest_name =
                 format!("{}@{}", test.name, piglit_sanitize_test_name(&subtest.name));
@@ -227,6 +551,7 @@ impl TestCommand for PiglitCommand {
                 ),
                 duration: subtest.duration.as_secs_f32(),
                 subtest: true,
+                flake_retries: 0,
             });
         }
 
@@ -235,6 +560,12 @@ impl TestCommand for PiglitCommand {
         {
             let mut file = File::create(log_path).context("opening log file")?;
 
+            serde_json::to_writer_pretty(
+                File::create(&replay_path).context("opening replay file")?,
+                &replay,
+            )
+            .context("writing replay file")?;
+
             fn write_output(file: &mut File, name: &str, out: &[String]) -> Result<()> {
                 if out.is_empty() {
                     writeln!(file, "{}: (empty)", name)?;
@@ -252,9 +583,13 @@ impl TestCommand for PiglitCommand {
             || -> Result<()> {
                 writeln!(file, "test: {}", test.name)?;
                 writeln!(file, "command: {}", command_line)?;
+                writeln!(file, "replay: piglit replay {}", replay_path.display())?;
                 if let Some(status) = status {
                     writeln!(file, "exit status: {}", status)?;
                 }
+                if let Some(violation) = &expected_output_violation {
+                    writeln!(file, "expected_output violation: {}", violation)?;
+                }
                 write_output(&mut file, "stdout", &piglit_result.stdout)?;
                 write_output(&mut file, "stderr", &stderr)?;
                 Ok(())
@@ -267,6 +602,7 @@ impl TestCommand for PiglitCommand {
             status: translated_result,
             duration: piglit_result.duration.as_secs_f32(),
             subtest: false,
+            flake_retries: 0,
         });
 
         debug!("End test {}", test.name);
@@ -0,0 +1,221 @@
+// Content-addressed cache for test results, borrowed from the
+// compiler-wrapper caching model: hash the inputs (test name + environment
+// fingerprint), reuse the stored output on a hit. Only stable passes are
+// ever served from cache (see `TestCommand::split_cached_tests` in lib.rs);
+// misses and anything that wasn't a clean pass always re-run, so a
+// regression or a flake can never hide behind a stale entry.
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::parse_deqp::DeqpStatus;
+
+/// The subset of a test's result worth keeping around after the process that
+/// produced it has exited.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CachedResult {
+    pub status: DeqpStatus,
+    pub duration: Duration,
+}
+
+/// Storage backend for cached results, keyed by the opaque digest `cache_key`
+/// produces. Implementations just need to round-trip a `CachedResult` under
+/// a key; deqp-runner doesn't care where that lives.
+pub trait ResultCacheStore: Send + Sync {
+    fn get(&self, key: &str) -> Result<Option<CachedResult>>;
+    fn put(&self, key: &str, result: &CachedResult) -> Result<()>;
+}
+
+/// Hashes a test's full name together with the caller-supplied environment
+/// fingerprint (driver version, GPU PCI id, build hash, ...) into a stable
+/// cache key, so a cached result is only ever reused for the exact
+/// test+environment pair that produced it.
+pub fn cache_key(test_name: &str, environment_fingerprint: &str) -> String {
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
+
+    let mut hasher = DefaultHasher::new();
+    test_name.hash(&mut hasher);
+    0u8.hash(&mut hasher); // separator, so ("ab", "c") and ("a", "bc") don't collide
+    environment_fingerprint.hash(&mut hasher);
+    format!("{:016x}", hasher.finish())
+}
+
+/// Local-filesystem result cache: one small JSON file per cache key under
+/// `dir`. The default, zero-setup backend -- good for a single persistent
+/// CI runner's local disk, or a directory shared over NFS between runners.
+pub struct FilesystemResultCache {
+    dir: PathBuf,
+}
+
+impl FilesystemResultCache {
+    pub fn new(dir: impl Into<PathBuf>) -> Result<FilesystemResultCache> {
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir)
+            .with_context(|| format!("creating result cache directory {:?}", dir))?;
+        Ok(FilesystemResultCache { dir })
+    }
+
+    fn entry_path(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", key))
+    }
+}
+
+impl ResultCacheStore for FilesystemResultCache {
+    fn get(&self, key: &str) -> Result<Option<CachedResult>> {
+        let path = self.entry_path(key);
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let data = std::fs::read_to_string(&path)
+            .with_context(|| format!("reading result cache entry {:?}", path))?;
+        Ok(Some(serde_json::from_str(&data).with_context(|| {
+            format!("parsing result cache entry {:?}", path)
+        })?))
+    }
+
+    fn put(&self, key: &str, result: &CachedResult) -> Result<()> {
+        let path = self.entry_path(key);
+        let data = serde_json::to_string(result).context("serializing result cache entry")?;
+        std::fs::write(&path, data)
+            .with_context(|| format!("writing result cache entry {:?}", path))
+    }
+}
+
+/// Minimal GET/PUT object-storage interface an S3-compatible (or GCS,
+/// Azure Blob, etc.) backend needs to implement to back a
+/// `ResultCacheStore`, kept separate from any particular SDK so this crate
+/// doesn't have to depend on one -- there's no object-storage client
+/// vendored alongside deqp-runner, so callers bring their own (e.g.
+/// wrapping `aws-sdk-s3`, `rusoto`, or a plain HTTP PUT/GET client) rather
+/// than deqp-runner shipping one.
+pub trait ObjectStoreClient: Send + Sync {
+    fn get_object(&self, key: &str) -> Result<Option<Vec<u8>>>;
+    fn put_object(&self, key: &str, data: &[u8]) -> Result<()>;
+}
+
+/// Object-storage-backed result cache, for sharing a result cache across a
+/// fleet of CI runners rather than keeping it on one machine's local disk.
+pub struct ObjectStoreResultCache<C: ObjectStoreClient> {
+    client: C,
+    prefix: String,
+}
+
+impl<C: ObjectStoreClient> ObjectStoreResultCache<C> {
+    pub fn new(client: C, prefix: impl Into<String>) -> ObjectStoreResultCache<C> {
+        ObjectStoreResultCache {
+            client,
+            prefix: prefix.into(),
+        }
+    }
+
+    fn object_key(&self, key: &str) -> String {
+        format!("{}/{}.json", self.prefix.trim_end_matches('/'), key)
+    }
+}
+
+impl<C: ObjectStoreClient> ResultCacheStore for ObjectStoreResultCache<C> {
+    fn get(&self, key: &str) -> Result<Option<CachedResult>> {
+        match self.client.get_object(&self.object_key(key))? {
+            Some(data) => Ok(Some(
+                serde_json::from_slice(&data).context("parsing result cache object")?,
+            )),
+            None => Ok(None),
+        }
+    }
+
+    fn put(&self, key: &str, result: &CachedResult) -> Result<()> {
+        let data = serde_json::to_vec(result).context("serializing result cache object")?;
+        self.client.put_object(&self.object_key(key), &data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cache_key_is_stable() {
+        assert_eq!(
+            cache_key("deqp-gles3.functional.foo", "driver-1.2.3"),
+            cache_key("deqp-gles3.functional.foo", "driver-1.2.3")
+        );
+    }
+
+    #[test]
+    fn cache_key_distinguishes_test_and_fingerprint_boundary() {
+        assert_ne!(cache_key("ab", "c"), cache_key("a", "bc"));
+    }
+
+    #[test]
+    fn cache_key_distinguishes_environment() {
+        assert_ne!(
+            cache_key("deqp-gles3.functional.foo", "driver-1.2.3"),
+            cache_key("deqp-gles3.functional.foo", "driver-1.2.4")
+        );
+    }
+
+    #[test]
+    fn filesystem_cache_round_trips() -> Result<()> {
+        let dir = std::env::temp_dir().join(format!(
+            "deqp-runner-test-result-cache-{}",
+            std::process::id()
+        ));
+        let cache = FilesystemResultCache::new(&dir)?;
+
+        let key = cache_key("deqp-gles3.functional.foo", "driver-1.2.3");
+        assert_eq!(cache.get(&key)?, None);
+
+        let result = CachedResult {
+            status: DeqpStatus::Pass,
+            duration: Duration::from_millis(1234),
+        };
+        cache.put(&key, &result)?;
+        assert_eq!(cache.get(&key)?, Some(result));
+
+        std::fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    struct MockObjectStore {
+        objects: std::sync::Mutex<std::collections::HashMap<String, Vec<u8>>>,
+    }
+
+    impl ObjectStoreClient for MockObjectStore {
+        fn get_object(&self, key: &str) -> Result<Option<Vec<u8>>> {
+            Ok(self.objects.lock().unwrap().get(key).cloned())
+        }
+
+        fn put_object(&self, key: &str, data: &[u8]) -> Result<()> {
+            self.objects
+                .lock()
+                .unwrap()
+                .insert(key.to_owned(), data.to_owned());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn object_store_cache_round_trips() -> Result<()> {
+        let cache = ObjectStoreResultCache::new(
+            MockObjectStore {
+                objects: std::sync::Mutex::new(std::collections::HashMap::new()),
+            },
+            "deqp-runner-results",
+        );
+
+        let key = cache_key("deqp-gles3.functional.foo", "driver-1.2.3");
+        assert_eq!(cache.get(&key)?, None);
+
+        let result = CachedResult {
+            status: DeqpStatus::Pass,
+            duration: Duration::from_millis(1234),
+        };
+        cache.put(&key, &result)?;
+        assert_eq!(cache.get(&key)?, Some(result));
+
+        Ok(())
+    }
+}
@@ -1,16 +1,125 @@
 use crate::parse_deqp::DeqpStatus;
 use anyhow::{Context, Result};
 use log::*;
-use regex::Regex;
+use serde::Serialize;
 use std::borrow::Borrow;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::fmt;
 use std::io::prelude::*;
-use std::io::{BufReader, BufWriter};
+use std::io::BufWriter;
 use std::str::FromStr;
 use std::time::{Duration, Instant};
 use structopt::StructOpt;
 
+// Quotes `field` per RFC 4180 if it contains a comma, double-quote, or
+// newline (e.g. a piglit subtest name like "GL_INTENSITY16, swizzled,
+// border color only"), doubling any embedded double-quotes. Used for the
+// `test` field of results.csv/failures.csv/baseline files so such names
+// round-trip losslessly instead of being split into phantom extra columns.
+fn csv_quote_field(field: &str) -> String {
+    if field.contains(',') || field.contains('"') || field.contains('\n') {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
+
+// Nearest-rank percentile (p in [0.0, 1.0]) over an already-ascending-sorted
+// slice, for RunnerResults::print_summary's duration stats.
+fn percentile(sorted: &[f32], p: f64) -> f32 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+    let rank = (((sorted.len() - 1) as f64) * p).round() as usize;
+    sorted[rank.min(sorted.len() - 1)]
+}
+
+// Splits a dotted test name into (classname, leaf name) for JUnit's
+// classname/name attributes, e.g. "dEQP-GLES2.test.p.0" splits into
+// ("dEQP-GLES2.test.p", "0") so viewers that group by classname show the
+// caselist as a tree instead of one flat list.
+fn split_classname(test: &str) -> (String, String) {
+    match test.rfind('.') {
+        Some(idx) => (test[..idx].to_owned(), test[idx + 1..].to_owned()),
+        None => (String::new(), test.to_owned()),
+    }
+}
+
+// Splits the contents of a results/baseline CSV into records, so that a
+// quoted field's embedded literal newline (see `csv_quote_field`) doesn't get
+// mistaken for a record boundary the way splitting on `\n` up front would.
+// Tracks whether we're inside a quoted field purely by the parity of `"`
+// seen so far: a doubled `""` escape (see `parse_csv_line`) flips it twice,
+// leaving it correctly unchanged.
+fn split_csv_records(contents: &str) -> Vec<String> {
+    let mut records = Vec::new();
+    let mut record = String::new();
+    let mut in_quotes = false;
+
+    for c in contents.chars() {
+        match c {
+            '"' => {
+                in_quotes = !in_quotes;
+                record.push(c);
+            }
+            '\r' if !in_quotes => {}
+            '\n' if !in_quotes => records.push(std::mem::take(&mut record)),
+            c => record.push(c),
+        }
+    }
+    if !record.is_empty() {
+        records.push(record);
+    }
+
+    records
+}
+
+// Splits one record of a results/baseline CSV (as produced by
+// `split_csv_records`) into its fields per RFC 4180.
+fn parse_csv_line(line: &str) -> Result<Vec<String>> {
+    let mut fields = Vec::new();
+    let mut chars = line.chars().peekable();
+
+    loop {
+        let mut field = String::new();
+        if chars.peek() == Some(&'"') {
+            chars.next();
+            loop {
+                match chars.next() {
+                    Some('"') if chars.peek() == Some(&'"') => {
+                        field.push('"');
+                        chars.next();
+                    }
+                    Some('"') => break,
+                    Some(c) => field.push(c),
+                    None => anyhow::bail!("unterminated quoted field in CSV line: {}", line),
+                }
+            }
+        } else {
+            while let Some(&c) = chars.peek() {
+                if c == ',' {
+                    break;
+                }
+                field.push(c);
+                chars.next();
+            }
+        }
+        fields.push(field);
+
+        match chars.next() {
+            Some(',') => continue,
+            None => break,
+            Some(c) => anyhow::bail!(
+                "unexpected character '{}' after field in CSV line: {}",
+                c,
+                line
+            ),
+        }
+    }
+
+    Ok(fields)
+}
+
 // Wrapper for displaying a duration in h:m:s (integer seconds, rounded down)
 struct HMSDuration(Duration);
 impl fmt::Display for HMSDuration {
@@ -31,7 +140,7 @@ impl fmt::Display for HMSDuration {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
 pub enum RunnerStatus {
     Pass,
     Fail,
@@ -94,12 +203,14 @@ impl RunnerStatus {
             DeqpStatus::Fail
             | DeqpStatus::ResourceError
             | DeqpStatus::InternalError
+            | DeqpStatus::DmesgFail
             | DeqpStatus::Pending => RunnerStatus::Fail,
-            DeqpStatus::Crash => RunnerStatus::Crash,
+            DeqpStatus::Crash | DeqpStatus::Incomplete => RunnerStatus::Crash,
             DeqpStatus::NotSupported => RunnerStatus::Skip,
-            DeqpStatus::CompatibilityWarning | DeqpStatus::QualityWarning | DeqpStatus::Waiver => {
-                RunnerStatus::Warn
-            }
+            DeqpStatus::CompatibilityWarning
+            | DeqpStatus::QualityWarning
+            | DeqpStatus::DmesgWarning
+            | DeqpStatus::Waiver => RunnerStatus::Warn,
             DeqpStatus::Timeout => RunnerStatus::Timeout,
         }
     }
@@ -176,6 +287,9 @@ pub struct RunnerResult {
     pub status: RunnerStatus,
     pub duration: f32,
     pub subtest: bool,
+    /// How many isolated reruns (see `--flake-retries`) it took before this result
+    /// came back passing. Zero unless `status` is `Flake` and a retry was involved.
+    pub flake_retries: u32,
 }
 
 // For comparing equality, we ignore the test runtime (particularly of use for the unit tests )
@@ -214,7 +328,7 @@ pub struct CaselistState {
     pub run_id: u32,
 }
 
-#[derive(Default, PartialEq, Debug)]
+#[derive(Default, PartialEq, Debug, Serialize)]
 pub struct ResultCounts {
     pub pass: u32,
     pub fail: u32,
@@ -247,6 +361,23 @@ impl ResultCounts {
         }
     }
 
+    // Undoes an `increment`, for when a carried-forward resumed result is
+    // superseded by a freshly-run one (see `RunnerResults::record_result`).
+    pub fn decrement(&mut self, s: RunnerStatus) {
+        match s {
+            RunnerStatus::Pass => self.pass -= 1,
+            RunnerStatus::Fail => self.fail -= 1,
+            RunnerStatus::Skip => self.skip -= 1,
+            RunnerStatus::Crash => self.crash -= 1,
+            RunnerStatus::Warn => self.warn -= 1,
+            RunnerStatus::Flake => self.flake -= 1,
+            RunnerStatus::Missing => self.missing -= 1,
+            RunnerStatus::ExpectedFail => self.expected_fail -= 1,
+            RunnerStatus::UnexpectedPass => self.unexpected_pass -= 1,
+            RunnerStatus::Timeout => self.timeout -= 1,
+        }
+    }
+
     pub fn get_count(&self, status: RunnerStatus) -> u32 {
         use RunnerStatus::*;
 
@@ -289,41 +420,885 @@ impl fmt::Display for ResultCounts {
     }
 }
 
+impl ResultCounts {
+    // Tests that didn't come back as one of the "successful" RunnerStatus variants
+    // (see RunnerStatus::is_success), for the progress line's "fails: N".
+    pub fn fail_count(&self) -> u32 {
+        self.fail + self.crash + self.missing + self.unexpected_pass + self.timeout
+    }
+
+    // Hard failures only, excluding unexpected_pass: for RunnerResults::outcome,
+    // which needs to tell "the driver is broken" (a real Fail/Crash/Missing/
+    // Timeout) apart from "expectations are stale" (only UnexpectedPass).
+    fn hard_fail_count(&self) -> u32 {
+        self.fail + self.crash + self.missing + self.timeout
+    }
+}
+
+/// A run's overall classification, following Fuchsia's run-test-suite
+/// `Outcome`: distinguishes a clean pass from the shades of "not a clean
+/// pass" that `is_success`'s bool collapses, so a caller can map each to a
+/// distinct process exit code instead of just 0/1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum Outcome {
+    /// Every test passed (or was an expected failure/skip), and the run saw
+    /// every test in the caselist.
+    Passed,
+    /// At least one hard failure (Fail/Crash/Missing/Timeout).
+    Failed,
+    /// No hard failures, but at least one test passed that the baseline
+    /// expected to fail -- the driver improved, but expectations are stale.
+    UnexpectedPass,
+    /// At least one test timed out, which usually means the harness itself
+    /// wedged rather than any single test being at fault.
+    Timedout,
+    /// Fewer results were recorded than the caselist contains, so the run
+    /// didn't finish (e.g. the runner crashed outright).
+    Inconclusive,
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Outcome::Passed => "Passed",
+            Outcome::Failed => "Failed",
+            Outcome::UnexpectedPass => "UnexpectedPass",
+            Outcome::Timedout => "Timedout",
+            Outcome::Inconclusive => "Inconclusive",
+        })
+    }
+}
+
+// Live "[mm:ss] completed/total tests (NN%), R tests/s, ETA mm:ss, fails: N" status line
+// printed by results_collection as groups complete. Workers bump `completed` as each of
+// their groups finishes; the collection thread polls it to redraw the line, throttled to
+// avoid spamming a non-terminal output (e.g. a CI log) with a line per group.
+pub struct ProgressMeter {
+    start: Instant,
+    completed: std::sync::atomic::AtomicUsize,
+    total: usize,
+    is_tty: bool,
+    last_print: std::sync::Mutex<Instant>,
+}
+
+impl ProgressMeter {
+    pub fn new(total: usize, is_tty: bool) -> ProgressMeter {
+        ProgressMeter {
+            start: Instant::now(),
+            completed: std::sync::atomic::AtomicUsize::new(0),
+            total,
+            is_tty,
+            last_print: std::sync::Mutex::new(Instant::now()),
+        }
+    }
+
+    // Called by a rayon worker as soon as one of its test groups has fully run.
+    pub fn record_completed(&self, count: usize) {
+        self.completed
+            .fetch_add(count, std::sync::atomic::Ordering::Relaxed);
+    }
+
+    pub fn is_tty(&self) -> bool {
+        self.is_tty
+    }
+
+    fn throttle(&self) -> Duration {
+        if self.is_tty {
+            Duration::from_millis(500)
+        } else {
+            Duration::from_secs(30)
+        }
+    }
+
+    // Redraws the status line if the throttle interval has elapsed (or if the run is done).
+    pub fn maybe_print(&self, fails: u32, writer: &mut impl Write) {
+        let completed = self.completed.load(std::sync::atomic::Ordering::Relaxed);
+
+        let mut last_print = self.last_print.lock().unwrap();
+        if last_print.elapsed() < self.throttle() && completed != self.total {
+            return;
+        }
+        *last_print = Instant::now();
+        drop(last_print);
+
+        let elapsed = self.start.elapsed();
+        let rate = completed as f32 / elapsed.as_secs_f32().max(0.001);
+        let remaining = self.total.saturating_sub(completed);
+        let eta = Duration::from_secs_f32(if rate > 0.0 {
+            remaining as f32 / rate
+        } else {
+            0.0
+        });
+        let pct = if self.total > 0 {
+            completed * 100 / self.total
+        } else {
+            100
+        };
+
+        let line = format!(
+            "[{}] {}/{} tests ({}%), {:.0} tests/s, ETA {}, fails: {}",
+            HMSDuration(elapsed),
+            completed,
+            self.total,
+            pct,
+            rate,
+            HMSDuration(eta),
+            fails
+        );
+
+        let result = if self.is_tty {
+            write!(writer, "\r\x1b[K{}", line)
+        } else {
+            writeln!(writer, "{}", line)
+        };
+        result
+            .and_then(|_| writer.flush())
+            .context("printing progress")
+            .unwrap_or_else(|e| error!("{}", e));
+    }
+}
+
 pub struct RunnerResults {
     pub tests: HashSet<RunnerResultNameHash>,
     pub result_counts: ResultCounts,
     pub time: Instant,
 }
 
+// One line of the --results-json/--ndjson event stream per completed result,
+// sharing RunnerResult's fields (and thus RunnerStatus's dEQP-derived naming)
+// so that gtest, dEQP, piglit and igt all produce the same schema.
+#[derive(Serialize)]
+struct ResultEvent<'a> {
+    name: &'a str,
+    status: RunnerStatus,
+    duration_ms: u64,
+    group: u32,
+}
+
+// The final line of the event stream, so a tailing dashboard knows the run is done.
+#[derive(Serialize)]
+struct SummaryEvent<'a> {
+    summary: &'a ResultCounts,
+}
+
+// The three line shapes of the --format=json report (see RunnerResults::write_json),
+// kept separate from ResultEvent/SummaryEvent above since that's a live --ndjson
+// per-result stream with its own schema, while this mirrors libtest's JSON
+// formatter (`type`/`event` fields) for CI tooling that already speaks that shape.
+#[derive(Serialize)]
+struct JsonSuiteEvent {
+    #[serde(rename = "type")]
+    type_: &'static str,
+    event: &'static str,
+    test_count: usize,
+}
+
+#[derive(Serialize)]
+struct JsonTestEvent<'a> {
+    #[serde(rename = "type")]
+    type_: &'static str,
+    name: &'a str,
+    event: &'static str,
+    status: RunnerStatus,
+    exec_time: f32,
+}
+
+#[derive(Serialize)]
+struct JsonSuiteSummaryEvent<'a> {
+    #[serde(rename = "type")]
+    type_: &'static str,
+    event: &'static str,
+    #[serde(flatten)]
+    summary: &'a ResultCounts,
+    exec_time: f32,
+}
+
+pub fn write_ndjson_result(
+    writer: &mut impl Write,
+    result: &RunnerResult,
+    group: u32,
+) -> Result<()> {
+    serde_json::to_writer(
+        &mut *writer,
+        &ResultEvent {
+            name: &result.test,
+            status: result.status,
+            duration_ms: (result.duration as f64 * 1000.0) as u64,
+            group,
+        },
+    )
+    .context("serializing ndjson result event")?;
+    writeln!(writer)?;
+    Ok(())
+}
+
+pub fn write_ndjson_summary(writer: &mut impl Write, result_counts: &ResultCounts) -> Result<()> {
+    serde_json::to_writer(
+        &mut *writer,
+        &SummaryEvent {
+            summary: result_counts,
+        },
+    )
+    .context("serializing ndjson summary event")?;
+    writeln!(writer)?;
+    Ok(())
+}
+
+// How a test's status changed between the "before" and "after" sides of a
+// `deqp compare` run, for --compare-csv/--compare-junit.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CompareDirection {
+    /// Passing before, failing after: what you run `compare` looking for.
+    Regression,
+    /// Failing before, passing after.
+    Fixed,
+    /// Any other change in status (e.g. Skip -> Pass, or one flavor of failure to another).
+    Churn,
+}
+
+impl fmt::Display for CompareDirection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            CompareDirection::Regression => "Regression",
+            CompareDirection::Fixed => "Fixed",
+            CompareDirection::Churn => "Churn",
+        })
+    }
+}
+
+// A single test whose status differs between the two sides of a comparison.
+// `before`/`after` are `None` when the test was missing entirely from that
+// side's results (e.g. it was added or removed from the caselist).
+pub struct CompareDelta<'a> {
+    pub test: &'a str,
+    pub before: Option<RunnerStatus>,
+    pub after: Option<RunnerStatus>,
+    pub direction: CompareDirection,
+}
+
+// Diffs two full runs of the same caselist (such as the two sides of `deqp
+// compare`), returning only the tests whose status changed, sorted by name.
+pub fn compare_results<'a>(
+    before: &'a RunnerResults,
+    after: &'a RunnerResults,
+) -> Vec<CompareDelta<'a>> {
+    let mut test_names: Vec<&str> = before
+        .tests
+        .iter()
+        .map(|r| r.0.test.as_str())
+        .chain(after.tests.iter().map(|r| r.0.test.as_str()))
+        .collect();
+    test_names.sort_unstable();
+    test_names.dedup();
+
+    test_names
+        .into_iter()
+        .filter_map(|test| {
+            let before_status = before.get(test).map(|r| r.status);
+            let after_status = after.get(test).map(|r| r.status);
+            if before_status == after_status {
+                return None;
+            }
+
+            let direction = match (
+                before_status.map(|s| s.is_success()),
+                after_status.map(|s| s.is_success()),
+            ) {
+                (Some(true), Some(false)) => CompareDirection::Regression,
+                (Some(false), Some(true)) => CompareDirection::Fixed,
+                _ => CompareDirection::Churn,
+            };
+
+            Some(CompareDelta {
+                test,
+                before: before_status,
+                after: after_status,
+                direction,
+            })
+        })
+        .collect()
+}
+
+fn compare_status_str(status: Option<RunnerStatus>) -> String {
+    status
+        .map(|s| s.to_string())
+        .unwrap_or_else(|| "Missing".to_owned())
+}
+
+pub fn write_compare_csv(writer: &mut impl Write, deltas: &[CompareDelta]) -> Result<()> {
+    writeln!(writer, "test,before,after,direction")?;
+    for delta in deltas {
+        writeln!(
+            writer,
+            "{},{},{},{}",
+            csv_quote_field(delta.test),
+            compare_status_str(delta.before),
+            compare_status_str(delta.after),
+            delta.direction
+        )?;
+    }
+    Ok(())
+}
+
+/// Reads a `--timings-output` file (`test,duration_ms` CSV, see
+/// `RunnerResults::write_timings`) into a test name -> duration_ms map.
+pub fn read_timings(r: &mut impl Read) -> Result<HashMap<String, f32>> {
+    let mut timings = HashMap::new();
+    let mut contents = String::new();
+    r.read_to_string(&mut contents).context("Reading timings CSV")?;
+    for (lineno, line) in split_csv_records(&contents).into_iter().enumerate() {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let fields = parse_csv_line(&line)
+            .with_context(|| format!("Failed to parse timings CSV at line {}", lineno))?;
+        if fields.len() != 2 {
+            anyhow::bail!(
+                "Failed to parse {} as CSV test,duration_ms at line {}",
+                line,
+                lineno
+            );
+        }
+
+        let duration_ms: f32 = fields[1]
+            .parse()
+            .with_context(|| format!("Parsing duration_ms at line {}", lineno))?;
+        // `f32::parse` happily accepts "nan"/"inf"/"-inf", which would
+        // otherwise flow into shard_tests' partial_cmp().unwrap() calls and
+        // panic a run over a corrupted or hand-edited timings file.
+        if !duration_ms.is_finite() {
+            anyhow::bail!(
+                "duration_ms must be finite, got {} at line {}",
+                duration_ms,
+                lineno
+            );
+        }
+        timings.insert(fields[0].clone(), duration_ms);
+    }
+    Ok(timings)
+}
+
+// One case's timing comparison between two `--timings-output` files.
+pub struct TimingDelta {
+    pub test: String,
+    pub baseline_ms: f32,
+    pub new_ms: f32,
+    pub ratio: f32,
+}
+
+// Compares two `--timings-output` maps, returning a delta for every case
+// present in both (new cases and ones dropped from the caselist aren't
+// comparable, so they're silently excluded), sorted by ratio (new/baseline)
+// descending so the worst slowdowns sort first.
+pub fn compare_timings(
+    baseline: &HashMap<String, f32>,
+    new: &HashMap<String, f32>,
+) -> Vec<TimingDelta> {
+    let mut deltas: Vec<_> = new
+        .iter()
+        .filter_map(|(test, &new_ms)| {
+            baseline.get(test).map(|&baseline_ms| TimingDelta {
+                test: test.clone(),
+                baseline_ms,
+                new_ms,
+                // A 0ms baseline (an instant or mocked test) would make a
+                // plain new_ms / baseline_ms divide-by-zero into NaN/inf,
+                // which then panics the `partial_cmp(..).unwrap()` sort
+                // below the first time two such ratios are compared. Treat
+                // "still 0ms" as unchanged and "now nonzero" as an
+                // unbounded slowdown instead of dividing.
+                ratio: if baseline_ms == 0.0 {
+                    if new_ms == 0.0 {
+                        1.0
+                    } else {
+                        f32::INFINITY
+                    }
+                } else {
+                    new_ms / baseline_ms
+                },
+            })
+        })
+        .collect();
+    deltas.sort_by(|a, b| b.ratio.partial_cmp(&a.ratio).unwrap());
+    deltas
+}
+
+// Prints the `limit` worst slowdowns from `compare_timings`, and returns
+// whether any case regressed past `threshold` (for the caller to decide the
+// process exit code).
+pub fn print_timings_summary(deltas: &[TimingDelta], threshold: f32, limit: usize) -> bool {
+    let regressed: Vec<_> = deltas.iter().filter(|d| d.ratio > threshold).collect();
+
+    if !deltas.is_empty() {
+        println!("Timing comparison, worst slowdowns first:");
+        let limit = if limit == 0 { deltas.len() } else { limit };
+        for delta in deltas.iter().take(limit) {
+            println!(
+                "  {}: {:.1}ms -> {:.1}ms ({:.2}x){}",
+                delta.test,
+                delta.baseline_ms,
+                delta.new_ms,
+                delta.ratio,
+                if delta.ratio > threshold {
+                    " REGRESSION"
+                } else {
+                    ""
+                }
+            );
+        }
+    }
+
+    if !regressed.is_empty() {
+        println!(
+            "{} case(s) regressed past {:.2}x",
+            regressed.len(),
+            threshold
+        );
+    }
+
+    !regressed.is_empty()
+}
+
+// Writes only the changed cases as a JUnit XML report, classified by
+// direction (the testcase name is tagged with it, since junit_report's
+// success() doesn't carry a message the way failure()/error() do).
+pub fn write_compare_junit(
+    writer: &mut impl Write,
+    deltas: &[CompareDelta],
+    testsuite_name: &str,
+) -> Result<()> {
+    use junit_report::*;
+
+    let mut testcases = Vec::new();
+    for delta in deltas {
+        let name = format!(
+            "[{}] {}",
+            delta.direction.to_string().to_uppercase(),
+            delta.test
+        );
+        let message = format!(
+            "{}: {} -> {}",
+            delta.test,
+            compare_status_str(delta.before),
+            compare_status_str(delta.after)
+        );
+
+        let tc = match delta.direction {
+            CompareDirection::Fixed => TestCase::success(&name, Duration::seconds(0)),
+            CompareDirection::Regression => {
+                TestCase::failure(&name, Duration::seconds(0), "Regression", &message)
+            }
+            CompareDirection::Churn => {
+                TestCase::error(&name, Duration::seconds(0), "Churn", &message)
+            }
+        };
+        testcases.push(tc);
+    }
+
+    let ts = TestSuite::new(testsuite_name).add_testcases(testcases);
+
+    Report::new()
+        .add_testsuite(ts)
+        .write_xml(BufWriter::new(writer))
+        .context("writing XML output")
+}
+
+// A triage-friendly summary of how a run differs from whatever --baseline it
+// was compared against, for --diff. Built from the ExpectedFail/
+// UnexpectedPass/Flake statuses `RunnerStatus::with_baseline` already folds
+// a baseline match into: anything still reported as a plain Fail/Crash/
+// Timeout/Missing wasn't matched by the baseline, so it's new.
+#[derive(Debug, Default, Serialize)]
+pub struct DiffReport {
+    /// Tests newly failing: no baseline entry, or the baseline expected a
+    /// Pass, but this run reported Fail/Crash/Timeout/Missing.
+    pub regressions: Vec<String>,
+    /// Tests the baseline expected to fail that passed this run (candidates
+    /// for removing from the baseline).
+    pub now_passing: Vec<String>,
+    /// Tests that flipped between pass and fail across --flake-retries.
+    pub flaky: Vec<String>,
+}
+
+impl DiffReport {
+    pub fn is_empty(&self) -> bool {
+        self.regressions.is_empty() && self.now_passing.is_empty() && self.flaky.is_empty()
+    }
+
+    pub fn print_summary(&self) {
+        if self.is_empty() {
+            return;
+        }
+
+        println!();
+        println!("Diff against baseline:");
+        if !self.regressions.is_empty() {
+            println!("  {} new regression(s):", self.regressions.len());
+            for test in &self.regressions {
+                println!("    {}", test);
+            }
+        }
+        if !self.now_passing.is_empty() {
+            println!(
+                "  {} test(s) now passing (remove from baseline?):",
+                self.now_passing.len()
+            );
+            for test in &self.now_passing {
+                println!("    {}", test);
+            }
+        }
+        if !self.flaky.is_empty() {
+            println!("  {} flaky test(s):", self.flaky.len());
+            for test in &self.flaky {
+                println!("    {}", test);
+            }
+        }
+    }
+}
+
+/// A pluggable sink for a completed run's results, mirroring rustc libtest's
+/// formatters module: each implementation owns a writer and is driven by one
+/// pass over the sorted results instead of `RunnerResults` growing another
+/// bespoke `write_*` method with its own `BufWriter`/sorting/`is_success` logic.
+pub trait OutputFormatter {
+    /// Called once, before the first `write_result`, with the total test count.
+    fn write_run_start(&mut self, test_count: usize) -> Result<()>;
+    /// Called once per result, in test-name order.
+    fn write_result(&mut self, result: &RunnerResult) -> Result<()>;
+    /// Called once, after the last `write_result`, with the run's final counts.
+    fn write_run_finish(&mut self, counts: &ResultCounts) -> Result<()>;
+}
+
+/// One CSV line per result: `test,status,duration,flake_retries`.
+pub struct CsvFormatter<W: Write> {
+    writer: BufWriter<W>,
+}
+
+impl<W: Write> CsvFormatter<W> {
+    pub fn new(writer: W) -> Self {
+        CsvFormatter {
+            writer: BufWriter::new(writer),
+        }
+    }
+}
+
+impl<W: Write> OutputFormatter for CsvFormatter<W> {
+    fn write_run_start(&mut self, _test_count: usize) -> Result<()> {
+        Ok(())
+    }
+
+    fn write_result(&mut self, result: &RunnerResult) -> Result<()> {
+        writeln!(
+            self.writer,
+            "{},{},{},{}",
+            csv_quote_field(&result.test),
+            result.status,
+            result.duration,
+            result.flake_retries
+        )?;
+        Ok(())
+    }
+
+    fn write_run_finish(&mut self, _counts: &ResultCounts) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Same shape as `CsvFormatter`, but only the failing results: `test,status`.
+pub struct FailuresFormatter<W: Write> {
+    writer: BufWriter<W>,
+}
+
+impl<W: Write> FailuresFormatter<W> {
+    pub fn new(writer: W) -> Self {
+        FailuresFormatter {
+            writer: BufWriter::new(writer),
+        }
+    }
+}
+
+impl<W: Write> OutputFormatter for FailuresFormatter<W> {
+    fn write_run_start(&mut self, _test_count: usize) -> Result<()> {
+        Ok(())
+    }
+
+    fn write_result(&mut self, result: &RunnerResult) -> Result<()> {
+        if !result.status.is_success() {
+            writeln!(
+                self.writer,
+                "{},{}",
+                csv_quote_field(&result.test),
+                result.status
+            )?;
+        }
+        Ok(())
+    }
+
+    fn write_run_finish(&mut self, _counts: &ResultCounts) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Accumulates failing results into JUnit `<testcase>` elements and writes the
+/// whole report on `write_run_finish`, since `junit_report::Report` can only be
+/// serialized once it holds every testcase.
+pub struct JunitFormatter<W: Write> {
+    writer: BufWriter<W>,
+    testsuite: String,
+    template: String,
+    limit: usize,
+    seen: usize,
+    testcases: Vec<junit_report::TestCase>,
+}
+
+impl<W: Write> JunitFormatter<W> {
+    pub fn new(writer: W, options: &JunitGeneratorOptions) -> Self {
+        JunitFormatter {
+            writer: BufWriter::new(writer),
+            testsuite: options.testsuite.clone(),
+            template: options.template.clone(),
+            limit: if options.limit == 0 {
+                std::usize::MAX
+            } else {
+                options.limit
+            },
+            seen: 0,
+            testcases: Vec::new(),
+        }
+    }
+}
+
+impl<W: Write> OutputFormatter for JunitFormatter<W> {
+    fn write_run_start(&mut self, _test_count: usize) -> Result<()> {
+        Ok(())
+    }
+
+    fn write_result(&mut self, result: &RunnerResult) -> Result<()> {
+        use junit_report::*;
+
+        if self.seen >= self.limit {
+            return Ok(());
+        }
+        self.seen += 1;
+
+        let time = Duration::milliseconds((result.duration as f64 * 1000.0) as i64);
+
+        let tc = if !result.status.is_success() {
+            let message = self.template.replace("{{testcase}}", &result.test);
+            let type_ = format!("{}", result.status);
+
+            junit_report::TestCase::failure(&result.test, time, &type_, &message)
+        } else {
+            junit_report::TestCase::success(&result.test, time)
+        };
+        self.testcases.push(tc);
+
+        Ok(())
+    }
+
+    fn write_run_finish(&mut self, _counts: &ResultCounts) -> Result<()> {
+        use junit_report::*;
+
+        let ts = TestSuite::new(&self.testsuite).add_testcases(std::mem::take(&mut self.testcases));
+
+        Report::new()
+            .add_testsuite(ts)
+            .write_xml(&mut self.writer)
+            .context("writing XML output")
+    }
+}
+
+/// Terse libtest `cargo test`-style output: one `.`/`F`/`S` character per
+/// result, line-wrapped at 100 columns, followed by the `ResultCounts` summary.
+pub struct DotFormatter<W: Write> {
+    writer: BufWriter<W>,
+    column: usize,
+}
+
+impl<W: Write> DotFormatter<W> {
+    pub fn new(writer: W) -> Self {
+        DotFormatter {
+            writer: BufWriter::new(writer),
+            column: 0,
+        }
+    }
+}
+
+impl<W: Write> OutputFormatter for DotFormatter<W> {
+    fn write_run_start(&mut self, _test_count: usize) -> Result<()> {
+        Ok(())
+    }
+
+    fn write_result(&mut self, result: &RunnerResult) -> Result<()> {
+        let c = if result.status == RunnerStatus::Skip {
+            'S'
+        } else if result.status.is_success() {
+            '.'
+        } else {
+            'F'
+        };
+        write!(self.writer, "{}", c)?;
+
+        self.column += 1;
+        if self.column == 100 {
+            writeln!(self.writer)?;
+            self.column = 0;
+        }
+
+        Ok(())
+    }
+
+    fn write_run_finish(&mut self, counts: &ResultCounts) -> Result<()> {
+        if self.column != 0 {
+            writeln!(self.writer)?;
+        }
+        writeln!(self.writer, "{}", counts)?;
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
 impl RunnerResults {
     pub fn new() -> RunnerResults {
         Default::default()
     }
 
+    /// Drives a set of pluggable `OutputFormatter` sinks from a single pass
+    /// over `sorted_results()`, so a third party can add its own output format
+    /// without touching this module.
+    pub fn write_formatted<'a>(
+        &self,
+        formatters: &mut [Box<dyn OutputFormatter + 'a>],
+    ) -> Result<()> {
+        let sorted = self.sorted_results();
+
+        for formatter in formatters.iter_mut() {
+            formatter.write_run_start(sorted.len())?;
+        }
+        for result in &sorted {
+            for formatter in formatters.iter_mut() {
+                formatter.write_result(result)?;
+            }
+        }
+        for formatter in formatters.iter_mut() {
+            formatter.write_run_finish(&self.result_counts)?;
+        }
+
+        Ok(())
+    }
+
+    // Buckets results into a `DiffReport` for --diff; see its docs for how
+    // each bucket is derived from the already baseline-combined status.
+    pub fn diff_report(&self) -> DiffReport {
+        let mut report = DiffReport::default();
+
+        for result in self.sorted_results() {
+            match result.status {
+                RunnerStatus::Fail
+                | RunnerStatus::Crash
+                | RunnerStatus::Timeout
+                | RunnerStatus::Missing => report.regressions.push(result.test.clone()),
+                RunnerStatus::UnexpectedPass => report.now_passing.push(result.test.clone()),
+                RunnerStatus::Flake => report.flaky.push(result.test.clone()),
+                RunnerStatus::Pass
+                | RunnerStatus::Skip
+                | RunnerStatus::Warn
+                | RunnerStatus::ExpectedFail => {}
+            }
+        }
+
+        report
+    }
+
+    pub fn write_diff_json(&self, writer: &mut impl Write) -> Result<()> {
+        serde_json::to_writer_pretty(writer, &self.diff_report()).context("writing --diff JSON")
+    }
+
     pub fn get(&self, test: &str) -> Option<&RunnerResult> {
         self.tests.get(test).map(|x| &x.0)
     }
 
+    // Test names whose last recorded status was a failure or flake, for
+    // `--replay` to restrict a re-run to exactly the cases worth reproducing.
+    pub fn failing_test_names(&self) -> Vec<String> {
+        self.tests
+            .iter()
+            .filter(|r| !r.0.status.is_success() || r.0.status == RunnerStatus::Flake)
+            .map(|r| r.0.test.clone())
+            .collect()
+    }
+
     pub fn record_result(&mut self, result: RunnerResult) {
         let mut result = result;
 
-        if self.get(&result.test).is_some() {
-            error!(
-                "Duplicate test result for {}, marking test failed",
-                &result.test
-            );
-            result.status = RunnerStatus::Fail;
-        } else if !result.subtest {
-            self.result_counts.total += 1;
+        match self.get(&result.test) {
+            // A placeholder carried forward by `tests_to_resume` (see its docs):
+            // supersede it instead of flagging a duplicate, so resuming an
+            // interrupted run doesn't fail every test it's retrying.
+            Some(prior) if matches!(prior.status, RunnerStatus::Flake | RunnerStatus::Missing) => {
+                self.result_counts.decrement(prior.status);
+            }
+            Some(_) => {
+                error!(
+                    "Duplicate test result for {}, marking test failed",
+                    &result.test
+                );
+                result.status = RunnerStatus::Fail;
+            }
+            None => {
+                if !result.subtest {
+                    self.result_counts.total += 1;
+                }
+            }
         }
         self.result_counts.increment(result.status);
         self.tests.replace(RunnerResultNameHash(result));
     }
 
+    /// Given the full caselist, returns the names still worth running: tests
+    /// missing entirely from this (presumably resumed from a partial
+    /// results.csv) set of results, plus any only recorded as `Flake` or
+    /// `Missing` so far. Everything else already has a terminal status and is
+    /// left untouched -- `record_result` will supersede a carried-forward
+    /// `Flake`/`Missing` placeholder rather than treating it as a duplicate.
+    pub fn tests_to_resume<'a>(&self, all_tests: &[&'a str]) -> Vec<&'a str> {
+        all_tests
+            .iter()
+            .copied()
+            .filter(|test| match self.get(test) {
+                None => true,
+                Some(result) => {
+                    matches!(result.status, RunnerStatus::Flake | RunnerStatus::Missing)
+                }
+            })
+            .collect()
+    }
+
     pub fn is_success(&self) -> bool {
         self.tests.iter().all(|result| result.0.status.is_success())
     }
 
+    /// Classifies the run against the full caselist size (`expected_total`),
+    /// so callers can distinguish "update your expectations" (`UnexpectedPass`)
+    /// from "the driver is broken" (`Failed`/`Timedout`) or "this run never
+    /// finished" (`Inconclusive`). See [`Outcome`] for the precedence rules.
+    pub fn outcome(&self, expected_total: usize) -> Outcome {
+        let counts = &self.result_counts;
+
+        if counts.timeout > 0 {
+            Outcome::Timedout
+        } else if (counts.total as usize) < expected_total {
+            Outcome::Inconclusive
+        } else if counts.hard_fail_count() > 0 {
+            Outcome::Failed
+        } else if counts.unexpected_pass > 0 {
+            Outcome::UnexpectedPass
+        } else {
+            Outcome::Passed
+        }
+    }
+
     /// Returns a list of references to the results, sorted by test name.
     pub fn sorted_results(&self) -> Vec<&RunnerResult> {
         let mut sorted: Vec<_> = self.tests.iter().map(|x| &x.0).collect();
@@ -332,23 +1307,51 @@ impl RunnerResults {
     }
 
     pub fn write_results<W: Write>(&self, writer: &mut W) -> Result<()> {
+        let mut formatters: Vec<Box<dyn OutputFormatter + '_>> =
+            vec![Box::new(CsvFormatter::new(writer))];
+        self.write_formatted(&mut formatters)
+    }
+
+    pub fn write_failures(&self, writer: &mut impl Write) -> Result<()> {
+        let mut formatters: Vec<Box<dyn OutputFormatter + '_>> =
+            vec![Box::new(FailuresFormatter::new(writer))];
+        self.write_formatted(&mut formatters)
+    }
+
+    /// Writes `test,duration_ms` for every result, for a later run's
+    /// `compare-timings` to diff against as a lightweight perf-regression gate.
+    pub fn write_timings(&self, writer: &mut impl Write) -> Result<()> {
         let mut writer = BufWriter::new(writer);
         for result in self.sorted_results() {
             writeln!(
                 writer,
-                "{},{},{}",
-                result.test, result.status, result.duration
+                "{},{}",
+                csv_quote_field(&result.test),
+                result.duration * 1000.0
             )?;
         }
         Ok(())
     }
 
-    pub fn write_failures(&self, writer: &mut impl Write) -> Result<()> {
+    // Writes a new baseline CSV reflecting the statuses actually observed
+    // this run, for --bless. `baseline` is the baseline this run was
+    // compared against (if any): Fail/Crash/Warn/Timeout all collapse to
+    // ExpectedFail once combined with a baseline entry, so it's the only
+    // place the original failure kind survives, and we want to keep
+    // recording that kind rather than flattening it to ExpectedFail.
+    // Pass/Skip/UnexpectedPass are dropped since they no longer need an
+    // entry in the baseline.
+    pub fn write_baseline(&self, writer: &mut impl Write, baseline: &RunnerResults) -> Result<()> {
         let mut writer = BufWriter::new(writer);
         for result in self.sorted_results() {
-            if !result.status.is_success() {
-                writeln!(writer, "{},{}", result.test, result.status)?;
-            }
+            let status = match result.status {
+                RunnerStatus::Pass | RunnerStatus::Skip | RunnerStatus::UnexpectedPass => continue,
+                RunnerStatus::ExpectedFail => baseline
+                    .get(&result.test)
+                    .map_or(RunnerStatus::ExpectedFail, |prior| prior.status),
+                other => other,
+            };
+            writeln!(writer, "{},{}", csv_quote_field(&result.test), status)?;
         }
         Ok(())
     }
@@ -358,76 +1361,168 @@ impl RunnerResults {
         writer: &mut impl Write,
         options: &JunitGeneratorOptions,
     ) -> Result<()> {
+        let mut formatters: Vec<Box<dyn OutputFormatter + '_>> =
+            vec![Box::new(JunitFormatter::new(writer, options))];
+        self.write_formatted(&mut formatters)
+    }
+
+    /// Writes the full set of results (not just failures) as a JUnit XML
+    /// `<testsuites>` tree, one `<testcase>` per result, so that the whole run can be
+    /// ingested by CI systems that expect a JUnit report rather than our own CSVs.
+    /// Unlike [`Self::write_junit_failures`], passing tests get a `<testcase>` of
+    /// their own instead of being omitted entirely. Dotted test name prefixes
+    /// (e.g. `dEQP-GLES2.test.p`) become the testcase's classname, so viewers
+    /// that group by classname display the caselist as a tree instead of one
+    /// flat list. `Missing`/`Crash`/`Timeout` (couldn't produce a verdict at all)
+    /// become `<error>`; `Fail`/`UnexpectedPass` become `<failure>`; `Flake`
+    /// still counts as passing, but its `<system-out>` notes that retries
+    /// didn't agree.
+    pub fn write_junit_report(&self, writer: &mut impl Write, testsuite_name: &str) -> Result<()> {
         use junit_report::*;
-        let limit = if options.limit == 0 {
-            std::usize::MAX
-        } else {
-            options.limit
-        };
 
         let mut testcases = Vec::new();
-        for result in self.sorted_results().iter().take(limit) {
-            let tc = if !result.status.is_success() {
-                let message = options.template.replace("{{testcase}}", &result.test);
-
-                let type_ = format!("{}", result.status);
-
-                junit_report::TestCase::failure(
-                    &result.test,
-                    Duration::seconds(0),
-                    &type_,
-                    &message,
-                )
-            } else {
-                junit_report::TestCase::success(&result.test, Duration::seconds(0))
+        for result in self.sorted_results() {
+            let time = Duration::milliseconds((result.duration as f64 * 1000.0) as i64);
+            let (classname, name) = split_classname(&result.test);
+            let mut tc = match result.status {
+                RunnerStatus::Pass | RunnerStatus::Warn | RunnerStatus::ExpectedFail => {
+                    TestCase::success(&name, time)
+                }
+                RunnerStatus::Flake => {
+                    let mut tc = TestCase::success(&name, time);
+                    tc.set_system_out(&format!(
+                        "{} was flaky: it did not report a consistent result across retries",
+                        result.test
+                    ));
+                    tc
+                }
+                RunnerStatus::Skip => TestCase::success(&name, time),
+                RunnerStatus::Crash | RunnerStatus::Timeout | RunnerStatus::Missing => {
+                    TestCase::error(
+                        &name,
+                        time,
+                        &format!("{}", result.status),
+                        &format!("{} reported {}", result.test, result.status),
+                    )
+                }
+                RunnerStatus::Fail | RunnerStatus::UnexpectedPass => TestCase::failure(
+                    &name,
+                    time,
+                    &format!("{}", result.status),
+                    &format!("{} reported {}", result.test, result.status),
+                ),
             };
+            tc.set_classname(&classname);
             testcases.push(tc);
         }
 
-        let ts = junit_report::TestSuite::new(&options.testsuite).add_testcases(testcases);
+        let ts = TestSuite::new(testsuite_name).add_testcases(testcases);
 
-        junit_report::Report::new()
+        Report::new()
             .add_testsuite(ts)
             .write_xml(BufWriter::new(writer))
             .context("writing XML output")
     }
 
-    pub fn from_csv(r: &mut impl Read) -> Result<RunnerResults> {
-        lazy_static! {
-            static ref CSV_RE: Regex = Regex::new("^([^,]+),([^,]+)").unwrap();
+    /// Writes a libtest-style JSON-lines report: a `"suite"`/`"started"` header
+    /// with the test count, one `"test"` object per result (keeping the full
+    /// `RunnerStatus` alongside libtest's ok/failed/ignored `event` so CSV-level
+    /// detail like Flake/ExpectedFail/UnexpectedPass isn't lost), and a trailing
+    /// `"suite"` summary object carrying the `ResultCounts` and total elapsed time.
+    pub fn write_json(&self, writer: &mut impl Write, options: &JsonGeneratorOptions) -> Result<()> {
+        let mut writer = BufWriter::new(writer);
+        let limit = if options.limit == 0 {
+            std::usize::MAX
+        } else {
+            options.limit
+        };
+
+        let sorted = self.sorted_results();
+
+        serde_json::to_writer(
+            &mut writer,
+            &JsonSuiteEvent {
+                type_: "suite",
+                event: "started",
+                test_count: sorted.len(),
+            },
+        )
+        .context("serializing json suite-started event")?;
+        writeln!(writer)?;
+
+        for result in sorted.iter().take(limit) {
+            let event = if result.status == RunnerStatus::Skip {
+                "ignored"
+            } else if result.status.is_success() {
+                "ok"
+            } else {
+                "failed"
+            };
+
+            serde_json::to_writer(
+                &mut writer,
+                &JsonTestEvent {
+                    type_: "test",
+                    name: &result.test,
+                    event,
+                    status: result.status,
+                    exec_time: result.duration,
+                },
+            )
+            .context("serializing json test event")?;
+            writeln!(writer)?;
         }
 
+        serde_json::to_writer(
+            &mut writer,
+            &JsonSuiteSummaryEvent {
+                type_: "suite",
+                event: if self.is_success() { "ok" } else { "failed" },
+                summary: &self.result_counts,
+                exec_time: self.time.elapsed().as_secs_f32(),
+            },
+        )
+        .context("serializing json suite-summary event")?;
+        writeln!(writer)?;
+
+        Ok(())
+    }
+
+    pub fn from_csv(r: &mut impl Read) -> Result<RunnerResults> {
         let mut results = RunnerResults::new();
-        let r = BufReader::new(r);
-        for (lineno, line) in r.lines().enumerate() {
-            let line = line.context("Reading CSV")?;
+        let mut contents = String::new();
+        r.read_to_string(&mut contents).context("Reading CSV")?;
+        for (lineno, line) in split_csv_records(&contents).into_iter().enumerate() {
             if line.is_empty() || line.starts_with('#') {
                 continue;
             }
 
-            if let Some(cap) = CSV_RE.captures(&line) {
-                let result = RunnerResult {
-                    test: cap[1].to_string(),
-                    status: cap[2].parse()?,
-                    duration: 0.0,
-                    subtest: false,
-                };
-
-                // If you have more than one result for a test in the CSV,
-                // something has gone wrong (probably human error writing a
-                // baseline list)
-                if results.get(&result.test).is_some() {
-                    anyhow::bail!("Found duplicate result for {} at line {}", line, lineno);
-                }
-
-                results.record_result(result);
-            } else {
+            let fields = parse_csv_line(&line)
+                .with_context(|| format!("Failed to parse CSV at line {}", lineno))?;
+            if fields.len() < 2 {
                 anyhow::bail!(
                     "Failed to parse {} as CSV test,status[,duration] or comment at line {}",
                     line,
                     lineno
                 );
             }
+
+            let result = RunnerResult {
+                test: fields[0].clone(),
+                status: fields[1].parse()?,
+                duration: 0.0,
+                subtest: false,
+                flake_retries: 0,
+            };
+
+            // If you have more than one result for a test in the CSV,
+            // something has gone wrong (probably human error writing a
+            // baseline list)
+            if results.get(&result.test).is_some() {
+                anyhow::bail!("Found duplicate result for {} at line {}", line, lineno);
+            }
+
+            results.record_result(result);
         }
         Ok(results)
     }
@@ -464,7 +1559,12 @@ impl RunnerResults {
             .unwrap_or_else(|e| error!("{}", e));
     }
 
-    pub fn print_summary(&self, summary_limit: usize) {
+    /// Prints the post-run summary: slowest tests, aggregate duration stats,
+    /// flaky/failing tests, and (if `slow_test_secs` is non-zero) a dedicated
+    /// section for every non-subtest result whose `duration` exceeded it, so
+    /// maintainers can track runtime regressions instead of only seeing the
+    /// handful of single slowest outliers.
+    pub fn print_summary(&self, summary_limit: usize, slow_test_secs: f32) {
         if self.tests.is_empty() {
             return;
         }
@@ -485,6 +1585,41 @@ impl RunnerResults {
             println!("  {} ({:.02}s)", test.0, test.1);
         }
 
+        let mut durations: Vec<f32> = self
+            .tests
+            .iter()
+            .filter(|result| !result.0.subtest)
+            .map(|result| result.0.duration)
+            .collect();
+        if !durations.is_empty() {
+            durations.sort_by(|a, b| a.partial_cmp(b).unwrap());
+            let mean = durations.iter().sum::<f32>() / durations.len() as f32;
+            println!();
+            println!(
+                "Test duration: mean {:.02}s, median {:.02}s, p95 {:.02}s",
+                mean,
+                percentile(&durations, 0.5),
+                percentile(&durations, 0.95)
+            );
+        }
+
+        if slow_test_secs > 0.0 {
+            let slow: Vec<_> = slowest
+                .iter()
+                .filter(|(_, duration)| *duration > slow_test_secs)
+                .collect();
+            if !slow.is_empty() {
+                println!();
+                println!("Tests exceeding time budget ({:.02}s):", slow_test_secs);
+                for (test, duration) in slow.iter().take(summary_limit) {
+                    println!("  {} ({:.02}s)", test, duration);
+                }
+                if slow.len() > summary_limit {
+                    println!("  ... and more (see results.csv)");
+                }
+            }
+        }
+
         let mut flakes: Vec<_> = self
             .tests
             .iter()
@@ -633,6 +1768,7 @@ piglit@crashy@test,Crash"
                 status: RunnerStatus::Fail,
                 duration: 0.0,
                 subtest: false,
+                flake_retries: 0,
             })
             .as_ref()
         );
@@ -643,6 +1779,7 @@ piglit@crashy@test,Crash"
                 status: RunnerStatus::Crash,
                 duration: 0.0,
                 subtest: false,
+                flake_retries: 0,
             })
             .as_ref()
         );
@@ -650,6 +1787,74 @@ piglit@crashy@test,Crash"
         Ok(())
     }
 
+    #[test]
+    fn csv_quoted_field_with_commas() -> Result<()> {
+        let results = RunnerResults::from_csv(
+            &mut "\"piglit@subtest_commas@GL_INTENSITY16, swizzled, border color only\",Fail"
+                .as_bytes(),
+        )?;
+        assert_eq!(
+            results.get("piglit@subtest_commas@GL_INTENSITY16, swizzled, border color only"),
+            Some(RunnerResult {
+                test: "piglit@subtest_commas@GL_INTENSITY16, swizzled, border color only"
+                    .to_string(),
+                status: RunnerStatus::Fail,
+                duration: 0.0,
+                subtest: false,
+                flake_retries: 0,
+            })
+            .as_ref()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn csv_round_trip_comma_and_quote() -> Result<()> {
+        let mut results = RunnerResults::new();
+        results.record_result(RunnerResult {
+            test: "piglit@has, a comma and a \"quote\"".to_string(),
+            status: RunnerStatus::Fail,
+            duration: 1.5,
+            subtest: false,
+            flake_retries: 0,
+        });
+
+        let mut csv = Vec::new();
+        results.write_results(&mut csv)?;
+
+        let round_tripped = RunnerResults::from_csv(&mut csv.as_slice())?;
+        assert_eq!(
+            round_tripped.get("piglit@has, a comma and a \"quote\""),
+            results.get("piglit@has, a comma and a \"quote\"")
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn csv_round_trip_newline() -> Result<()> {
+        let mut results = RunnerResults::new();
+        results.record_result(RunnerResult {
+            test: "piglit@has\na newline".to_string(),
+            status: RunnerStatus::Fail,
+            duration: 1.5,
+            subtest: false,
+            flake_retries: 0,
+        });
+
+        let mut csv = Vec::new();
+        results.write_results(&mut csv)?;
+
+        let round_tripped = RunnerResults::from_csv(&mut csv.as_slice())?;
+        assert_eq!(
+            round_tripped.get("piglit@has\na newline"),
+            results.get("piglit@has\na newline")
+        );
+
+        Ok(())
+    }
+
     #[test]
     fn csv_parse_dup() {
         assert!(RunnerResults::from_csv(
@@ -667,6 +1872,39 @@ dEQP-GLES2.info.version,Pass"
         assert!(RunnerResults::from_csv(&mut "dEQP-GLES2.info.version".as_bytes()).is_err());
     }
 
+    #[test]
+    fn read_timings_rejects_non_finite() {
+        assert!(read_timings(&mut "some_test,nan".as_bytes()).is_err());
+        assert!(read_timings(&mut "some_test,inf".as_bytes()).is_err());
+        assert!(read_timings(&mut "some_test,-inf".as_bytes()).is_err());
+        assert!(read_timings(&mut "some_test,12.5".as_bytes()).is_ok());
+    }
+
+    #[test]
+    fn compare_timings_zero_baseline() {
+        let mut baseline = HashMap::new();
+        baseline.insert("instant_test".to_string(), 0.0);
+        baseline.insert("still_instant_test".to_string(), 0.0);
+        baseline.insert("normal_test".to_string(), 10.0);
+
+        let mut new = HashMap::new();
+        new.insert("instant_test".to_string(), 5.0);
+        new.insert("still_instant_test".to_string(), 0.0);
+        new.insert("normal_test".to_string(), 20.0);
+
+        // Must not panic sorting ratios that would otherwise be NaN/inf from
+        // dividing by a 0ms baseline.
+        let deltas = compare_timings(&baseline, &new);
+        assert_eq!(deltas.len(), 3);
+
+        let get = |test: &str| deltas.iter().find(|d| d.test == test).unwrap().ratio;
+        assert_eq!(get("instant_test"), f32::INFINITY);
+        assert_eq!(get("still_instant_test"), 1.0);
+        assert_eq!(get("normal_test"), 2.0);
+
+        assert!(print_timings_summary(&deltas, 1.5, 0));
+    }
+
     #[test]
     fn hms_display() {
         assert_eq!(format!("{}", HMSDuration(Duration::new(15, 20))), "15");
@@ -689,6 +1927,7 @@ dEQP-GLES2.info.version,Pass"
             status,
             duration: 0.0,
             subtest: false,
+            flake_retries: 0,
         });
     }
 
@@ -726,3 +1965,13 @@ pub struct JunitGeneratorOptions {
     )]
     limit: usize,
 }
+
+#[derive(Debug, StructOpt)]
+pub struct JsonGeneratorOptions {
+    #[structopt(
+        long,
+        default_value = "0",
+        help = "Number of per-test json objects to emit (or 0 for unlimited)"
+    )]
+    limit: usize,
+}
@@ -0,0 +1,115 @@
+// Self-profiling support: records a Chrome `chrome://tracing` / Perfetto
+// compatible JSON trace of a run, so users have a visual timeline to diagnose
+// tail latency (e.g. the "one slow deqp-vk group stuck at the end" problem
+// the min_tests_per_group comment in bin/deqp.rs describes) and to tune
+// tests_per_group empirically.
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::fs::File;
+use std::path::Path;
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::time::{Duration, Instant};
+
+// One entry in the profile: either a complete ("X") event spanning a group's
+// run (dur_us: Some) or an instant ("i") event marking a point in time
+// (dur_us: None).
+struct ProfileEvent {
+    name: String,
+    tid: u32,
+    ts_us: f64,
+    dur_us: Option<f64>,
+}
+
+// Cheaply-clonable handle that rayon workers use to record timing into the
+// profile without contending on a shared lock; events are sent down a
+// channel to a single collection thread (see `write_chrome_trace`), which
+// serializes them once the run is done.
+#[derive(Clone)]
+pub struct Profiler {
+    sender: Sender<ProfileEvent>,
+    start: Instant,
+}
+
+impl Profiler {
+    pub fn new() -> (Profiler, Receiver<ProfileEvent>) {
+        let (sender, receiver) = channel();
+        (
+            Profiler {
+                sender,
+                start: Instant::now(),
+            },
+            receiver,
+        )
+    }
+
+    // Records a completed span, such as a deqp/piglit/gtest/igt group invocation.
+    pub fn record_complete(
+        &self,
+        name: impl Into<String>,
+        tid: u32,
+        span_start: Instant,
+        dur: Duration,
+    ) {
+        let _ = self.sender.send(ProfileEvent {
+            name: name.into(),
+            tid,
+            ts_us: span_start.saturating_duration_since(self.start).as_secs_f64() * 1e6,
+            dur_us: Some(dur.as_secs_f64() * 1e6),
+        });
+    }
+
+    // Records a point-in-time event, such as a renderer-check probe or a
+    // group-splitting decision.
+    pub fn record_instant(&self, name: impl Into<String>, tid: u32) {
+        let _ = self.sender.send(ProfileEvent {
+            name: name.into(),
+            tid,
+            ts_us: self.start.elapsed().as_secs_f64() * 1e6,
+            dur_us: None,
+        });
+    }
+}
+
+#[derive(Serialize)]
+struct ChromeEvent {
+    name: String,
+    cat: &'static str,
+    ph: &'static str,
+    ts: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    dur: Option<f64>,
+    pid: u32,
+    tid: u32,
+}
+
+#[derive(Serialize)]
+struct ChromeTrace {
+    #[serde(rename = "traceEvents")]
+    trace_events: Vec<ChromeEvent>,
+}
+
+// Drains `receiver` (run this on its own thread, the same way results_collection
+// does for test results) and writes the accumulated events to `path` as a
+// Chrome trace once the sending half of the channel (the Profiler and any
+// clones of it) has been dropped.
+pub fn write_chrome_trace(path: &Path, receiver: Receiver<ProfileEvent>) -> Result<()> {
+    let pid = std::process::id();
+
+    let trace_events: Vec<ChromeEvent> = receiver
+        .iter()
+        .map(|event| ChromeEvent {
+            name: event.name,
+            cat: "deqp-runner",
+            ph: if event.dur_us.is_some() { "X" } else { "i" },
+            ts: event.ts_us,
+            dur: event.dur_us,
+            pid,
+            tid: event.tid,
+        })
+        .collect();
+
+    let file = File::create(path).context("creating --profile file")?;
+    serde_json::to_writer(file, &ChromeTrace { trace_events })
+        .context("serializing chrome trace")?;
+    Ok(())
+}
@@ -1,6 +1,7 @@
 use super::*;
 use ::deqp_runner::{RunnerResults, RunnerStatus};
 use anyhow::{Context, Result};
+use std::collections::HashSet;
 use std::fs::File;
 use std::io::{BufReader, Cursor};
 use std::path::{Path, PathBuf};
@@ -41,7 +42,14 @@ struct DeqpMock {
     pub renderer_check: String,
     pub version_check: String,
     pub extensions_check: Option<MaybeTempFile>,
+    pub vk_features_check: Option<MaybeTempFile>,
     pub includes: Vec<String>,
+    pub fraction: Option<usize>,
+    pub shard_timings: Option<tempfile::TempPath>,
+    // suite.toml-only: this block's relative share of the suite's worker
+    // pool (written as `weight = N`; see DeqpMock::with_jobs).
+    pub jobs: Option<usize>,
+    pub junit_output: bool,
 }
 
 impl DeqpMock {
@@ -105,6 +113,27 @@ impl DeqpMock {
             child.arg(file.as_ref().as_os_str());
         }
 
+        if let Some(file) = &self.vk_features_check {
+            child.arg("--vk-features-check");
+            child.arg(file.as_ref().as_os_str());
+        }
+
+        if let Some(fraction) = self.fraction {
+            child.arg("--fraction");
+            child.arg(fraction.to_string());
+        }
+
+        if let Some(shard_timings) = &self.shard_timings {
+            child.arg("--shard-timings");
+            child.arg(shard_timings);
+        }
+
+        let junit_xml_path = output_dir.path().join("junit.xml");
+        if self.junit_output {
+            child.arg("--junit-xml");
+            child.arg(&junit_xml_path);
+        }
+
         add_includes(child, &self.includes);
 
         for arg in &self.runner_args {
@@ -125,6 +154,12 @@ impl DeqpMock {
             .with_context(|| format!("opening {:?}", &results_path))
             .and_then(|mut f| RunnerResults::from_csv(&mut f).context("reading results.csv"));
 
+        let junit_xml = if self.junit_output {
+            Some(std::fs::read_to_string(&junit_xml_path).context("reading --junit-xml report")?)
+        } else {
+            None
+        };
+
         output_dir.close().context("deleting temp output dir")?;
 
         Ok(RunnerCommandResult {
@@ -132,6 +167,7 @@ impl DeqpMock {
             stdout: String::from_utf8(output.stdout).context("UTF-8 of stdout")?,
             stderr: String::from_utf8(output.stderr).context("UTF-8 of stderr")?,
             results,
+            junit_xml,
         })
     }
 
@@ -198,6 +234,62 @@ impl DeqpMock {
         self.extensions_check = Some(path);
         self
     }
+
+    pub fn with_vk_features_check(&mut self, path: MaybeTempFile) -> &mut DeqpMock {
+        self.vk_features_check = Some(path);
+        self
+    }
+
+    pub fn with_shard(&mut self, shard: &str) -> &mut DeqpMock {
+        self.runner_args.push("--shard".into());
+        self.runner_args.push(shard.to_owned());
+        self
+    }
+
+    pub fn with_shuffle_seed(&mut self, seed: u64) -> &mut DeqpMock {
+        self.runner_args.push("--shuffle-seed".into());
+        self.runner_args.push(seed.to_string());
+        self
+    }
+
+    pub fn with_shard_timings(&mut self, data: impl AsRef<str>) -> &mut DeqpMock {
+        self.shard_timings =
+            Some(tempfile(data).context("writing --shard-timings file").unwrap());
+        self
+    }
+
+    pub fn with_fail_fast(&mut self, n: u32) -> &mut DeqpMock {
+        self.runner_args.push("--fail-fast".into());
+        self.runner_args.push(n.to_string());
+        self
+    }
+
+    pub fn with_retries(&mut self, n: u32) -> &mut DeqpMock {
+        self.runner_args.push("--flake-retries".into());
+        self.runner_args.push(n.to_string());
+        self
+    }
+
+    pub fn with_fraction(&mut self, fraction: usize) -> &mut DeqpMock {
+        self.fraction = Some(fraction);
+        self
+    }
+
+    // Requests a --junit-xml report, readable back afterwards via
+    // RunnerCommandResult::junit_xml.
+    pub fn with_junit_output(&mut self) -> &mut DeqpMock {
+        self.junit_output = true;
+        self
+    }
+
+    // Sets this block's relative share of a DeqpSuite's worker pool (suite.toml's
+    // `weight` key). Named with_jobs to match how suite.toml authors think about
+    // it ("give this suite more jobs"), even though the TOML key itself is
+    // `weight` to avoid colliding with the pre-existing global --jobs flag.
+    pub fn with_jobs(&mut self, jobs: usize) -> &mut DeqpMock {
+        self.jobs = Some(jobs);
+        self
+    }
 }
 
 fn mocked_deqp_runner<S: AsRef<str>>(tests: Vec<S>) -> RunnerResults {
@@ -213,10 +305,35 @@ fn result_status<S: AsRef<str>>(results: &RunnerResults, test: S) -> RunnerStatu
     results.get(test.as_ref()).unwrap().status
 }
 
+// Minimal builder for a `[[gtest]]` suite.toml block, pointed at the
+// gtest-runner binary's own mock-gtest mode (see GTestMock in
+// gtest_runner.rs for the same trick against the gtest-runner binary
+// directly).
+#[derive(Default)]
+struct GtestSuiteMock {
+    includes: Vec<String>,
+}
+
+impl GtestSuiteMock {
+    pub fn new() -> GtestSuiteMock {
+        Default::default()
+    }
+
+    pub fn with_includes(&mut self, arg: &str) -> &mut GtestSuiteMock {
+        self.includes.push(arg.to_owned());
+        self
+    }
+}
+
 #[derive(Default)]
 struct DeqpSuite {
     deqps: Vec<DeqpMock>,
+    gtests: Vec<GtestSuiteMock>,
     includes: Vec<String>,
+    // Suite-global --baseline, applied to every [[deqp]] block that doesn't
+    // set its own (see DeqpMock::with_baseline for the per-block version).
+    baseline: Option<tempfile::TempPath>,
+    junit_output: bool,
 }
 
 fn write_file_list_toml<W: Write, P: AsRef<Path>>(
@@ -287,6 +404,13 @@ impl DeqpSuite {
                 //    child.arg(arg);
                 //}
 
+                if let Some(fraction) = deqp.fraction {
+                    writeln!(toml, "fraction = {}", fraction)?;
+                }
+                if let Some(jobs) = deqp.jobs {
+                    writeln!(toml, "weight = {}", jobs)?;
+                }
+
                 writeln!(toml, "timeout = 1.0")?;
 
                 if !deqp.prefix.is_empty() {
@@ -295,6 +419,25 @@ impl DeqpSuite {
 
                 writeln!(toml)?;
             }
+
+            for gtest in &self.gtests {
+                writeln!(toml, "[[gtest]]")?;
+                writeln!(toml, r#"bin = "{}""#, env!("CARGO_BIN_EXE_gtest-runner"))?;
+                // Passed as the first arg of the gtest-runner binary (the one
+                // we just pointed "bin" at!) to trigger its mock-gtest mode.
+                writeln!(toml, r#"gtest_args = ["mock-gtest"]"#)?;
+
+                if !gtest.includes.is_empty() {
+                    write!(toml, r#"include = ["#)?;
+                    for i in &gtest.includes {
+                        write!(toml, r#""{}", "#, i)?;
+                    }
+                    writeln!(toml, "]")?;
+                }
+
+                writeln!(toml, "timeout = 1.0")?;
+                writeln!(toml)?;
+            }
             Ok(())
         }()
         .context("writing toml file")?;
@@ -311,6 +454,17 @@ impl DeqpSuite {
         let child = child.arg("--suite");
         let child = child.arg(toml.path());
 
+        if let Some(baseline) = &self.baseline {
+            child.arg("--baseline");
+            child.arg(baseline);
+        }
+
+        let junit_xml_path = output_dir.path().join("junit.xml");
+        if self.junit_output {
+            child.arg("--junit-xml");
+            child.arg(&junit_xml_path);
+        }
+
         add_includes(child, &self.includes);
 
         let output = child
@@ -324,6 +478,12 @@ impl DeqpSuite {
             .with_context(|| format!("opening {:?}", &results_path))
             .and_then(|mut f| RunnerResults::from_csv(&mut f).context("reading results.csv"));
 
+        let junit_xml = if self.junit_output {
+            Some(std::fs::read_to_string(&junit_xml_path).context("reading --junit-xml report")?)
+        } else {
+            None
+        };
+
         output_dir.close().context("deleting temp output dir")?;
 
         Ok(RunnerCommandResult {
@@ -331,6 +491,7 @@ impl DeqpSuite {
             stdout: String::from_utf8(output.stdout).context("UTF-8 of stdout")?,
             stderr: String::from_utf8(output.stderr).context("UTF-8 of stderr")?,
             results,
+            junit_xml,
         })
     }
 
@@ -339,10 +500,25 @@ impl DeqpSuite {
         self
     }
 
+    pub fn with_gtest(&mut self, gtest: GtestSuiteMock) -> &mut DeqpSuite {
+        self.gtests.push(gtest);
+        self
+    }
+
     pub fn with_includes(&mut self, arg: &str) -> &mut DeqpSuite {
         self.includes.push(arg.to_owned());
         self
     }
+
+    pub fn with_baseline(&mut self, data: impl AsRef<str>) -> &mut DeqpSuite {
+        self.baseline = Some(tempfile(data).context("writing baseline").unwrap());
+        self
+    }
+
+    pub fn with_junit_output(&mut self) -> &mut DeqpSuite {
+        self.junit_output = true;
+        self
+    }
 }
 
 #[test]
@@ -514,6 +690,81 @@ fn flake_handling() {
     }
 }
 
+// Tests --flake-retries reclassifying a test that isn't in any --flakes list
+// at all, and --record-flakes appending it for a future run to pick up.
+#[test]
+fn flake_retries_reclassify_without_flakes_list() {
+    let mut tests = Vec::new();
+    for i in 0..100 {
+        tests.push(format!("dEQP-GLES2.test.p.{}", i));
+    }
+    tests.push("dEQP-GLES2.test.flaky.0".to_string());
+
+    let record_flakes = tempfile::NamedTempFile::new()
+        .context("creating --record-flakes file")
+        .unwrap()
+        .into_temp_path();
+
+    // Loop until one of --flake-retries's reruns actually passes, so this
+    // isn't itself a flaky test (the mock flaky test is a 50/50 coin flip
+    // per invocation).
+    loop {
+        let results = DeqpMock::new()
+            .with_cases(tests.clone())
+            .with_retries(8)
+            .with_runner_arg("--record-flakes")
+            .with_runner_arg(record_flakes.to_str().unwrap())
+            .run()
+            .unwrap()
+            .results
+            .unwrap();
+
+        match result_status(&results, "dEQP-GLES2.test.flaky.0") {
+            RunnerStatus::Flake => {
+                assert_eq!(results.result_counts.flake, 1);
+                assert_eq!(results.result_counts.fail, 0);
+                break;
+            }
+            RunnerStatus::Fail => continue,
+            _ => unreachable!("bad test result"),
+        }
+    }
+
+    let recorded = std::fs::read_to_string(&record_flakes).unwrap();
+    assert!(recorded.contains("dEQP-GLES2.test.flaky.0"));
+}
+
+// --fail-on-flake turns a Flake reclassification into a failing exit code,
+// for CI configs that want to treat any flake as a hard gate.
+#[test]
+fn fail_on_flake() {
+    let mut tests = Vec::new();
+    for i in 0..100 {
+        tests.push(format!("dEQP-GLES2.test.p.{}", i));
+    }
+    tests.push("dEQP-GLES2.test.flaky.0".to_string());
+
+    loop {
+        let run = DeqpMock::new()
+            .with_cases(tests.clone())
+            .with_retries(8)
+            .with_runner_arg("--fail-on-flake")
+            .run()
+            .unwrap();
+
+        let results = run.results.unwrap();
+        match result_status(&results, "dEQP-GLES2.test.flaky.0") {
+            RunnerStatus::Flake => {
+                assert_eq!(results.result_counts.flake, 1);
+                assert_eq!(Some(1), run.status.code());
+                break;
+            }
+            RunnerStatus::Fail => continue,
+            _ => unreachable!("bad test result"),
+        }
+    }
+}
+
 #[test]
 fn baseline() {
     let mut tests = Vec::new();
@@ -603,6 +854,347 @@ fn results_serialization() {
     );
 }
 
+#[test]
+fn junit_report_serialization() {
+    let mut tests = Vec::new();
+    for i in 0..50 {
+        tests.push(format!("dEQP-GLES2.test.p.{}", i));
+    }
+    for i in 0..30 {
+        tests.push(format!("dEQP-GLES2.test.f.{}", i));
+    }
+    for i in 0..20 {
+        tests.push(format!("dEQP-GLES2.test.s.{}", i));
+    }
+    for i in 0..10 {
+        tests.push(format!("dEQP-GLES2.test.m.{}", i));
+    }
+    tests.push("dEQP-GLES2.test.c.foo".to_string());
+    let results = mocked_deqp_runner(tests);
+
+    let mut junit_xml = Cursor::new(Vec::new());
+    results.write_junit_report(&mut junit_xml, "deqp").unwrap();
+    let junit_xml = String::from_utf8(junit_xml.into_inner()).unwrap();
+
+    let doc = roxmltree::Document::parse(&junit_xml).unwrap();
+    let testcases: Vec<roxmltree::Node> = doc
+        .descendants()
+        .filter(|n| n.has_tag_name("testcase"))
+        .collect();
+    assert_eq!(testcases.len(), results.result_counts.total as usize);
+
+    let failures = testcases
+        .iter()
+        .filter(|tc| tc.children().any(|c| c.has_tag_name("failure")))
+        .count();
+    let errors = testcases
+        .iter()
+        .filter(|tc| tc.children().any(|c| c.has_tag_name("error")))
+        .count();
+    assert_eq!(
+        failures as u32,
+        results.result_counts.fail + results.result_counts.missing
+    );
+    assert_eq!(
+        errors as u32,
+        results.result_counts.crash + results.result_counts.timeout
+    );
+
+    // Dotted test name prefixes should become the testcase's classname.
+    let crash_case = testcases
+        .iter()
+        .find(|tc| tc.attribute("name") == Some("foo"))
+        .unwrap();
+    assert_eq!(crash_case.attribute("classname"), Some("dEQP-GLES2.test.c"));
+}
+
+// End-to-end test of --junit-xml actually getting written by a real `deqp-runner run`
+// invocation (junit_report_serialization above only exercises write_junit_report directly).
+#[test]
+fn junit_output() {
+    let mut deqp = DeqpMock::new();
+    deqp.with_cases(vec!["dEQP-GLES2.test.p.1", "dEQP-GLES2.test.f.1"]);
+    deqp.with_junit_output();
+
+    let run = deqp.run().unwrap();
+    let junit_xml = run.junit_xml.unwrap();
+
+    let doc = roxmltree::Document::parse(&junit_xml).unwrap();
+    let testcases: Vec<roxmltree::Node> = doc
+        .descendants()
+        .filter(|n| n.has_tag_name("testcase"))
+        .collect();
+    assert_eq!(testcases.len(), 2);
+}
+
+// End-to-end test of the standalone `deqp-runner json` subcommand, which has
+// no dedicated coverage anywhere else (unlike `--junit-xml` above).
+#[test]
+fn json_subcommand() {
+    use deqp_runner::RunnerResult;
+
+    let mut results = RunnerResults::new();
+    results.record_result(RunnerResult {
+        test: "dEQP-GLES2.test.p".to_string(),
+        status: RunnerStatus::Pass,
+        duration: 0.1,
+        subtest: false,
+        flake_retries: 0,
+    });
+    results.record_result(RunnerResult {
+        test: "dEQP-GLES2.test.f".to_string(),
+        status: RunnerStatus::Fail,
+        duration: 0.2,
+        subtest: false,
+        flake_retries: 0,
+    });
+
+    let results_csv = tempfile::NamedTempFile::new().unwrap();
+    results
+        .write_results(&mut File::create(results_csv.path()).unwrap())
+        .unwrap();
+
+    let output = tempfile::NamedTempFile::new().unwrap();
+
+    let status = Command::new(env!("CARGO_BIN_EXE_deqp-runner"))
+        .arg("json")
+        .arg("--results")
+        .arg(results_csv.path())
+        .arg("--output")
+        .arg(output.path())
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let report = std::fs::read_to_string(output.path()).unwrap();
+    let events: Vec<serde_json::Value> = report
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(events.len(), 4);
+
+    assert_eq!(events[0]["type"], "suite");
+    assert_eq!(events[0]["event"], "started");
+    assert_eq!(events[0]["test_count"], 2);
+
+    let test_event = |name: &str| {
+        events
+            .iter()
+            .find(|e| e["type"] == "test" && e["name"] == name)
+            .unwrap()
+    };
+    assert_eq!(test_event("dEQP-GLES2.test.p")["event"], "ok");
+    assert_eq!(test_event("dEQP-GLES2.test.p")["status"], "Pass");
+    assert_eq!(test_event("dEQP-GLES2.test.f")["event"], "failed");
+    assert_eq!(test_event("dEQP-GLES2.test.f")["status"], "Fail");
+
+    let summary = events.last().unwrap();
+    assert_eq!(summary["type"], "suite");
+    assert_eq!(summary["event"], "failed");
+    assert_eq!(summary["pass"], 1);
+    assert_eq!(summary["fail"], 1);
+}
+
+// write_timings/read_timings/compare_timings round-trip, exercising the
+// --timings-output / compare-timings perf-regression-gate path.
+#[test]
+fn timings_comparison() {
+    use deqp_runner::{compare_timings, read_timings, RunnerResult};
+
+    let mut baseline = RunnerResults::new();
+    for (test, duration) in [
+        ("dEQP-GLES2.test.steady", 0.010),
+        ("dEQP-GLES2.test.slower", 0.010),
+        ("dEQP-GLES2.test.dropped", 0.010),
+    ] {
+        baseline.record_result(RunnerResult {
+            test: test.to_owned(),
+            status: RunnerStatus::Pass,
+            duration,
+            subtest: false,
+            flake_retries: 0,
+        });
+    }
+
+    let mut new = RunnerResults::new();
+    for (test, duration) in [
+        ("dEQP-GLES2.test.steady", 0.010),
+        ("dEQP-GLES2.test.slower", 0.020),
+        ("dEQP-GLES2.test.added", 0.010),
+    ] {
+        new.record_result(RunnerResult {
+            test: test.to_owned(),
+            status: RunnerStatus::Pass,
+            duration,
+            subtest: false,
+            flake_retries: 0,
+        });
+    }
+
+    let mut baseline_csv = Cursor::new(Vec::new());
+    baseline.write_timings(&mut baseline_csv).unwrap();
+    baseline_csv.set_position(0);
+    let baseline_timings = read_timings(&mut baseline_csv).unwrap();
+
+    let mut new_csv = Cursor::new(Vec::new());
+    new.write_timings(&mut new_csv).unwrap();
+    new_csv.set_position(0);
+    let new_timings = read_timings(&mut new_csv).unwrap();
+
+    // "dropped"/"added" aren't present in both timings files, so only the
+    // two common cases produce a delta.
+    let deltas = compare_timings(&baseline_timings, &new_timings);
+    assert_eq!(deltas.len(), 2);
+
+    // Sorted worst-slowdown-first: "slower" (2x) should come before "steady" (1x).
+    assert_eq!(deltas[0].test, "dEQP-GLES2.test.slower");
+    assert!((deltas[0].ratio - 2.0).abs() < 0.01);
+    assert_eq!(deltas[1].test, "dEQP-GLES2.test.steady");
+    assert!((deltas[1].ratio - 1.0).abs() < 0.01);
+}
+
+fn test_names(results: &RunnerResults) -> HashSet<String> {
+    results
+        .sorted_results()
+        .into_iter()
+        .map(|r| r.test.clone())
+        .collect()
+}
+
+#[test]
+fn shard_covers_caselist_disjointly() {
+    let tests: Vec<String> = (0..1000)
+        .map(|i| format!("dEQP-GLES2.test.p.{}", i))
+        .collect();
+    let all_tests: HashSet<String> = tests.iter().cloned().collect();
+
+    let shard1 = DeqpMock::new()
+        .with_cases(tests.clone())
+        .with_shard("1/2")
+        .with_shuffle_seed(42)
+        .run()
+        .unwrap()
+        .results
+        .unwrap();
+    let shard2 = DeqpMock::new()
+        .with_cases(tests.clone())
+        .with_shard("2/2")
+        .with_shuffle_seed(42)
+        .run()
+        .unwrap()
+        .results
+        .unwrap();
+
+    let shard1_tests = test_names(&shard1);
+    let shard2_tests = test_names(&shard2);
+
+    assert!(shard1_tests.is_disjoint(&shard2_tests));
+    assert_eq!(
+        shard1_tests.union(&shard2_tests).cloned().collect::<HashSet<_>>(),
+        all_tests
+    );
+
+    // Re-running the same shard with the same seed reproduces the same set of tests.
+    let shard1_again = DeqpMock::new()
+        .with_cases(tests)
+        .with_shard("1/2")
+        .with_shuffle_seed(42)
+        .run()
+        .unwrap()
+        .results
+        .unwrap();
+    assert_eq!(shard1_tests, test_names(&shard1_again));
+}
+
+// With --shard-timings, cases are assigned by greedy longest-processing-time
+// bin-packing instead of a hash, so we can predict exactly which shard each
+// case lands in: the two 100ms cases each seed a shard (ties going to the
+// lowest-index shard), then the 1ms case and the untimed (0ms) case each go
+// to whichever shard is cheapest at that point.
+#[test]
+fn shard_with_timings_balances_by_duration() {
+    let tests = vec![
+        "dEQP-GLES2.test.heavy_a",
+        "dEQP-GLES2.test.heavy_b",
+        "dEQP-GLES2.test.light",
+        "dEQP-GLES2.test.untimed",
+    ];
+    let timings = "dEQP-GLES2.test.heavy_a,100\n\
+                    dEQP-GLES2.test.heavy_b,100\n\
+                    dEQP-GLES2.test.light,1\n";
+
+    let shard1 = DeqpMock::new()
+        .with_cases(tests.clone())
+        .with_shard("1/2")
+        .with_shard_timings(timings)
+        .run()
+        .unwrap()
+        .results
+        .unwrap();
+    let shard2 = DeqpMock::new()
+        .with_cases(tests.clone())
+        .with_shard("2/2")
+        .with_shard_timings(timings)
+        .run()
+        .unwrap()
+        .results
+        .unwrap();
+
+    let shard1_tests = test_names(&shard1);
+    let shard2_tests = test_names(&shard2);
+
+    assert!(shard1_tests.is_disjoint(&shard2_tests));
+    assert_eq!(
+        shard1_tests
+            .union(&shard2_tests)
+            .cloned()
+            .collect::<HashSet<_>>(),
+        tests.iter().map(|s| s.to_string()).collect::<HashSet<_>>()
+    );
+
+    assert_eq!(
+        shard1_tests,
+        ["dEQP-GLES2.test.heavy_a", "dEQP-GLES2.test.light"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect::<HashSet<_>>()
+    );
+    assert_eq!(
+        shard2_tests,
+        ["dEQP-GLES2.test.heavy_b", "dEQP-GLES2.test.untimed"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect::<HashSet<_>>()
+    );
+}
+
+#[test]
+fn fail_fast_truncates_run() {
+    let mut tests = Vec::new();
+    for i in 0..20 {
+        tests.push(format!("dEQP-GLES2.test.p.{}", i));
+        tests.push(format!("dEQP-GLES2.test.f.{}", i));
+    }
+    let total_cases = tests.len();
+
+    let results = DeqpMock::new()
+        .with_cases(tests)
+        .with_fail_fast(3)
+        .with_runner_arg("--tests-per-group")
+        .with_runner_arg("1")
+        .with_runner_arg("--jobs")
+        .with_runner_arg("1")
+        .run()
+        .unwrap()
+        .results
+        .unwrap();
+
+    let hard_failures =
+        results.result_counts.fail + results.result_counts.crash + results.result_counts.timeout;
+    assert_eq!(hard_failures, 3);
+    assert!((results.result_counts.total as usize) < total_cases);
+}
+
 #[test]
 fn missing_skips() {
     let results = DeqpMock::new()
@@ -706,6 +1298,92 @@ fn suite_fail() {
     assert_eq!(counts.fail, 1);
 }
 
+// A [[gtest]] block runs alongside [[deqp]] blocks in the same suite.toml,
+// feeding into the same grouping/parallelization machinery and reporting one
+// combined pass/fail summary.
+#[test]
+fn suite_deqp_and_gtest() {
+    let mut deqp = DeqpMock::new();
+    deqp.with_cases(vec!["dEQP-GLES2.test.p.1"]);
+
+    let mut gtest = GtestSuiteMock::new();
+    gtest.with_includes("pass").with_includes("fail");
+
+    let results = DeqpSuite::new()
+        .with_deqp(deqp)
+        .with_gtest(gtest)
+        .run()
+        .unwrap();
+    assert_eq!(Some(1), results.status.code());
+    let counts = results.results.unwrap().result_counts;
+    assert_eq!(counts.pass, 11);
+    assert_eq!(counts.fail, 12);
+}
+
+// A suite-global --baseline applies to every [[deqp]] block that doesn't set
+// its own, same as a single `run`'s --baseline, so driver CI can gate a
+// whole suite.toml on regressions against one known-failures list.
+#[test]
+fn suite_baseline() {
+    let mut deqp1 = DeqpMock::new();
+    deqp1.with_cases(vec!["dEQP-GLES2.test.p.1", "dEQP-GLES2.test.f.1"]);
+    let results = DeqpSuite::new()
+        .with_deqp(deqp1)
+        .with_baseline("dEQP-GLES2.test.f.1,Fail")
+        .run()
+        .unwrap();
+    assert_eq!(Some(0), results.status.code());
+    let counts = results.results.unwrap().result_counts;
+    assert_eq!(counts.pass, 1);
+    assert_eq!(counts.expected_fail, 1);
+}
+
+// A case baselined as Fail that comes back Pass is an UnexpectedPass, which
+// still fails the suite run even though every individual test "passed" --
+// the baseline needs updating, same as the single-deqp `baseline` test.
+#[test]
+fn suite_baseline_unexpected_pass() {
+    let mut deqp1 = DeqpMock::new();
+    deqp1.with_cases(vec!["dEQP-GLES2.test.p.1"]);
+    let results = DeqpSuite::new()
+        .with_deqp(deqp1)
+        .with_baseline("dEQP-GLES2.test.p.1,Fail")
+        .run()
+        .unwrap();
+    assert_eq!(Some(1), results.status.code());
+    let counts = results.results.unwrap().result_counts;
+    assert_eq!(counts.unexpected_pass, 1);
+}
+
+// A `fraction` on one suite.toml [[deqp]] block thins out just that block,
+// while another block with no fraction set still runs to completion, and
+// both contribute to the suite's merged results.csv.
+#[test]
+fn suite_fraction_per_deqp() {
+    let thinned_tests: Vec<String> = (0..100)
+        .map(|i| format!("dEQP-GLES2.thinned.test.p.{}", i))
+        .collect();
+    let full_tests: Vec<String> = (0..20)
+        .map(|i| format!("dEQP-GLES2.full.test.p.{}", i))
+        .collect();
+
+    let mut deqp1 = DeqpMock::new();
+    deqp1.with_cases(thinned_tests).with_fraction(2);
+    let mut deqp2 = DeqpMock::new();
+    deqp2.with_cases(full_tests);
+
+    let results = DeqpSuite::new()
+        .with_deqp(deqp1)
+        .with_deqp(deqp2)
+        .run()
+        .unwrap();
+    assert_eq!(Some(0), results.status.code());
+
+    let counts = results.results.unwrap().result_counts;
+    // 100 cases at a 1/2 fraction plus 20 full-run cases.
+    assert_eq!(counts.pass, 50 + 20);
+}
+
 // Same-named test between deqps should be a fail since you can't distinguish them.
 #[test]
 fn suite_dupe_test() {
@@ -1013,6 +1691,46 @@ fn vk_renderer_check_fail() {
     assert_eq!(Some(1), results.status.code());
 }
 
+// The mock dEQP-VK.info.device fixture doesn't emit any "deviceExtension: "
+// lines today, so any non-empty expected list is reported Missing. This
+// still exercises the real --extensions-check/qpa_vk_extensions_check path
+// for dEQP-VK, same as gl_extensions_check_missing does for GL.
+#[test]
+fn vk_extensions_check_missing() -> Result<()> {
+    let test_ext_file = lines_tempfile(vec!["VK_KHR_ham_sandwich"])
+        .context("writing list of VK extensions to test for")?;
+
+    let mut deqp = DeqpMock::new();
+    deqp.with_cases(vec!["dEQP-VK.test.p.1"]);
+    deqp.with_extensions_check(MaybeTempFile::Temp(test_ext_file));
+
+    let results = deqp.run().unwrap();
+
+    assert_eq!(Some(1), results.status.code());
+    assert!(results.stderr.contains("Missing: VK_KHR_ham_sandwich"));
+
+    Ok(())
+}
+
+// Same rationale as vk_extensions_check_missing: the mock fixture reports no
+// VkPhysicalDeviceFeatures today, so any feature we assert on comes back Missing.
+#[test]
+fn vk_features_check_missing() -> Result<()> {
+    let test_features_file = lines_tempfile(vec!["robustBufferAccess=true"])
+        .context("writing list of VK features to test for")?;
+
+    let mut deqp = DeqpMock::new();
+    deqp.with_cases(vec!["dEQP-VK.test.p.1"]);
+    deqp.with_vk_features_check(MaybeTempFile::Temp(test_features_file));
+
+    let results = deqp.run().unwrap();
+
+    assert_eq!(Some(1), results.status.code());
+    assert!(results.stderr.contains("Missing: robustBufferAccess"));
+
+    Ok(())
+}
+
 #[test]
 fn suite_renderer_version_check() {
     let mut deqp = DeqpMock::new();
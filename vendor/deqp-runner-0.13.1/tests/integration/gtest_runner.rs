@@ -81,6 +81,7 @@ impl GTestMock {
             stdout: String::from_utf8(output.stdout).context("UTF-8 of stdout")?,
             stderr: String::from_utf8(output.stderr).context("UTF-8 of stderr")?,
             results,
+            junit_xml: None,
         })
     }
 
@@ -141,6 +142,18 @@ fn crash() {
     assert_eq!(results.result_counts.crash, 1);
 }
 
+/// The test itself reports fine, but a LeakSanitizer report on stderr taints
+/// the whole (single-test, in this caselist) batch as failed.
+#[test]
+fn leak() {
+    let result = GTestMock::new().run(vec!["leak"]).unwrap();
+    assert!(result.stderr.contains("LeakSanitizer"));
+    assert_eq!(result.status.code(), Some(1));
+    assert!(result.stdout.contains("Fail: 1"));
+    let results = result.results.unwrap();
+    assert_eq!(results.result_counts.fail, 1);
+}
+
 #[test]
 fn logs_stderr() {
     let result = GTestMock::new().run(vec!["stderr"]).unwrap();
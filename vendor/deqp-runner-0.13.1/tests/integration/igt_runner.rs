@@ -0,0 +1,128 @@
+use super::*;
+use ::deqp_runner::RunnerResults;
+use anyhow::{Context, Result};
+use std::process::{Command, Stdio};
+
+/// Builder for a mocked igt-runner invocation
+#[derive(Default)]
+struct IgtMock {
+    subtests: Vec<String>,
+}
+
+impl IgtMock {
+    pub fn new() -> IgtMock {
+        Default::default()
+    }
+
+    pub fn with_subtest<S: AsRef<str>>(&mut self, subtest: S) -> &mut IgtMock {
+        self.subtests.push(subtest.as_ref().to_owned());
+        self
+    }
+
+    pub fn run(&self) -> Result<RunnerCommandResult> {
+        let output_dir = tempfile::tempdir().context("Creating output dir")?;
+
+        // Get the location of our igt-runner binary from rustc
+        let igt_runner = env!("CARGO_BIN_EXE_igt-runner");
+
+        let caselist = lines_tempfile(
+            self.subtests
+                .iter()
+                .map(|subtest| format!("mock-binary@{}", subtest)),
+        )
+        .context("writing caselist")?;
+
+        let mut cmd = Command::new(&igt_runner);
+        let child = cmd.stdout(Stdio::piped()).stderr(Stdio::piped());
+        let child = child.arg("run");
+
+        let child = child.arg("--igt-folder");
+        let child = child.arg(igt_runner);
+
+        let child = child.arg("--caselist");
+        let child = child.arg(&caselist);
+
+        let child = child.arg("--output");
+        let child = child.arg(output_dir.path());
+
+        let child = child.arg("--timeout");
+        let child = child.arg("1");
+
+        child.arg("--");
+        // Passed as the first arg of the "igt" binary (the igt-runner we
+        // passed as --igt-folder!) to trigger its mock-igt mode.
+        child.arg("mock-igt");
+
+        let output = child
+            .spawn()
+            .with_context(|| format!("Spawning {:?}", igt_runner))?
+            .wait_with_output()
+            .context("waiting for igt-runner")?;
+
+        let results_path = output_dir.path().to_owned().join("results.csv");
+        let results = std::fs::File::open(&results_path)
+            .with_context(|| format!("opening {:?}", &results_path))
+            .and_then(|mut f| RunnerResults::from_csv(&mut f).context("reading results.csv"));
+
+        // Debug knob, flip it to save the output dirs so you can look into why things failed.
+        if false {
+            output_dir.into_path();
+        } else {
+            output_dir.close().context("deleting temp output dir")?;
+        }
+
+        Ok(RunnerCommandResult {
+            status: output.status,
+            stdout: String::from_utf8(output.stdout).context("UTF-8 of stdout")?,
+            stderr: String::from_utf8(output.stderr).context("UTF-8 of stderr")?,
+            results,
+            junit_xml: None,
+        })
+    }
+}
+
+#[test]
+fn pass_and_skip() {
+    let mut igt = IgtMock::new();
+    igt.with_subtest("pass").with_subtest("skip");
+
+    let result = igt.run().unwrap();
+    assert_eq!(result.status.code(), Some(0));
+    assert!(result.stdout.contains("Pass: 1"));
+    assert!(result.stdout.contains("Skip: 1"));
+    let results = result.results.unwrap();
+    assert_eq!(results.result_counts.pass, 1);
+    assert_eq!(results.result_counts.skip, 1);
+}
+
+#[test]
+fn fail_and_warn() {
+    let mut igt = IgtMock::new();
+    igt.with_subtest("fail").with_subtest("warn");
+
+    let result = igt.run().unwrap();
+    assert_eq!(result.status.code(), Some(1));
+    assert!(result.stdout.contains("Fail: 2"));
+    let results = result.results.unwrap();
+    assert_eq!(results.result_counts.fail, 2);
+}
+
+/// A subtest that starts but the binary exits (crashes) without ever
+/// reporting "Subtest x: RESULT" is attributed as a Crash.
+#[test]
+fn crash() {
+    let result = IgtMock::new().with_subtest("crash").run().unwrap();
+    assert_eq!(result.status.code(), Some(1));
+    assert!(result.stdout.contains("Crash: 1"));
+    let results = result.results.unwrap();
+    assert_eq!(results.result_counts.crash, 1);
+}
+
+#[test]
+fn timeout() {
+    let result = IgtMock::new().with_subtest("timeout").run().unwrap();
+    assert_eq!(result.status.code(), Some(1));
+    assert!(result.stdout.contains("Timeout: 1"));
+    let results = result.results.unwrap();
+    assert_eq!(results.result_counts.timeout, 1);
+}
@@ -5,6 +5,7 @@ use std::{io::prelude::*, path::PathBuf};
 /// Integration test binary.  See https://matklad.github.io/2021/02/27/delete-cargo-integration-tests.html
 mod deqp_runner;
 mod gtest_runner;
+mod igt_runner;
 mod piglit_runner;
 
 // All the output we capture from an invocation of deqp-runner
@@ -14,6 +15,9 @@ struct RunnerCommandResult {
     stderr: String,
 
     results: Result<RunnerResults>,
+
+    // Contents of the --junit-xml report, if the runner was asked to write one.
+    junit_xml: Option<String>,
 }
 
 pub fn tempfile<S: AsRef<str>>(data: S) -> Result<tempfile::TempPath> {
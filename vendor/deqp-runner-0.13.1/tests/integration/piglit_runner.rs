@@ -112,6 +112,7 @@ impl PiglitMock {
             stdout: String::from_utf8(output.stdout).context("UTF-8 of stdout")?,
             stderr: String::from_utf8(output.stderr).context("UTF-8 of stderr")?,
             results,
+            junit_xml: None,
         })
     }
 
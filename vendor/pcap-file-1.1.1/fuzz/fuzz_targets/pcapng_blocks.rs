@@ -0,0 +1,25 @@
+#![no_main]
+
+use byteorder::{BigEndian, LittleEndian};
+use libfuzzer_sys::fuzz_target;
+use pcap_file::pcapng::Block;
+
+// Drives `Block::from_slice` (and so every block type's own `from_slice`,
+// including `InterfaceDescriptionBlock`'s) over arbitrary bytes. The first
+// byte of the input picks the byte order the rest is parsed as, same as
+// `SectionHeaderBlock::from_slice` picks it off the section's byte-order
+// magic in real captures. A malformed block must come back as a
+// `PcapError`, never a panic.
+fuzz_target!(|data: &[u8]| {
+    let (selector, body) = match data.split_first() {
+        Some(split) => split,
+        None => return
+    };
+
+    let _ = if selector & 1 == 0 {
+        Block::from_slice::<BigEndian>(body).map(|_| ())
+    }
+    else {
+        Block::from_slice::<LittleEndian>(body).map(|_| ())
+    };
+});
@@ -39,6 +39,19 @@ impl Endianness {
             Endianness::Little
         }
     }
+
+    /// Returns the endianness of the machine this code is compiled for, so
+    /// writers have a sensible default byte order to emit a new global
+    /// header with.
+    pub fn native() -> Self {
+
+        if cfg!(target_endian = "big") {
+            Endianness::Big
+        }
+        else {
+            Endianness::Little
+        }
+    }
 }
 
 /// Data link type
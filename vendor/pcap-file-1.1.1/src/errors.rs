@@ -0,0 +1,67 @@
+use std::fmt;
+
+/// Error type for the pcap/pcapng parsers and writers in this crate.
+#[derive(Debug)]
+pub enum PcapError {
+
+    /// Buffer too small to contain a valid header or packet, more data is needed
+    IncompleteBuffer,
+
+    /// Global header magic number didn't match any of the recognized pcap magic numbers
+    IncorrectMagicNumber,
+
+    /// A record's captured length field disagrees with the number of bytes actually present
+    PacketPayloadMismatch,
+
+    /// Invalid field value
+    InvalidField(&'static str),
+
+    /// Utf8 error
+    Utf8Error(std::str::Utf8Error),
+
+    /// Io error
+    IoError(std::io::Error)
+}
+
+impl fmt::Display for PcapError {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+
+        match self {
+            PcapError::IncompleteBuffer => write!(f, "Not enough data in the buffer to parse a complete header or packet"),
+            PcapError::IncorrectMagicNumber => write!(f, "Incorrect magic number, not a pcap file or an unsupported variant"),
+            PcapError::PacketPayloadMismatch => write!(f, "Packet capture length does not match the number of bytes actually present"),
+            PcapError::InvalidField(field) => write!(f, "Invalid field value: {}", field),
+            PcapError::Utf8Error(err) => write!(f, "Utf8 error: {}", err),
+            PcapError::IoError(err) => write!(f, "Io error: {}", err)
+        }
+    }
+}
+
+impl std::error::Error for PcapError {
+
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            PcapError::Utf8Error(err) => Some(err),
+            PcapError::IoError(err) => Some(err),
+            _ => None
+        }
+    }
+}
+
+impl From<std::io::Error> for PcapError {
+
+    fn from(err: std::io::Error) -> Self {
+        PcapError::IoError(err)
+    }
+}
+
+impl From<std::str::Utf8Error> for PcapError {
+
+    fn from(err: std::str::Utf8Error) -> Self {
+        PcapError::Utf8Error(err)
+    }
+}
+
+/// Alias for `Result<T, PcapError>`
+pub type PcapResult<T> = Result<T, PcapError>;
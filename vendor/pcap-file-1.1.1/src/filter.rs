@@ -0,0 +1,419 @@
+//! A small tcpdump-style (BPF-like) filter expression language, compiled
+//! against a known `DataLink` and evaluated directly over captured frames --
+//! no libpcap/BPF bytecode involved.
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use crate::errors::{PcapError, PcapResult};
+use crate::linklayer::NextProto;
+use crate::DataLink;
+
+/// A compiled filter expression, bound to the link type of the capture it
+/// will be run against.
+pub struct Filter {
+    expr: Expr,
+    link: DataLink
+}
+
+impl Filter {
+
+    /// Parses and compiles a tcpdump-style filter expression. `link` is used
+    /// to resolve link-layer header offsets (via `DataLink::dissect`) when
+    /// the filter is later run with `matches`.
+    pub fn compile(expr: &str, link: DataLink) -> PcapResult<Filter> {
+
+        let tokens = tokenize(expr);
+        let mut parser = Parser { tokens: &tokens, pos: 0 };
+
+        let expr = parser.parse_or()?;
+
+        if parser.pos != tokens.len() {
+            return Err(PcapError::InvalidField("Filter: unexpected trailing tokens"));
+        }
+
+        Ok(Filter { expr, link })
+    }
+
+    /// Evaluates the filter against a raw captured frame (link-layer header
+    /// included). A frame this crate can't dissect far enough to evaluate a
+    /// primitive is treated as not matching it, rather than erroring.
+    pub fn matches(&self, frame: &[u8]) -> bool {
+        eval(&self.expr, &dissect_packet(frame, self.link))
+    }
+}
+
+// ---- AST ----------------------------------------------------------------
+
+enum Expr {
+    Primitive(Primitive),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>)
+}
+
+enum Primitive {
+    Host(IpAddr),
+    SrcHost(IpAddr),
+    DstHost(IpAddr),
+
+    Net(Net),
+    SrcNet(Net),
+    DstNet(Net),
+
+    Port(u16),
+    SrcPort(u16),
+    DstPort(u16),
+
+    Proto(Protocol)
+}
+
+#[derive(Copy, Clone)]
+enum Protocol {
+    Ip,
+    Ip6,
+    Tcp,
+    Udp,
+    Arp
+}
+
+#[derive(Copy, Clone)]
+struct Net {
+    addr: IpAddr,
+    prefix_len: u8
+}
+
+impl Net {
+
+    fn contains(&self, ip: IpAddr) -> bool {
+        match (self.addr, ip) {
+            (IpAddr::V4(net), IpAddr::V4(ip)) => {
+                let mask: u32 = if self.prefix_len == 0 { 0 } else { u32::max_value() << (32 - self.prefix_len) };
+                u32::from(net) & mask == u32::from(ip) & mask
+            },
+            (IpAddr::V6(net), IpAddr::V6(ip)) => {
+                let mask: u128 = if self.prefix_len == 0 { 0 } else { u128::max_value() << (128 - self.prefix_len) };
+                u128::from(net) & mask == u128::from(ip) & mask
+            },
+            _ => false
+        }
+    }
+}
+
+// ---- Tokenizer ------------------------------------------------------------
+
+fn tokenize(expr: &str) -> Vec<String> {
+
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+
+    for c in expr.chars() {
+        match c {
+            '(' | ')' => {
+                if !current.is_empty() {
+                    tokens.push(std::mem::replace(&mut current, String::new()));
+                }
+                tokens.push(c.to_string());
+            },
+            c if c.is_whitespace() => {
+                if !current.is_empty() {
+                    tokens.push(std::mem::replace(&mut current, String::new()));
+                }
+            },
+            c => current.push(c)
+        }
+    }
+
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+
+    tokens
+}
+
+// ---- Parser (recursive descent, tcpdump's `not` > `and` > `or` precedence) -
+
+struct Parser<'a> {
+    tokens: &'a [String],
+    pos: usize
+}
+
+impl<'a> Parser<'a> {
+
+    fn peek(&self) -> Option<&str> {
+        self.tokens.get(self.pos).map(String::as_str)
+    }
+
+    fn is_next(&self, word: &str) -> bool {
+        self.peek().map(|t| t.eq_ignore_ascii_case(word)).unwrap_or(false)
+    }
+
+    fn next(&mut self) -> PcapResult<&'a str> {
+        let tok = self.tokens.get(self.pos)
+            .map(String::as_str)
+            .ok_or(PcapError::InvalidField("Filter: unexpected end of expression"))?;
+        self.pos += 1;
+        Ok(tok)
+    }
+
+    fn eat(&mut self, expected: &str) -> PcapResult<()> {
+        if !self.is_next(expected) {
+            return Err(PcapError::InvalidField("Filter: unexpected token"));
+        }
+        self.pos += 1;
+        Ok(())
+    }
+
+    fn parse_or(&mut self) -> PcapResult<Expr> {
+
+        let mut expr = self.parse_and()?;
+
+        while self.is_next("or") {
+            self.pos += 1;
+            let rhs = self.parse_and()?;
+            expr = Expr::Or(Box::new(expr), Box::new(rhs));
+        }
+
+        Ok(expr)
+    }
+
+    fn parse_and(&mut self) -> PcapResult<Expr> {
+
+        let mut expr = self.parse_not()?;
+
+        while self.is_next("and") {
+            self.pos += 1;
+            let rhs = self.parse_not()?;
+            expr = Expr::And(Box::new(expr), Box::new(rhs));
+        }
+
+        Ok(expr)
+    }
+
+    fn parse_not(&mut self) -> PcapResult<Expr> {
+
+        if self.is_next("not") {
+            self.pos += 1;
+            return Ok(Expr::Not(Box::new(self.parse_not()?)));
+        }
+
+        self.parse_primary()
+    }
+
+    fn parse_primary(&mut self) -> PcapResult<Expr> {
+
+        if self.peek() == Some("(") {
+            self.pos += 1;
+            let expr = self.parse_or()?;
+            self.eat(")")?;
+            return Ok(expr);
+        }
+
+        Ok(Expr::Primitive(self.parse_primitive()?))
+    }
+
+    fn parse_primitive(&mut self) -> PcapResult<Primitive> {
+
+        let keyword = self.next()?.to_ascii_lowercase();
+
+        match keyword.as_str() {
+            "host" => Ok(Primitive::Host(self.parse_ip()?)),
+            "net" => Ok(Primitive::Net(self.parse_net()?)),
+            "port" => Ok(Primitive::Port(self.parse_port()?)),
+
+            "src" => match self.next()?.to_ascii_lowercase().as_str() {
+                "host" => Ok(Primitive::SrcHost(self.parse_ip()?)),
+                "net" => Ok(Primitive::SrcNet(self.parse_net()?)),
+                "port" => Ok(Primitive::SrcPort(self.parse_port()?)),
+                _ => Err(PcapError::InvalidField("Filter: expected host/net/port after src"))
+            },
+            "dst" => match self.next()?.to_ascii_lowercase().as_str() {
+                "host" => Ok(Primitive::DstHost(self.parse_ip()?)),
+                "net" => Ok(Primitive::DstNet(self.parse_net()?)),
+                "port" => Ok(Primitive::DstPort(self.parse_port()?)),
+                _ => Err(PcapError::InvalidField("Filter: expected host/net/port after dst"))
+            },
+
+            "ip" => Ok(Primitive::Proto(Protocol::Ip)),
+            "ip6" => Ok(Primitive::Proto(Protocol::Ip6)),
+            "tcp" => Ok(Primitive::Proto(Protocol::Tcp)),
+            "udp" => Ok(Primitive::Proto(Protocol::Udp)),
+            "arp" => Ok(Primitive::Proto(Protocol::Arp)),
+
+            _ => Err(PcapError::InvalidField("Filter: unrecognized primitive"))
+        }
+    }
+
+    fn parse_ip(&mut self) -> PcapResult<IpAddr> {
+        self.next()?.parse().map_err(|_| PcapError::InvalidField("Filter: invalid IP address"))
+    }
+
+    fn parse_net(&mut self) -> PcapResult<Net> {
+
+        let tok = self.next()?;
+        let slash = tok.find('/').ok_or(PcapError::InvalidField("Filter: net primitive requires a /prefix"))?;
+        let (addr, prefix_len) = tok.split_at(slash);
+
+        let addr: IpAddr = addr.parse().map_err(|_| PcapError::InvalidField("Filter: invalid net address"))?;
+        let prefix_len: u8 = prefix_len[1..].parse().map_err(|_| PcapError::InvalidField("Filter: invalid net prefix length"))?;
+
+        let max_prefix_len = if addr.is_ipv4() { 32 } else { 128 };
+        if prefix_len > max_prefix_len {
+            return Err(PcapError::InvalidField("Filter: net prefix length out of range"));
+        }
+
+        Ok(Net { addr, prefix_len })
+    }
+
+    fn parse_port(&mut self) -> PcapResult<u16> {
+        self.next()?.parse().map_err(|_| PcapError::InvalidField("Filter: invalid port number"))
+    }
+}
+
+// ---- Evaluation -----------------------------------------------------------
+
+enum PacketInfo {
+    Ip { src: IpAddr, dst: IpAddr, is_ipv6: bool, protocol: u8, src_port: Option<u16>, dst_port: Option<u16> },
+    Arp
+}
+
+fn dissect_packet(frame: &[u8], link: DataLink) -> Option<PacketInfo> {
+
+    let layer = link.dissect(frame).ok()?;
+    let payload = frame.get(layer.header_len..)?;
+
+    match layer.next {
+        NextProto::Ipv4 => dissect_ipv4(payload),
+        NextProto::Ipv6 => dissect_ipv6(payload),
+        NextProto::Arp => Some(PacketInfo::Arp),
+
+        // Link types (NULL/LOOP's address family, an unrecognized EtherType,
+        // or an unknown link type entirely) that don't directly identify
+        // IP/ARP: fall back to peeking the IP version nibble, same trick
+        // DataLink::dissect itself uses for RAW.
+        NextProto::EtherType(_) | NextProto::AddressFamily(_) | NextProto::Unknown => {
+            match payload.first()? >> 4 {
+                4 => dissect_ipv4(payload),
+                6 => dissect_ipv6(payload),
+                _ => None
+            }
+        }
+    }
+}
+
+fn dissect_ipv4(payload: &[u8]) -> Option<PacketInfo> {
+
+    if payload.len() < 20 {
+        return None;
+    }
+
+    let ihl = (payload[0] & 0x0F) as usize * 4;
+    if payload.len() < ihl {
+        return None;
+    }
+
+    let protocol = payload[9];
+    let src = IpAddr::V4(Ipv4Addr::new(payload[12], payload[13], payload[14], payload[15]));
+    let dst = IpAddr::V4(Ipv4Addr::new(payload[16], payload[17], payload[18], payload[19]));
+    let (src_port, dst_port) = ports_from_transport(protocol, &payload[ihl..]);
+
+    Some(PacketInfo::Ip { src, dst, is_ipv6: false, protocol, src_port, dst_port })
+}
+
+fn dissect_ipv6(payload: &[u8]) -> Option<PacketInfo> {
+
+    if payload.len() < 40 {
+        return None;
+    }
+
+    let protocol = payload[6];
+    let src = IpAddr::V6(ipv6_from_slice(&payload[8..24]));
+    let dst = IpAddr::V6(ipv6_from_slice(&payload[24..40]));
+    let (src_port, dst_port) = ports_from_transport(protocol, &payload[40..]);
+
+    Some(PacketInfo::Ip { src, dst, is_ipv6: true, protocol, src_port, dst_port })
+}
+
+fn ipv6_from_slice(b: &[u8]) -> Ipv6Addr {
+    Ipv6Addr::new(
+        u16::from_be_bytes([b[0], b[1]]),
+        u16::from_be_bytes([b[2], b[3]]),
+        u16::from_be_bytes([b[4], b[5]]),
+        u16::from_be_bytes([b[6], b[7]]),
+        u16::from_be_bytes([b[8], b[9]]),
+        u16::from_be_bytes([b[10], b[11]]),
+        u16::from_be_bytes([b[12], b[13]]),
+        u16::from_be_bytes([b[14], b[15]])
+    )
+}
+
+// TCP = 6, UDP = 17: the only two IP protocol numbers with a 16-bit
+// src/dst port pair at the front of their header.
+fn ports_from_transport(protocol: u8, transport: &[u8]) -> (Option<u16>, Option<u16>) {
+    match protocol {
+        6 | 17 if transport.len() >= 4 => (
+            Some(u16::from_be_bytes([transport[0], transport[1]])),
+            Some(u16::from_be_bytes([transport[2], transport[3]]))
+        ),
+        _ => (None, None)
+    }
+}
+
+fn eval(expr: &Expr, info: &Option<PacketInfo>) -> bool {
+    match expr {
+        Expr::Primitive(p) => eval_primitive(p, info),
+        Expr::And(a, b) => eval(a, info) && eval(b, info),
+        Expr::Or(a, b) => eval(a, info) || eval(b, info),
+        Expr::Not(a) => !eval(a, info)
+    }
+}
+
+fn eval_primitive(primitive: &Primitive, info: &Option<PacketInfo>) -> bool {
+
+    let info = match info {
+        Some(info) => info,
+        None => return false
+    };
+
+    match primitive {
+        Primitive::Proto(Protocol::Arp) => matches!(info, PacketInfo::Arp),
+        Primitive::Proto(Protocol::Ip) => matches!(info, PacketInfo::Ip { is_ipv6: false, .. }),
+        Primitive::Proto(Protocol::Ip6) => matches!(info, PacketInfo::Ip { is_ipv6: true, .. }),
+        Primitive::Proto(Protocol::Tcp) => matches!(info, PacketInfo::Ip { protocol: 6, .. }),
+        Primitive::Proto(Protocol::Udp) => matches!(info, PacketInfo::Ip { protocol: 17, .. }),
+
+        Primitive::Host(ip) => ip_matches(info, *ip, true, true),
+        Primitive::SrcHost(ip) => ip_matches(info, *ip, true, false),
+        Primitive::DstHost(ip) => ip_matches(info, *ip, false, true),
+
+        Primitive::Net(net) => net_matches(info, net, true, true),
+        Primitive::SrcNet(net) => net_matches(info, net, true, false),
+        Primitive::DstNet(net) => net_matches(info, net, false, true),
+
+        Primitive::Port(port) => port_matches(info, *port, true, true),
+        Primitive::SrcPort(port) => port_matches(info, *port, true, false),
+        Primitive::DstPort(port) => port_matches(info, *port, false, true)
+    }
+}
+
+fn ip_matches(info: &PacketInfo, ip: IpAddr, check_src: bool, check_dst: bool) -> bool {
+    match info {
+        PacketInfo::Ip { src, dst, .. } => (check_src && *src == ip) || (check_dst && *dst == ip),
+        PacketInfo::Arp => false
+    }
+}
+
+fn net_matches(info: &PacketInfo, net: &Net, check_src: bool, check_dst: bool) -> bool {
+    match info {
+        PacketInfo::Ip { src, dst, .. } => (check_src && net.contains(*src)) || (check_dst && net.contains(*dst)),
+        PacketInfo::Arp => false
+    }
+}
+
+fn port_matches(info: &PacketInfo, port: u16, check_src: bool, check_dst: bool) -> bool {
+    match info {
+        PacketInfo::Ip { src_port, dst_port, .. } => {
+            (check_src && *src_port == Some(port)) || (check_dst && *dst_port == Some(port))
+        },
+        PacketInfo::Arp => false
+    }
+}
@@ -0,0 +1,144 @@
+use byteorder::{ByteOrder, LittleEndian, NativeEndian};
+
+use crate::errors::{PcapError, PcapResult};
+use crate::DataLink;
+
+/// Identity of the protocol encapsulated right after a link-layer header.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum NextProto {
+
+    /// IPv4, identified either by an EtherType of `0x0800` or by the IP
+    /// version nibble on link types that carry no EtherType of their own.
+    Ipv4,
+
+    /// IPv6 (EtherType `0x86DD`, or the IP version nibble).
+    Ipv6,
+
+    /// ARP (EtherType `0x0806`).
+    Arp,
+
+    /// Some other EtherType-identified protocol this crate doesn't name.
+    EtherType(u16),
+
+    /// The host-endian address family carried by a NULL/LOOP header.
+    AddressFamily(u32),
+
+    /// Next protocol could not be determined -- an unrecognized link type,
+    /// or a link type whose payload this crate doesn't dissect any further
+    /// (e.g. the 802.11 MAC header following a radiotap header).
+    Unknown
+}
+
+/// Result of stripping a link-layer header off a captured frame: how many
+/// bytes the header took up, and what comes after it.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct LinkLayer {
+    pub header_len: usize,
+    pub next: NextProto
+}
+
+impl DataLink {
+
+    /// Strips this link type's header off `frame`, returning its length and
+    /// the identity of the encapsulated protocol that follows it. Mirrors
+    /// how capture-analysis tools pick the first dissector to run from the
+    /// link type. Link types this crate doesn't know how to dissect yield
+    /// `LinkLayer { header_len: 0, next: NextProto::Unknown }` rather than
+    /// an error, since "don't know how to go further" isn't a parse failure.
+    pub fn dissect(self, frame: &[u8]) -> PcapResult<LinkLayer> {
+
+        match self {
+            DataLink::ETHERNET => dissect_ethernet(frame),
+            DataLink::RAW | DataLink::IPV4 | DataLink::IPV6 => dissect_raw_ip(frame),
+            DataLink::NULL | DataLink::LOOP => dissect_null(frame),
+            DataLink::LINUX_SLL => dissect_linux_sll(frame),
+            DataLink::IEEE802_11_RADIOTAP => dissect_radiotap(frame),
+
+            _ => Ok(LinkLayer { header_len: 0, next: NextProto::Unknown })
+        }
+    }
+}
+
+fn next_proto_from_ethertype(ethertype: u16) -> NextProto {
+    match ethertype {
+        0x0800 => NextProto::Ipv4,
+        0x86DD => NextProto::Ipv6,
+        0x0806 => NextProto::Arp,
+        other => NextProto::EtherType(other)
+    }
+}
+
+fn dissect_ethernet(frame: &[u8]) -> PcapResult<LinkLayer> {
+
+    if frame.len() < 14 {
+        return Err(PcapError::IncompleteBuffer);
+    }
+
+    let mut header_len = 14;
+    let mut ethertype = u16::from(frame[12]) << 8 | u16::from(frame[13]);
+
+    // 802.1Q VLAN tag: the "EtherType" at bytes 12-13 is actually the tag
+    // protocol id 0x8100, and the real EtherType is 4 bytes further in.
+    if ethertype == 0x8100 {
+        if frame.len() < 18 {
+            return Err(PcapError::IncompleteBuffer);
+        }
+
+        header_len = 18;
+        ethertype = u16::from(frame[16]) << 8 | u16::from(frame[17]);
+    }
+
+    Ok(LinkLayer { header_len, next: next_proto_from_ethertype(ethertype) })
+}
+
+fn dissect_raw_ip(frame: &[u8]) -> PcapResult<LinkLayer> {
+
+    if frame.is_empty() {
+        return Err(PcapError::IncompleteBuffer);
+    }
+
+    let next = match frame[0] >> 4 {
+        4 => NextProto::Ipv4,
+        6 => NextProto::Ipv6,
+        _ => NextProto::Unknown
+    };
+
+    Ok(LinkLayer { header_len: 0, next })
+}
+
+fn dissect_null(frame: &[u8]) -> PcapResult<LinkLayer> {
+
+    if frame.len() < 4 {
+        return Err(PcapError::IncompleteBuffer);
+    }
+
+    let family = NativeEndian::read_u32(&frame[..4]);
+    Ok(LinkLayer { header_len: 4, next: NextProto::AddressFamily(family) })
+}
+
+fn dissect_linux_sll(frame: &[u8]) -> PcapResult<LinkLayer> {
+
+    if frame.len() < 16 {
+        return Err(PcapError::IncompleteBuffer);
+    }
+
+    let protocol = u16::from(frame[14]) << 8 | u16::from(frame[15]);
+    Ok(LinkLayer { header_len: 16, next: next_proto_from_ethertype(protocol) })
+}
+
+fn dissect_radiotap(frame: &[u8]) -> PcapResult<LinkLayer> {
+
+    if frame.len() < 4 {
+        return Err(PcapError::IncompleteBuffer);
+    }
+
+    let header_len = LittleEndian::read_u16(&frame[2..4]) as usize;
+
+    if frame.len() < header_len {
+        return Err(PcapError::IncompleteBuffer);
+    }
+
+    // Radiotap precedes an 802.11 MAC header, which this crate doesn't
+    // dissect any further yet.
+    Ok(LinkLayer { header_len, next: NextProto::Unknown })
+}
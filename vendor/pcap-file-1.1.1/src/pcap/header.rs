@@ -0,0 +1,120 @@
+use std::io::{Read, Write};
+
+use byteorder::{BigEndian, ByteOrder, LittleEndian, ReadBytesExt, WriteBytesExt};
+
+use crate::errors::{PcapError, PcapResult};
+use crate::{DataLink, Endianness, TsResolution};
+
+/// Magic number of a classic pcap global header, written and read big-endian,
+/// when the file was produced by a big-endian machine with microsecond
+/// timestamps.
+const MAGIC_BE_MICRO: u32 = 0xa1b2_c3d4;
+/// Same file, produced by a little-endian machine: the magic number's bytes
+/// come out byte-swapped when read as big-endian.
+const MAGIC_LE_MICRO: u32 = 0xd4c3_b2a1;
+/// Big-endian machine, nanosecond timestamps.
+const MAGIC_BE_NANO: u32 = 0xa1b2_3c4d;
+/// Little-endian machine, nanosecond timestamps.
+const MAGIC_LE_NANO: u32 = 0x4d3c_b2a1;
+
+/// Global header of a classic (non pcapng) pcap file.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct PcapHeader {
+
+    /// Major version number
+    pub version_major: u16,
+
+    /// Minor version number
+    pub version_minor: u16,
+
+    /// Maximum number of octets captured from each packet
+    pub snaplen: u32,
+
+    /// Data link type of the packets in this capture
+    pub datalink: DataLink,
+
+    /// Resolution of the timestamp of each packet
+    pub ts_resolution: TsResolution,
+
+    /// Byte order the fields following the magic number are encoded in
+    pub endianness: Endianness,
+}
+
+impl PcapHeader {
+
+    /// Reads a [`PcapHeader`] from a reader, auto-detecting the endianness
+    /// and timestamp resolution from the 32-bit magic number at the start
+    /// of the global header.
+    pub fn from_reader<R: Read>(reader: &mut R) -> PcapResult<Self> {
+
+        let magic = reader.read_u32::<BigEndian>()?;
+
+        let (endianness, ts_resolution) = match magic {
+            MAGIC_BE_MICRO => (Endianness::Big, TsResolution::MicroSecond),
+            MAGIC_LE_MICRO => (Endianness::Little, TsResolution::MicroSecond),
+            MAGIC_BE_NANO => (Endianness::Big, TsResolution::NanoSecond),
+            MAGIC_LE_NANO => (Endianness::Little, TsResolution::NanoSecond),
+
+            _ => return Err(PcapError::IncorrectMagicNumber)
+        };
+
+        if endianness.is_big() {
+            Self::from_reader_fields::<_, BigEndian>(reader, ts_resolution, endianness)
+        }
+        else {
+            Self::from_reader_fields::<_, LittleEndian>(reader, ts_resolution, endianness)
+        }
+    }
+
+    fn from_reader_fields<R: Read, B: ByteOrder>(reader: &mut R, ts_resolution: TsResolution, endianness: Endianness) -> PcapResult<Self> {
+
+        let version_major = reader.read_u16::<B>()?;
+        let version_minor = reader.read_u16::<B>()?;
+        let _this_zone = reader.read_i32::<B>()?;
+        let _sigfigs = reader.read_u32::<B>()?;
+        let snaplen = reader.read_u32::<B>()?;
+        let datalink = DataLink::from(reader.read_u32::<B>()?);
+
+        Ok(PcapHeader {
+            version_major,
+            version_minor,
+            snaplen,
+            datalink,
+            ts_resolution,
+            endianness
+        })
+    }
+
+    /// Writes this header, picking the magic number that encodes its own
+    /// `endianness`/`ts_resolution`, then writing every other field in that
+    /// same byte order.
+    pub fn to_writer<W: Write>(&self, writer: &mut W) -> PcapResult<()> {
+
+        if self.endianness.is_big() {
+            self.to_writer_fields::<_, BigEndian>(writer)
+        }
+        else {
+            self.to_writer_fields::<_, LittleEndian>(writer)
+        }
+    }
+
+    fn to_writer_fields<W: Write, B: ByteOrder>(&self, writer: &mut W) -> PcapResult<()> {
+
+        let magic = match self.ts_resolution {
+            TsResolution::MicroSecond if self.endianness.is_big() => MAGIC_BE_MICRO,
+            TsResolution::MicroSecond => MAGIC_LE_MICRO,
+            TsResolution::NanoSecond if self.endianness.is_big() => MAGIC_BE_NANO,
+            TsResolution::NanoSecond => MAGIC_LE_NANO
+        };
+
+        writer.write_u32::<BigEndian>(magic)?;
+        writer.write_u16::<B>(self.version_major)?;
+        writer.write_u16::<B>(self.version_minor)?;
+        writer.write_i32::<B>(0)?;
+        writer.write_u32::<B>(0)?;
+        writer.write_u32::<B>(self.snaplen)?;
+        writer.write_u32::<B>(u32::from(self.datalink))?;
+
+        Ok(())
+    }
+}
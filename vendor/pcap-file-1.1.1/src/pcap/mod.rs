@@ -0,0 +1,9 @@
+pub mod header;
+pub mod packet;
+pub mod reader;
+pub mod writer;
+
+pub use header::PcapHeader;
+pub use packet::PacketHeader;
+pub use reader::PcapReader;
+pub use writer::PcapWriter;
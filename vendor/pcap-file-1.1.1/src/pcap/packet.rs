@@ -0,0 +1,70 @@
+use std::io::{Read, Write};
+use std::time::Duration;
+
+use byteorder::{ByteOrder, ReadBytesExt, WriteBytesExt};
+
+use crate::errors::PcapResult;
+use crate::TsResolution;
+
+/// Per-record header of a classic pcap packet.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct PacketHeader {
+
+    /// Capture timestamp, already resolved to real nanoseconds regardless of
+    /// whether the file stores it in micro- or nanosecond units.
+    pub timestamp: Duration,
+
+    /// Number of octets of the packet actually present in this record
+    pub incl_len: u32,
+
+    /// Length of the packet as it appeared on the wire
+    pub orig_len: u32
+}
+
+impl PacketHeader {
+
+    /// Builds a record header for writing.
+    pub fn new(timestamp: Duration, incl_len: u32, orig_len: u32) -> Self {
+        PacketHeader { timestamp, incl_len, orig_len }
+    }
+
+    pub(crate) fn from_reader<R: Read, B: ByteOrder>(reader: &mut R, ts_resolution: TsResolution) -> PcapResult<Self> {
+
+        let ts_sec = reader.read_u32::<B>()?;
+        let ts_frac = reader.read_u32::<B>()?;
+        let incl_len = reader.read_u32::<B>()?;
+        let orig_len = reader.read_u32::<B>()?;
+
+        Ok(Self::from_fields(ts_sec, ts_frac, incl_len, orig_len, ts_resolution))
+    }
+
+    pub(crate) fn from_fields(ts_sec: u32, ts_frac: u32, incl_len: u32, orig_len: u32, ts_resolution: TsResolution) -> Self {
+
+        let subsec_nanos = match ts_resolution {
+            TsResolution::MicroSecond => ts_frac.saturating_mul(1_000),
+            TsResolution::NanoSecond => ts_frac
+        };
+
+        PacketHeader {
+            timestamp: Duration::new(u64::from(ts_sec), subsec_nanos),
+            incl_len,
+            orig_len
+        }
+    }
+
+    pub(crate) fn to_writer<W: Write, B: ByteOrder>(&self, writer: &mut W, ts_resolution: TsResolution) -> PcapResult<()> {
+
+        let ts_sec = self.timestamp.as_secs() as u32;
+        let ts_frac = match ts_resolution {
+            TsResolution::MicroSecond => self.timestamp.subsec_micros(),
+            TsResolution::NanoSecond => self.timestamp.subsec_nanos()
+        };
+
+        writer.write_u32::<B>(ts_sec)?;
+        writer.write_u32::<B>(ts_frac)?;
+        writer.write_u32::<B>(self.incl_len)?;
+        writer.write_u32::<B>(self.orig_len)?;
+
+        Ok(())
+    }
+}
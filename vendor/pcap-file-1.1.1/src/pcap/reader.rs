@@ -0,0 +1,82 @@
+use std::io::Read;
+
+use byteorder::{BigEndian, ByteOrder, LittleEndian, ReadBytesExt};
+
+use crate::errors::{PcapError, PcapResult};
+use crate::pcap::header::PcapHeader;
+use crate::pcap::packet::PacketHeader;
+
+/// Reads a classic pcap file's global header once, then yields its packet
+/// records one at a time via `next_packet`, decoding every multi-byte field
+/// through the endianness the global header was detected with.
+pub struct PcapReader<R: Read> {
+    reader: R,
+    header: PcapHeader
+}
+
+impl<R: Read> PcapReader<R> {
+
+    /// Reads the global header off `reader` and returns a reader positioned
+    /// at the first record.
+    pub fn new(mut reader: R) -> PcapResult<Self> {
+        let header = PcapHeader::from_reader(&mut reader)?;
+        Ok(PcapReader { reader, header })
+    }
+
+    /// The capture's global header.
+    pub fn header(&self) -> &PcapHeader {
+        &self.header
+    }
+
+    /// Reads the next record, or `None` once the reader is exhausted.
+    pub fn next_packet(&mut self) -> PcapResult<Option<(PacketHeader, Vec<u8>)>> {
+
+        let mut ts_sec_buf = [0u8; 4];
+        if !read_first_field(&mut self.reader, &mut ts_sec_buf)? {
+            return Ok(None);
+        }
+
+        if self.header.endianness.is_big() {
+            self.read_packet_fields::<BigEndian>(ts_sec_buf)
+        }
+        else {
+            self.read_packet_fields::<LittleEndian>(ts_sec_buf)
+        }
+    }
+
+    fn read_packet_fields<B: ByteOrder>(&mut self, ts_sec_buf: [u8; 4]) -> PcapResult<Option<(PacketHeader, Vec<u8>)>> {
+
+        let ts_sec = B::read_u32(&ts_sec_buf);
+        let ts_frac = self.reader.read_u32::<B>()?;
+        let incl_len = self.reader.read_u32::<B>()?;
+        let orig_len = self.reader.read_u32::<B>()?;
+
+        let header = PacketHeader::from_fields(ts_sec, ts_frac, incl_len, orig_len, self.header.ts_resolution);
+
+        let mut data = vec![0; incl_len as usize];
+        self.reader.read_exact(&mut data)?;
+
+        Ok(Some((header, data)))
+    }
+}
+
+/// Reads exactly `buf.len()` bytes. Returns `Ok(false)` if the reader was
+/// already at EOF before any byte of this field was read (no record
+/// follows), or `IncompleteBuffer` if EOF hit partway through it (the
+/// capture was truncated mid-record).
+fn read_first_field<R: Read>(reader: &mut R, buf: &mut [u8]) -> PcapResult<bool> {
+
+    let mut total = 0;
+
+    while total < buf.len() {
+        match reader.read(&mut buf[total..]) {
+            Ok(0) if total == 0 => return Ok(false),
+            Ok(0) => return Err(PcapError::IncompleteBuffer),
+            Ok(n) => total += n,
+            Err(ref e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(PcapError::IoError(e))
+        }
+    }
+
+    Ok(true)
+}
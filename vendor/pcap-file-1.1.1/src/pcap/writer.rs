@@ -0,0 +1,66 @@
+use std::io::Write;
+
+use byteorder::{BigEndian, LittleEndian};
+
+use crate::errors::{PcapError, PcapResult};
+use crate::pcap::header::PcapHeader;
+use crate::pcap::packet::PacketHeader;
+use crate::{DataLink, Endianness, TsResolution};
+
+/// Writes a classic pcap file: a global header up front, then one record per
+/// call to `write_packet`.
+pub struct PcapWriter<W: Write> {
+    writer: W,
+    header: PcapHeader
+}
+
+impl<W: Write> PcapWriter<W> {
+
+    /// Writes a new global header and returns a writer positioned to append
+    /// records. Version is fixed at 2.4, the only version this format has
+    /// shipped in practice.
+    pub fn new(mut writer: W, datalink: DataLink, endianness: Endianness, ts_resolution: TsResolution, snaplen: u32) -> PcapResult<Self> {
+
+        let header = PcapHeader {
+            version_major: 2,
+            version_minor: 4,
+            snaplen,
+            datalink,
+            ts_resolution,
+            endianness
+        };
+
+        header.to_writer(&mut writer)?;
+
+        Ok(PcapWriter { writer, header })
+    }
+
+    /// The global header this writer was created with.
+    pub fn header(&self) -> &PcapHeader {
+        &self.header
+    }
+
+    /// Appends a record, rejecting it if `header.incl_len` doesn't match
+    /// `data`'s actual length or exceeds the global header's snaplen.
+    pub fn write_packet(&mut self, header: &PacketHeader, data: &[u8]) -> PcapResult<()> {
+
+        if header.incl_len as usize != data.len() {
+            return Err(PcapError::InvalidField("PcapWriter: packet header's captured length does not match payload length"));
+        }
+
+        if header.incl_len > self.header.snaplen {
+            return Err(PcapError::InvalidField("PcapWriter: packet captured length exceeds snaplen"));
+        }
+
+        if self.header.endianness.is_big() {
+            header.to_writer::<_, BigEndian>(&mut self.writer, self.header.ts_resolution)?;
+        }
+        else {
+            header.to_writer::<_, LittleEndian>(&mut self.writer, self.header.ts_resolution)?;
+        }
+
+        self.writer.write_all(data)?;
+
+        Ok(())
+    }
+}
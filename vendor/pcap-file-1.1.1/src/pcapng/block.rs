@@ -0,0 +1,104 @@
+use byteorder::{ByteOrder, ReadBytesExt, WriteBytesExt};
+use derive_into_owned::IntoOwned;
+
+use crate::errors::{PcapError, PcapResult};
+use crate::pcapng::blocks::enhanced_packet::EnhancedPacketBlock;
+use crate::pcapng::blocks::interface_description::InterfaceDescriptionBlock;
+use crate::pcapng::blocks::section_header::SectionHeaderBlock;
+use crate::pcapng::blocks::simple_packet::SimplePacketBlock;
+
+const SECTION_HEADER_BLOCK: u32 = 0x0A0D_0D0A;
+const INTERFACE_DESCRIPTION_BLOCK: u32 = 0x0000_0001;
+const SIMPLE_PACKET_BLOCK: u32 = 0x0000_0003;
+const ENHANCED_PACKET_BLOCK: u32 = 0x0000_0006;
+
+/// Body of a pcapng block, one variant per block type this crate understands.
+///
+/// Every block is framed on the wire as `[type u32][total_length u32][body]
+/// [total_length u32]`, so a reader can always skip a block it doesn't have a
+/// variant for just by its length; those blocks surface as `Block::Unknown`
+/// instead of failing the whole read.
+#[derive(Clone, Debug, IntoOwned)]
+pub enum Block<'a> {
+    SectionHeader(SectionHeaderBlock<'a>),
+    InterfaceDescription(InterfaceDescriptionBlock<'a>),
+    EnhancedPacket(EnhancedPacketBlock<'a>),
+    SimplePacket(SimplePacketBlock<'a>),
+
+    /// A block type this crate doesn't parse the body of, kept as raw bytes
+    /// so the reader can skip over it and a writer could still re-emit it.
+    Unknown(u32, &'a [u8])
+}
+
+impl<'a> Block<'a> {
+
+    /// Reads one length-framed block from the front of `slice`, returning the
+    /// block and whatever of `slice` follows it. `B` must be the byte order
+    /// of the section this block belongs to -- established by the section's
+    /// leading `SectionHeader` block, whose own byte-order magic is read
+    /// without needing `B` up front (see `SectionHeaderBlock::from_slice`).
+    pub fn from_slice<B: ByteOrder>(slice: &'a [u8]) -> PcapResult<(&'a [u8], Self)> {
+
+        if slice.len() < 12 {
+            return Err(PcapError::IncompleteBuffer);
+        }
+
+        let mut header = slice;
+        let block_type = header.read_u32::<B>()?;
+        let total_length = header.read_u32::<B>()? as usize;
+
+        if total_length < 12 || slice.len() < total_length {
+            return Err(PcapError::IncompleteBuffer);
+        }
+
+        let body = &slice[8..total_length - 4];
+        let trailing_length = B::read_u32(&slice[total_length - 4..total_length]);
+
+        if trailing_length as usize != total_length {
+            return Err(PcapError::InvalidField("Block: trailing total_length doesn't match the leading one"));
+        }
+
+        let block = match block_type {
+
+            SECTION_HEADER_BLOCK => Block::SectionHeader(SectionHeaderBlock::from_slice(body)?.1),
+            INTERFACE_DESCRIPTION_BLOCK => Block::InterfaceDescription(InterfaceDescriptionBlock::from_slice::<B>(body)?.1),
+            ENHANCED_PACKET_BLOCK => Block::EnhancedPacket(EnhancedPacketBlock::from_slice::<B>(body)?.1),
+            SIMPLE_PACKET_BLOCK => Block::SimplePacket(SimplePacketBlock::from_slice::<B>(body)?),
+
+            _ => Block::Unknown(block_type, body)
+        };
+
+        Ok((&slice[total_length..], block))
+    }
+
+    /// Id of the interface an Enhanced Packet Block belongs to, if this is one.
+    pub fn interface_id(&self) -> Option<u32> {
+        match self {
+            Block::EnhancedPacket(epb) => Some(epb.interface_id),
+            _ => None
+        }
+    }
+
+    /// Serializes this block, including its type and length framing, in the
+    /// given byte order.
+    pub fn to_bytes<B: ByteOrder>(&self) -> Vec<u8> {
+
+        let (block_type, body) = match self {
+            Block::SectionHeader(shb) => (SECTION_HEADER_BLOCK, shb.to_bytes()),
+            Block::InterfaceDescription(idb) => (INTERFACE_DESCRIPTION_BLOCK, idb.to_bytes::<B>()),
+            Block::EnhancedPacket(epb) => (ENHANCED_PACKET_BLOCK, epb.to_bytes::<B>()),
+            Block::SimplePacket(spb) => (SIMPLE_PACKET_BLOCK, spb.to_bytes::<B>()),
+            Block::Unknown(block_type, body) => (*block_type, body.to_vec())
+        };
+
+        let total_length = (body.len() + 12) as u32;
+
+        let mut buf = Vec::with_capacity(total_length as usize);
+        buf.write_u32::<B>(block_type).unwrap();
+        buf.write_u32::<B>(total_length).unwrap();
+        buf.extend_from_slice(&body);
+        buf.write_u32::<B>(total_length).unwrap();
+
+        buf
+    }
+}
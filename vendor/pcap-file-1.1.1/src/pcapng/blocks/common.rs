@@ -0,0 +1,67 @@
+use byteorder::{ByteOrder, ReadBytesExt, WriteBytesExt};
+
+use crate::errors::{PcapError, PcapResult};
+
+/// Parses a pcapng option TLV list out of the front of `slice`, calling
+/// `parse_opt(value, code, length)` for every option up to (but excluding)
+/// the terminating `opt_endofopt`, and returns whatever of `slice` is left
+/// once the option list and its padding have been consumed.
+pub(crate) fn opts_from_slice<'a, B, F, T>(mut slice: &'a [u8], parse_opt: F) -> PcapResult<(&'a [u8], Vec<T>)>
+where
+    B: ByteOrder,
+    F: Fn(&'a [u8], u16, u16) -> PcapResult<T>
+{
+    let mut options = Vec::new();
+
+    while slice.len() >= 4 {
+
+        let code = slice.read_u16::<B>()?;
+        let length = slice.read_u16::<B>()?;
+
+        if code == 0 {
+            break;
+        }
+
+        let length = length as usize;
+        if slice.len() < length {
+            return Err(PcapError::IncompleteBuffer);
+        }
+
+        let (value, rest) = slice.split_at(length);
+        let padding = (4 - (length % 4)) % 4;
+
+        if rest.len() < padding {
+            return Err(PcapError::IncompleteBuffer);
+        }
+
+        slice = &rest[padding..];
+        options.push(parse_opt(value, code, length as u16)?);
+    }
+
+    Ok((slice, options))
+}
+
+/// Serializes a pcapng option TLV list to the end of `buf`, symmetric to
+/// `opts_from_slice`: calls `to_code_and_value(opt)` for every option to get
+/// its code and value bytes, writes each as a TLV padded to a 4-byte
+/// boundary, and finishes with the `opt_endofopt` terminator.
+pub(crate) fn opts_to_writer<B, T, F>(buf: &mut Vec<u8>, options: &[T], mut to_code_and_value: F)
+where
+    B: ByteOrder,
+    F: FnMut(&T) -> (u16, Vec<u8>)
+{
+    for opt in options {
+
+        let (code, value) = to_code_and_value(opt);
+        let length = value.len();
+        let padding = (4 - (length % 4)) % 4;
+
+        buf.write_u16::<B>(code).unwrap();
+        buf.write_u16::<B>(length as u16).unwrap();
+        buf.extend_from_slice(&value);
+        buf.extend(std::iter::repeat(0u8).take(padding));
+    }
+
+    buf.write_u16::<B>(0).unwrap();
+    buf.write_u16::<B>(0).unwrap();
+}
@@ -0,0 +1,171 @@
+use std::borrow::Cow;
+
+use byteorder::{ByteOrder, ReadBytesExt, WriteBytesExt};
+use derive_into_owned::IntoOwned;
+
+use crate::errors::{PcapError, PcapResult};
+use crate::pcapng::blocks::common::opts_from_slice;
+use crate::pcapng::{CustomBinaryOption, CustomUtf8Option, UnknownOption};
+
+/// An Enhanced Packet Block (EPB) records a single captured packet along
+/// with the id of the interface it was captured on, so its link type and
+/// timestamp resolution have to be looked up in the section's interface
+/// table rather than assumed to be a single global value.
+#[derive(Clone, Debug, IntoOwned)]
+pub struct EnhancedPacketBlock<'a> {
+
+    /// Id of the interface this packet was captured on, see `InterfaceDescriptionBlock`.
+    pub interface_id: u32,
+
+    /// High 32 bits of the packet's timestamp, in the unit defined by the
+    /// interface's `if_tsresol` option.
+    pub timestamp_high: u32,
+
+    /// Low 32 bits of the packet's timestamp.
+    pub timestamp_low: u32,
+
+    /// Number of octets captured from the packet (the length of `data`).
+    pub captured_len: u32,
+
+    /// Actual length of the packet when it was transmitted on the network.
+    pub original_len: u32,
+
+    /// Packet data, `captured_len` bytes
+    pub data: Cow<'a, [u8]>,
+
+    /// Options
+    pub options: Vec<EnhancedPacketOption<'a>>
+}
+
+impl<'a> EnhancedPacketBlock<'a> {
+
+    /// Combines `timestamp_high`/`timestamp_low` into the 64-bit timestamp
+    /// value they jointly encode.
+    pub fn timestamp(&self) -> u64 {
+        (u64::from(self.timestamp_high) << 32) | u64::from(self.timestamp_low)
+    }
+
+    pub fn from_slice<B: ByteOrder>(mut slice: &'a [u8]) -> PcapResult<(&'a [u8], Self)> {
+
+        if slice.len() < 20 {
+            return Err(PcapError::InvalidField("EnhancedPacketBlock: block length < 20"));
+        }
+
+        let interface_id = slice.read_u32::<B>()?;
+        let timestamp_high = slice.read_u32::<B>()?;
+        let timestamp_low = slice.read_u32::<B>()?;
+        let captured_len = slice.read_u32::<B>()?;
+        let original_len = slice.read_u32::<B>()?;
+
+        if slice.len() < captured_len as usize {
+            return Err(PcapError::PacketPayloadMismatch);
+        }
+
+        let (data, rest) = slice.split_at(captured_len as usize);
+        let padding = (4 - (captured_len as usize % 4)) % 4;
+
+        if rest.len() < padding {
+            return Err(PcapError::IncompleteBuffer);
+        }
+
+        let (slice, options) = EnhancedPacketOption::from_slice::<B>(&rest[padding..])?;
+
+        let block = EnhancedPacketBlock {
+            interface_id,
+            timestamp_high,
+            timestamp_low,
+            captured_len,
+            original_len,
+            data: Cow::Borrowed(data),
+            options
+        };
+
+        Ok((slice, block))
+    }
+
+    /// Serializes this block's body in the given byte order, padding the
+    /// packet data to a 32-bit boundary.
+    ///
+    /// Options are not yet round-tripped through the writer (see the
+    /// `opts_from_slice`/`opts_to_writer` split in `blocks::common`); this
+    /// always emits an empty option list.
+    pub fn to_bytes<B: ByteOrder>(&self) -> Vec<u8> {
+
+        let mut buf = Vec::new();
+
+        buf.write_u32::<B>(self.interface_id).unwrap();
+        buf.write_u32::<B>(self.timestamp_high).unwrap();
+        buf.write_u32::<B>(self.timestamp_low).unwrap();
+        buf.write_u32::<B>(self.captured_len).unwrap();
+        buf.write_u32::<B>(self.original_len).unwrap();
+        buf.extend_from_slice(&self.data);
+
+        let padding = (4 - (self.data.len() % 4)) % 4;
+        buf.extend_from_slice(&[0u8; 4][..padding]);
+
+        buf.write_u16::<B>(0).unwrap();
+        buf.write_u16::<B>(0).unwrap();
+
+        buf
+    }
+}
+
+#[derive(Clone, Debug, IntoOwned)]
+pub enum EnhancedPacketOption<'a> {
+
+    Comment(Cow<'a, str>),
+
+    /// The epb_flags option is a 32-bit flags word containing link-layer
+    /// information about the packet.
+    Flags(u32),
+
+    /// The epb_hash option contains a hash of the packet.
+    Hash(Cow<'a, [u8]>),
+
+    /// The epb_dropcount option is a 64-bit number of packets lost (by the
+    /// interface or system) between this packet and the previous one.
+    DropCount(u64),
+
+    /// Custom option containing binary octets in the Custom Data portion
+    CustomBinary(CustomBinaryOption<'a>),
+
+    /// Custom option containing a UTF-8 string in the Custom Data portion
+    CustomUtf8(CustomUtf8Option<'a>),
+
+    /// Unknown option
+    Unknown(UnknownOption<'a>)
+}
+
+impl<'a> EnhancedPacketOption<'a> {
+
+    fn from_slice<B: ByteOrder>(slice: &'a [u8]) -> PcapResult<(&'a [u8], Vec<Self>)> {
+
+        opts_from_slice::<B, _, _>(slice, |mut slice, code, length| {
+
+            let opt = match code {
+
+                1 => EnhancedPacketOption::Comment(Cow::Borrowed(std::str::from_utf8(slice)?)),
+                2 => {
+                    if slice.len() != 4 {
+                        return Err(PcapError::InvalidField("EnhancedPacketOption: Flags length != 4"))
+                    }
+                    EnhancedPacketOption::Flags(slice.read_u32::<B>()?)
+                },
+                3 => EnhancedPacketOption::Hash(Cow::Borrowed(slice)),
+                4 => {
+                    if slice.len() != 8 {
+                        return Err(PcapError::InvalidField("EnhancedPacketOption: DropCount length != 8"))
+                    }
+                    EnhancedPacketOption::DropCount(slice.read_u64::<B>()?)
+                },
+
+                2988 | 19372 => EnhancedPacketOption::CustomUtf8(CustomUtf8Option::from_slice::<B>(code, slice)?),
+                2989 | 19373 => EnhancedPacketOption::CustomBinary(CustomBinaryOption::from_slice::<B>(code, slice)?),
+
+                _ => EnhancedPacketOption::Unknown(UnknownOption::new(code, length, slice))
+            };
+
+            Ok(opt)
+        })
+    }
+}
@@ -1,9 +1,9 @@
 #![allow(clippy::cast_lossless)]
 
-use crate::pcapng::blocks::common::opts_from_slice;
+use crate::pcapng::blocks::common::{opts_from_slice, opts_to_writer};
 use crate::errors::PcapError;
-use crate::DataLink;
-use byteorder::{ByteOrder, ReadBytesExt};
+use crate::{DataLink, TsResolution};
+use byteorder::{ByteOrder, ReadBytesExt, WriteBytesExt};
 use crate::pcapng::{CustomUtf8Option, CustomBinaryOption, UnknownOption};
 use std::borrow::Cow;
 use derive_into_owned::IntoOwned;
@@ -52,6 +52,90 @@ impl<'a> InterfaceDescriptionBlock<'a> {
 
         Ok((slice, block))
     }
+
+    /// Resolves this interface's timestamp resolution from its `if_tsresol`
+    /// option, defaulting to microseconds (as the pcapng spec does when the
+    /// option is absent) -- this is how an Enhanced Packet Block's timestamp
+    /// is interpreted once its interface id is looked up in the section's
+    /// interface table.
+    pub fn ts_resolution(&self) -> TsResolution {
+
+        for opt in &self.options {
+            if let InterfaceDescriptionOption::IfTsResol(resol) = opt {
+
+                let binary = resol & 0x80 != 0;
+                let exponent = resol & 0x7F;
+
+                return if binary {
+                    if exponent >= 30 { TsResolution::NanoSecond } else { TsResolution::MicroSecond }
+                }
+                else if exponent >= 9 {
+                    TsResolution::NanoSecond
+                }
+                else {
+                    TsResolution::MicroSecond
+                };
+            }
+        }
+
+        TsResolution::MicroSecond
+    }
+
+    /// Scaling factor, in seconds, of one raw timestamp unit on this
+    /// interface, derived from its `if_tsresol` option per the pcapng spec:
+    /// if the option's most significant bit is 0, the low 7 bits `n` mean
+    /// units of 10⁻ⁿ seconds; if it's 1, they mean units of 2⁻ⁿ seconds.
+    /// Defaults to microseconds (10⁻⁶) when the option is absent.
+    pub fn timestamp_resolution(&self) -> f64 {
+
+        for opt in &self.options {
+            if let InterfaceDescriptionOption::IfTsResol(resol) = opt {
+
+                let exponent = i32::from(resol & 0x7F);
+
+                return if resol & 0x80 != 0 {
+                    2f64.powi(-exponent)
+                }
+                else {
+                    10f64.powi(-exponent)
+                };
+            }
+        }
+
+        1e-6
+    }
+
+    /// Resolves a raw packet timestamp (the combined high/low halves of an
+    /// Enhanced Packet Block's timestamp) to an absolute Unix timestamp in
+    /// seconds, applying this interface's `if_tsresol` scale and any
+    /// `if_tsoffset` shift.
+    pub fn resolve_timestamp(&self, raw: u64) -> f64 {
+
+        let mut offset = 0u64;
+
+        for opt in &self.options {
+            if let InterfaceDescriptionOption::IfTsOffset(tsoffset) = opt {
+                offset = *tsoffset;
+                break;
+            }
+        }
+
+        raw as f64 * self.timestamp_resolution() + offset as f64
+    }
+
+    /// Serializes this block's body in the given byte order, including its
+    /// options.
+    pub fn to_bytes<B: ByteOrder>(&self) -> Vec<u8> {
+
+        let mut buf = Vec::new();
+
+        buf.write_u16::<B>(u32::from(self.linktype) as u16).unwrap();
+        buf.write_u16::<B>(self.reserved).unwrap();
+        buf.write_u32::<B>(self.snaplen).unwrap();
+        opts_to_writer::<B, _, _>(&mut buf, &self.options, InterfaceDescriptionOption::to_code_and_value::<B>);
+
+        buf
+    }
 }
 
 #[derive(Clone, Debug, IntoOwned)]
@@ -198,4 +282,50 @@ impl<'a> InterfaceDescriptionOption<'a> {
             Ok(opt)
         })
     }
+
+    /// Returns this option's code and serialized value bytes, the inputs
+    /// `opts_to_writer` needs to re-emit it as a TLV.
+    fn to_code_and_value<B: ByteOrder>(&self) -> (u16, Vec<u8>) {
+
+        match self {
+
+            InterfaceDescriptionOption::Comment(val) => (1, val.as_bytes().to_vec()),
+            InterfaceDescriptionOption::IfName(val) => (2, val.as_bytes().to_vec()),
+            InterfaceDescriptionOption::IfDescription(val) => (3, val.as_bytes().to_vec()),
+            InterfaceDescriptionOption::IfIpv4Addr(val) => (4, val.to_vec()),
+            InterfaceDescriptionOption::IfIpv6Addr(val) => (5, val.to_vec()),
+            InterfaceDescriptionOption::IfMacAddr(val) => (6, val.to_vec()),
+            InterfaceDescriptionOption::IfEuIAddr(val) => {
+                let mut buf = Vec::new();
+                buf.write_u64::<B>(*val).unwrap();
+                (7, buf)
+            },
+            InterfaceDescriptionOption::IfSpeed(val) => {
+                let mut buf = Vec::new();
+                buf.write_u64::<B>(*val).unwrap();
+                (8, buf)
+            },
+            InterfaceDescriptionOption::IfTsResol(val) => (9, vec![*val]),
+            InterfaceDescriptionOption::IfTzone(val) => {
+                let mut buf = Vec::new();
+                buf.write_u32::<B>(*val).unwrap();
+                (10, buf)
+            },
+            InterfaceDescriptionOption::IfFilter(val) => (11, val.to_vec()),
+            InterfaceDescriptionOption::IfOs(val) => (12, val.as_bytes().to_vec()),
+            InterfaceDescriptionOption::IfFcsLen(val) => (13, vec![*val]),
+            InterfaceDescriptionOption::IfTsOffset(val) => {
+                let mut buf = Vec::new();
+                buf.write_u64::<B>(*val).unwrap();
+                (14, buf)
+            },
+            InterfaceDescriptionOption::IfHardware(val) => (15, val.as_bytes().to_vec()),
+
+            InterfaceDescriptionOption::CustomUtf8(opt) =>
+                (opt.code, opt.value.as_bytes().to_vec()),
+            InterfaceDescriptionOption::CustomBinary(opt) => (opt.code, opt.value.to_vec()),
+
+            InterfaceDescriptionOption::Unknown(opt) => (opt.code, opt.value.to_vec())
+        }
+    }
 }
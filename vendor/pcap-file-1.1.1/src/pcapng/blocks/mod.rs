@@ -0,0 +1,6 @@
+pub(crate) mod common;
+
+pub mod enhanced_packet;
+pub mod interface_description;
+pub mod section_header;
+pub mod simple_packet;
@@ -0,0 +1,163 @@
+use std::borrow::Cow;
+
+use byteorder::{BigEndian, ByteOrder, LittleEndian, ReadBytesExt, WriteBytesExt};
+use derive_into_owned::IntoOwned;
+
+use crate::errors::{PcapError, PcapResult};
+use crate::pcapng::blocks::common::opts_from_slice;
+use crate::pcapng::{CustomBinaryOption, CustomUtf8Option, UnknownOption};
+use crate::Endianness;
+
+/// Magic number appearing right after a Section Header Block's type/length
+/// fields. Its value, read in the section's own byte order, is always
+/// `0x1A2B3C4D` -- reading it both ways is how a reader figures out which
+/// byte order the rest of the section was written in.
+pub const BYTE_ORDER_MAGIC: u32 = 0x1A2B_3C4D;
+
+/// A Section Header Block (SHB) marks the beginning of a section of the
+/// capture file and defines the byte order used to encode the rest of the
+/// blocks in that section.
+#[derive(Clone, Debug, IntoOwned)]
+pub struct SectionHeaderBlock<'a> {
+
+    /// Byte order the rest of this section is encoded in.
+    pub endianness: Endianness,
+
+    /// Major version of the pcapng format used by this section.
+    pub version_major: u16,
+
+    /// Minor version of the pcapng format used by this section.
+    pub version_minor: u16,
+
+    /// Length in bytes of the following section, excluding this block,
+    /// or `-1` if unknown/not specified.
+    pub section_length: i64,
+
+    /// Options
+    pub options: Vec<SectionHeaderOption<'a>>
+}
+
+impl<'a> SectionHeaderBlock<'a> {
+
+    /// Reads a Section Header Block's body, auto-detecting the section's
+    /// byte order from the byte-order magic that starts it.
+    pub fn from_slice(mut slice: &'a [u8]) -> PcapResult<(&'a [u8], Self)> {
+
+        if slice.len() < 16 {
+            return Err(PcapError::InvalidField("SectionHeaderBlock: block length < 16"));
+        }
+
+        let magic = slice.read_u32::<BigEndian>()?;
+        let endianness = match magic {
+            BYTE_ORDER_MAGIC => Endianness::Big,
+            _ if magic == BYTE_ORDER_MAGIC.swap_bytes() => Endianness::Little,
+            _ => return Err(PcapError::IncorrectMagicNumber)
+        };
+
+        if endianness.is_big() {
+            Self::from_slice_fields::<BigEndian>(slice, endianness)
+        }
+        else {
+            Self::from_slice_fields::<LittleEndian>(slice, endianness)
+        }
+    }
+
+    fn from_slice_fields<B: ByteOrder>(mut slice: &'a [u8], endianness: Endianness) -> PcapResult<(&'a [u8], Self)> {
+
+        let version_major = slice.read_u16::<B>()?;
+        let version_minor = slice.read_u16::<B>()?;
+        let section_length = slice.read_i64::<B>()?;
+        let (slice, options) = SectionHeaderOption::from_slice::<B>(slice)?;
+
+        let block = SectionHeaderBlock {
+            endianness,
+            version_major,
+            version_minor,
+            section_length,
+            options
+        };
+
+        Ok((slice, block))
+    }
+
+    /// Serializes this block's body (everything a writer needs between the
+    /// block's type and its `total_length` trailer) in its own endianness.
+    ///
+    /// Options are not yet round-tripped through the writer (see the
+    /// `opts_from_slice`/`opts_to_writer` split in `blocks::common`); this
+    /// always emits an empty option list.
+    pub fn to_bytes(&self) -> Vec<u8> {
+
+        let mut buf = Vec::new();
+
+        if self.endianness.is_big() {
+            self.write_fields::<BigEndian>(&mut buf);
+        }
+        else {
+            self.write_fields::<LittleEndian>(&mut buf);
+        }
+
+        buf
+    }
+
+    fn write_fields<B: ByteOrder>(&self, buf: &mut Vec<u8>) {
+
+        buf.write_u32::<BigEndian>(BYTE_ORDER_MAGIC).unwrap();
+        buf.write_u16::<B>(self.version_major).unwrap();
+        buf.write_u16::<B>(self.version_minor).unwrap();
+        buf.write_i64::<B>(self.section_length).unwrap();
+        buf.write_u16::<B>(0).unwrap();
+        buf.write_u16::<B>(0).unwrap();
+    }
+}
+
+#[derive(Clone, Debug, IntoOwned)]
+pub enum SectionHeaderOption<'a> {
+
+    Comment(Cow<'a, str>),
+
+    /// The shb_hardware option is a UTF-8 string containing the description of
+    /// the hardware used to create this section.
+    Hardware(Cow<'a, str>),
+
+    /// The shb_os option is a UTF-8 string containing the name of the operating
+    /// system used to create this section.
+    Os(Cow<'a, str>),
+
+    /// The shb_userappl option is a UTF-8 string containing the name of the
+    /// application used to create this section.
+    UserApplication(Cow<'a, str>),
+
+    /// Custom option containing binary octets in the Custom Data portion
+    CustomBinary(CustomBinaryOption<'a>),
+
+    /// Custom option containing a UTF-8 string in the Custom Data portion
+    CustomUtf8(CustomUtf8Option<'a>),
+
+    /// Unknown option
+    Unknown(UnknownOption<'a>)
+}
+
+impl<'a> SectionHeaderOption<'a> {
+
+    fn from_slice<B: ByteOrder>(slice: &'a [u8]) -> PcapResult<(&'a [u8], Vec<Self>)> {
+
+        opts_from_slice::<B, _, _>(slice, |slice, code, length| {
+
+            let opt = match code {
+
+                1 => SectionHeaderOption::Comment(Cow::Borrowed(std::str::from_utf8(slice)?)),
+                2 => SectionHeaderOption::Hardware(Cow::Borrowed(std::str::from_utf8(slice)?)),
+                3 => SectionHeaderOption::Os(Cow::Borrowed(std::str::from_utf8(slice)?)),
+                4 => SectionHeaderOption::UserApplication(Cow::Borrowed(std::str::from_utf8(slice)?)),
+
+                2988 | 19372 => SectionHeaderOption::CustomUtf8(CustomUtf8Option::from_slice::<B>(code, slice)?),
+                2989 | 19373 => SectionHeaderOption::CustomBinary(CustomBinaryOption::from_slice::<B>(code, slice)?),
+
+                _ => SectionHeaderOption::Unknown(UnknownOption::new(code, length, slice))
+            };
+
+            Ok(opt)
+        })
+    }
+}
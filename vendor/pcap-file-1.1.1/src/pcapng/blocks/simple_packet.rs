@@ -0,0 +1,51 @@
+use std::borrow::Cow;
+
+use byteorder::{ByteOrder, ReadBytesExt, WriteBytesExt};
+use derive_into_owned::IntoOwned;
+
+use crate::errors::{PcapError, PcapResult};
+
+/// A Simple Packet Block (SPB) is a lightweight container for a single
+/// captured packet, without per-packet options or an explicit interface id
+/// -- it always refers to the section's first (or only) interface.
+#[derive(Clone, Debug, IntoOwned)]
+pub struct SimplePacketBlock<'a> {
+
+    /// Actual length of the packet when it was transmitted on the network.
+    pub original_len: u32,
+
+    /// Packet data, truncated to the interface's snaplen.
+    pub data: Cow<'a, [u8]>
+}
+
+impl<'a> SimplePacketBlock<'a> {
+
+    pub fn from_slice<B: ByteOrder>(mut slice: &'a [u8]) -> PcapResult<Self> {
+
+        if slice.len() < 4 {
+            return Err(PcapError::InvalidField("SimplePacketBlock: block length < 4"));
+        }
+
+        let original_len = slice.read_u32::<B>()?;
+
+        Ok(SimplePacketBlock {
+            original_len,
+            data: Cow::Borrowed(slice)
+        })
+    }
+
+    /// Serializes this block's body in the given byte order, padding the
+    /// packet data to a 32-bit boundary.
+    pub fn to_bytes<B: ByteOrder>(&self) -> Vec<u8> {
+
+        let mut buf = Vec::new();
+
+        buf.write_u32::<B>(self.original_len).unwrap();
+        buf.extend_from_slice(&self.data);
+
+        let padding = (4 - (self.data.len() % 4)) % 4;
+        buf.extend_from_slice(&[0u8; 4][..padding]);
+
+        buf
+    }
+}
@@ -0,0 +1,61 @@
+pub mod block;
+pub mod blocks;
+pub mod pretty_print;
+pub mod reader;
+
+use std::borrow::Cow;
+
+use byteorder::ByteOrder;
+use derive_into_owned::IntoOwned;
+
+use crate::errors::PcapResult;
+
+pub use block::Block;
+pub use blocks::enhanced_packet::{EnhancedPacketBlock, EnhancedPacketOption};
+pub use blocks::interface_description::{InterfaceDescriptionBlock, InterfaceDescriptionOption};
+pub use blocks::section_header::{SectionHeaderBlock, SectionHeaderOption};
+pub use blocks::simple_packet::SimplePacketBlock;
+pub use pretty_print::PrettyPrinter;
+pub use reader::{InterfaceInfo, PcapNgReader};
+
+/// Custom option containing a UTF-8 string in its Custom Data portion,
+/// shared by every pcapng block's option set (codes `2988`/`19372`).
+#[derive(Clone, Debug, IntoOwned)]
+pub struct CustomUtf8Option<'a> {
+    pub code: u16,
+    pub value: Cow<'a, str>
+}
+
+impl<'a> CustomUtf8Option<'a> {
+    pub(crate) fn from_slice<B: ByteOrder>(code: u16, slice: &'a [u8]) -> PcapResult<Self> {
+        Ok(CustomUtf8Option { code, value: Cow::Borrowed(std::str::from_utf8(slice)?) })
+    }
+}
+
+/// Custom option containing raw binary octets in its Custom Data portion,
+/// shared by every pcapng block's option set (codes `2989`/`19373`).
+#[derive(Clone, Debug, IntoOwned)]
+pub struct CustomBinaryOption<'a> {
+    pub code: u16,
+    pub value: Cow<'a, [u8]>
+}
+
+impl<'a> CustomBinaryOption<'a> {
+    pub(crate) fn from_slice<B: ByteOrder>(code: u16, slice: &'a [u8]) -> PcapResult<Self> {
+        Ok(CustomBinaryOption { code, value: Cow::Borrowed(slice) })
+    }
+}
+
+/// An option whose code this crate doesn't give a dedicated variant to.
+#[derive(Clone, Debug, IntoOwned)]
+pub struct UnknownOption<'a> {
+    pub code: u16,
+    pub length: u16,
+    pub value: Cow<'a, [u8]>
+}
+
+impl<'a> UnknownOption<'a> {
+    pub(crate) fn new(code: u16, length: u16, value: &'a [u8]) -> Self {
+        UnknownOption { code, length, value: Cow::Borrowed(value) }
+    }
+}
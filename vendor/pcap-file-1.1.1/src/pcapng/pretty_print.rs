@@ -0,0 +1,67 @@
+use std::fmt;
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+use crate::pcapng::blocks::interface_description::{InterfaceDescriptionBlock, InterfaceDescriptionOption};
+
+/// Wraps a parsed block to `Display` it as indented, human-readable text,
+/// the way `tcpdump -v` dumps a capture's interfaces instead of callers
+/// hand-matching every option variant themselves.
+///
+/// Only `InterfaceDescriptionBlock` is covered so far; the enhanced/simple
+/// packet and section header blocks are left for a later pass.
+pub struct PrettyPrinter<'a, 'b>(pub &'b InterfaceDescriptionBlock<'a>);
+
+impl<'a, 'b> fmt::Display for PrettyPrinter<'a, 'b> {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+
+        let idb = self.0;
+
+        writeln!(f, "InterfaceDescriptionBlock")?;
+        writeln!(f, "  linktype: {:?}", idb.linktype)?;
+        writeln!(f, "  snaplen: {}", idb.snaplen)?;
+        writeln!(f, "  timestamp resolution: {} s/unit", idb.timestamp_resolution())?;
+
+        for opt in &idb.options {
+            writeln!(f, "  {}", format_option(opt))?;
+        }
+
+        Ok(())
+    }
+}
+
+fn format_option(opt: &InterfaceDescriptionOption) -> String {
+
+    match opt {
+
+        InterfaceDescriptionOption::Comment(val) => format!("comment: {}", val),
+        InterfaceDescriptionOption::IfName(val) => format!("if_name: {}", val),
+        InterfaceDescriptionOption::IfDescription(val) => format!("if_description: {}", val),
+        InterfaceDescriptionOption::IfIpv4Addr(val) => format!(
+            "if_IPv4addr: {}/{}",
+            Ipv4Addr::new(val[0], val[1], val[2], val[3]),
+            Ipv4Addr::new(val[4], val[5], val[6], val[7])
+        ),
+        InterfaceDescriptionOption::IfIpv6Addr(val) => {
+            let mut octets = [0u8; 16];
+            octets.copy_from_slice(&val[..16]);
+            format!("if_IPv6addr: {}/{}", Ipv6Addr::from(octets), val[16])
+        },
+        InterfaceDescriptionOption::IfMacAddr(val) => format!(
+            "if_MACaddr: {:02x}:{:02x}:{:02x}:{:02x}:{:02x}:{:02x}",
+            val[0], val[1], val[2], val[3], val[4], val[5]
+        ),
+        InterfaceDescriptionOption::IfEuIAddr(val) => format!("if_EUIaddr: {:016x}", val),
+        InterfaceDescriptionOption::IfSpeed(val) => format!("if_speed: {} bit/s", val),
+        InterfaceDescriptionOption::IfTsResol(val) => format!("if_tsresol: {:#x}", val),
+        InterfaceDescriptionOption::IfTzone(val) => format!("if_tzone: {}", val),
+        InterfaceDescriptionOption::IfFilter(val) => format!("if_filter: {:?}", val),
+        InterfaceDescriptionOption::IfOs(val) => format!("if_os: {}", val),
+        InterfaceDescriptionOption::IfFcsLen(val) => format!("if_fcslen: {}", val),
+        InterfaceDescriptionOption::IfTsOffset(val) => format!("if_tsoffset: {} s", val),
+        InterfaceDescriptionOption::IfHardware(val) => format!("if_hardware: {}", val),
+        InterfaceDescriptionOption::CustomBinary(opt) => format!("custom ({}): {:?}", opt.code, opt.value),
+        InterfaceDescriptionOption::CustomUtf8(opt) => format!("custom ({}): {}", opt.code, opt.value),
+        InterfaceDescriptionOption::Unknown(opt) => format!("unknown ({}): {:?}", opt.code, opt.value)
+    }
+}
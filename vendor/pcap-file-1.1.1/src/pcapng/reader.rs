@@ -0,0 +1,119 @@
+use byteorder::{BigEndian, ByteOrder, LittleEndian};
+
+use crate::errors::{PcapError, PcapResult};
+use crate::pcapng::block::Block;
+use crate::pcapng::blocks::interface_description::InterfaceDescriptionBlock;
+use crate::pcapng::blocks::section_header::BYTE_ORDER_MAGIC;
+use crate::{DataLink, Endianness, TsResolution};
+
+/// The `DataLink`/`TsResolution` pair an interface was declared with, looked
+/// up by interface id -- each interface in a pcapng capture can use a
+/// different link type and timestamp resolution, unlike classic pcap where
+/// a single global header covers the whole file.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct InterfaceInfo {
+    pub linktype: DataLink,
+    pub snaplen: u32,
+    pub ts_resolution: TsResolution
+}
+
+/// Reads a pcapng capture block by block, maintaining the table of
+/// interfaces declared so far so that an Enhanced or Simple Packet Block's
+/// `DataLink`/`TsResolution` can be resolved by interface id instead of
+/// assuming one global value like classic pcap does.
+pub struct PcapNgReader<'a> {
+    slice: &'a [u8],
+    endianness: Endianness,
+    interfaces: Vec<InterfaceInfo>
+}
+
+impl<'a> PcapNgReader<'a> {
+
+    /// Creates a reader over `slice`, which must start with the section's
+    /// leading Section Header Block.
+    pub fn new(slice: &'a [u8]) -> PcapResult<Self> {
+
+        if slice.len() < 12 {
+            return Err(PcapError::IncompleteBuffer);
+        }
+
+        // The leading block's type (bytes 0..4) is the palindromic SHB magic
+        // 0x0A0D0D0A, which reads the same regardless of endianness, so
+        // unlike every later block we can't yet trust its total_length field
+        // (bytes 4..8) to slice out the body -- the byte-order magic at
+        // bytes 8..12 is the only field that tells us which way to read the
+        // rest of the section.
+        let byte_order_magic = BigEndian::read_u32(&slice[8..12]);
+        let endianness = match byte_order_magic {
+            BYTE_ORDER_MAGIC => Endianness::Big,
+            _ if byte_order_magic == BYTE_ORDER_MAGIC.swap_bytes() => Endianness::Little,
+            _ => return Err(PcapError::IncorrectMagicNumber)
+        };
+
+        let (_, block) = if endianness.is_big() {
+            Block::from_slice::<BigEndian>(slice)?
+        }
+        else {
+            Block::from_slice::<LittleEndian>(slice)?
+        };
+
+        match block {
+            Block::SectionHeader(_) => {},
+            _ => return Err(PcapError::InvalidField("PcapNgReader: capture does not start with a SectionHeaderBlock"))
+        };
+
+        Ok(PcapNgReader {
+            slice,
+            endianness,
+            interfaces: Vec::new()
+        })
+    }
+
+    /// The interfaces declared by every Interface Description Block read so
+    /// far, in declaration order -- an Enhanced Packet Block's
+    /// `interface_id` indexes into this table.
+    pub fn interfaces(&self) -> &[InterfaceInfo] {
+        &self.interfaces
+    }
+
+    /// Resolves the `DataLink`/`TsResolution` a previously-read Enhanced
+    /// Packet Block's `interface_id` refers to.
+    pub fn interface(&self, interface_id: u32) -> Option<&InterfaceInfo> {
+        self.interfaces.get(interface_id as usize)
+    }
+
+    /// Reads the next block, updating the interface table if it is an
+    /// Interface Description Block, and returns `None` once the section is
+    /// exhausted.
+    pub fn next_block(&mut self) -> PcapResult<Option<Block<'a>>> {
+
+        if self.slice.is_empty() {
+            return Ok(None);
+        }
+
+        let (rest, block) = if self.endianness.is_big() {
+            Block::from_slice::<BigEndian>(self.slice)?
+        }
+        else {
+            Block::from_slice::<LittleEndian>(self.slice)?
+        };
+
+        self.slice = rest;
+
+        if let Block::InterfaceDescription(ref idb) = block {
+            self.interfaces.push(InterfaceInfo::from_idb(idb));
+        }
+
+        Ok(Some(block))
+    }
+}
+
+impl InterfaceInfo {
+    fn from_idb(idb: &InterfaceDescriptionBlock) -> Self {
+        InterfaceInfo {
+            linktype: idb.linktype,
+            snaplen: idb.snaplen,
+            ts_resolution: idb.ts_resolution()
+        }
+    }
+}
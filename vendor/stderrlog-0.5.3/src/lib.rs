@@ -209,14 +209,16 @@
 
 use atty::Stream;
 #[cfg(feature = "timestamps")]
-use chrono::Local;
+use chrono::{Local, Utc};
 use log::{Level, LevelFilter, Log, Metadata, Record};
 use std::cell::RefCell;
 use std::fmt;
+use std::fs::{File, OpenOptions};
 use std::io::{self, Write};
+use std::path::PathBuf;
 #[cfg(feature = "timestamps")]
 use std::str::FromStr;
-use termcolor::{Color, ColorSpec, StandardStream, WriteColor};
+use termcolor::{Color, ColorSpec, NoColor, StandardStream, StandardStreamLock, WriteColor};
 
 pub use termcolor::ColorChoice;
 use thread_local::ThreadLocal;
@@ -262,6 +264,218 @@ impl FromStr for Timestamp {
     }
 }
 
+/// A single piece of a parsed [`StdErrLog::format`] template: either literal
+/// text copied through unchanged, or a placeholder resolved at log time.
+#[derive(Clone, Debug, PartialEq)]
+enum FormatSegment {
+    Literal(String),
+    Token(FormatToken),
+}
+
+/// The placeholders a [`StdErrLog::format`] template may contain.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum FormatToken {
+    /// `{s}`: the log message body (`record.args()`)
+    Message,
+    /// `{L}`: the log level
+    Level,
+    /// `{m}`: the target/module path
+    Module,
+    /// `{t}`: the formatted timestamp, honoring the configured [`Timestamp`] granularity
+    Timestamp,
+}
+
+/// A malformed template passed to [`StdErrLog::format`], reported from
+/// [`StdErrLog::init`] (which is where the template is parsed) rather than
+/// panicking.
+#[derive(Debug)]
+pub enum FormatError {
+    /// A `{` was never closed with a matching `}`
+    UnterminatedPlaceholder,
+    /// `{name}` used a placeholder other than `s`, `L`, `m`, or `t`
+    UnknownPlaceholder(String),
+}
+
+impl fmt::Display for FormatError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FormatError::UnterminatedPlaceholder => {
+                write!(f, "unterminated '{{' in log format template")
+            }
+            FormatError::UnknownPlaceholder(name) => {
+                write!(f, "unknown log format placeholder '{{{}}}'", name)
+            }
+        }
+    }
+}
+
+impl std::error::Error for FormatError {}
+
+/// Parses a [`StdErrLog::format`] template into the segments [`StdErrLog::log`]
+/// walks at log time. `{{` and `}}` escape a literal brace.
+fn parse_format(fmt: &str) -> Result<Vec<FormatSegment>, FormatError> {
+    let mut segments = Vec::new();
+    let mut literal = String::new();
+    let mut chars = fmt.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                literal.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                literal.push('}');
+            }
+            '{' => {
+                let mut name = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => name.push(c),
+                        None => return Err(FormatError::UnterminatedPlaceholder),
+                    }
+                }
+                let token = match name.as_str() {
+                    "s" => FormatToken::Message,
+                    "L" => FormatToken::Level,
+                    "m" => FormatToken::Module,
+                    "t" => FormatToken::Timestamp,
+                    _ => return Err(FormatError::UnknownPlaceholder(name)),
+                };
+                if !literal.is_empty() {
+                    segments.push(FormatSegment::Literal(std::mem::take(&mut literal)));
+                }
+                segments.push(FormatSegment::Token(token));
+            }
+            c => literal.push(c),
+        }
+    }
+
+    if !literal.is_empty() {
+        segments.push(FormatSegment::Literal(literal));
+    }
+
+    Ok(segments)
+}
+
+/// Error returned by [`StdErrLog::init`].
+#[derive(Debug)]
+pub enum InitError {
+    /// The template passed to [`StdErrLog::format`] was malformed
+    Format(FormatError),
+    /// `log::set_boxed_logger` failed because a logger was already installed
+    SetLogger(log::SetLoggerError),
+}
+
+impl fmt::Display for InitError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InitError::Format(e) => write!(f, "invalid log format template: {}", e),
+            InitError::SetLogger(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for InitError {}
+
+impl From<log::SetLoggerError> for InitError {
+    fn from(e: log::SetLoggerError) -> Self {
+        InitError::SetLogger(e)
+    }
+}
+
+/// Where log lines are written, set via [`StdErrLog::target`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum Target {
+    /// Write to stderr (the default)
+    Stderr,
+    /// Write to stdout
+    Stdout,
+    /// Append log lines to the file at this path, lazily opened (and created) the first
+    /// time each thread logs
+    File(PathBuf),
+}
+
+impl Default for Target {
+    fn default() -> Self {
+        Target::Stderr
+    }
+}
+
+/// The lazily-opened, per-thread handle backing a [`Target`].
+enum Sink {
+    Std(StandardStream),
+    File(NoColor<File>),
+}
+
+impl Sink {
+    fn open(target: &Target, color_choice: ColorChoice) -> io::Result<Sink> {
+        Ok(match target {
+            Target::Stderr => Sink::Std(StandardStream::stderr(color_choice)),
+            Target::Stdout => Sink::Std(StandardStream::stdout(color_choice)),
+            Target::File(path) => {
+                let file = OpenOptions::new().create(true).append(true).open(path)?;
+                Sink::File(NoColor::new(file))
+            }
+        })
+    }
+
+    fn lock(&mut self) -> SinkLock<'_> {
+        match self {
+            Sink::Std(s) => SinkLock::Std(s.lock()),
+            Sink::File(f) => SinkLock::File(f),
+        }
+    }
+}
+
+/// A locked, directly-writable handle to a [`Sink`], borrowed for the duration of a single
+/// [`StdErrLog::log`] call.
+enum SinkLock<'a> {
+    Std(StandardStreamLock<'a>),
+    File(&'a mut NoColor<File>),
+}
+
+impl Write for SinkLock<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            SinkLock::Std(s) => s.write(buf),
+            SinkLock::File(f) => f.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            SinkLock::Std(s) => s.flush(),
+            SinkLock::File(f) => f.flush(),
+        }
+    }
+}
+
+impl WriteColor for SinkLock<'_> {
+    fn supports_color(&self) -> bool {
+        match self {
+            SinkLock::Std(s) => s.supports_color(),
+            SinkLock::File(f) => f.supports_color(),
+        }
+    }
+
+    fn set_color(&mut self, spec: &ColorSpec) -> io::Result<()> {
+        match self {
+            SinkLock::Std(s) => s.set_color(spec),
+            SinkLock::File(f) => f.set_color(spec),
+        }
+    }
+
+    fn reset(&mut self) -> io::Result<()> {
+        match self {
+            SinkLock::Std(s) => s.reset(),
+            SinkLock::File(f) => f.reset(),
+        }
+    }
+}
+
 /// Data specific to this logger
 pub struct StdErrLog {
     verbosity: LevelFilter,
@@ -269,10 +483,24 @@ pub struct StdErrLog {
     show_level: bool,
     #[cfg(feature = "timestamps")]
     timestamp: Timestamp,
+    #[cfg(feature = "timestamps")]
+    utc: bool,
     modules: Vec<String>,
-    writer: ThreadLocal<RefCell<StandardStream>>,
+    #[cfg(feature = "regex")]
+    regex_set: Option<regex::RegexSet>,
+    target: Target,
+    writer: ThreadLocal<RefCell<Sink>>,
     color_choice: ColorChoice,
     show_module_names: bool,
+    format: Option<String>,
+    parsed_format: Option<Vec<FormatSegment>>,
+    /// `path=level` directives, sorted by path, as set by [`parse_filters`](Self::parse_filters).
+    directives: Vec<(String, LevelFilter)>,
+    /// the level a bare directive with no path sets, used when no directive's path matches
+    default_level: Option<LevelFilter>,
+    /// per-level [`ColorSpec`] overrides set via [`color_for`](Self::color_for); any level
+    /// without an override falls back to the hard-coded default mapping
+    colors: Vec<(Level, ColorSpec)>,
 }
 
 impl fmt::Debug for StdErrLog {
@@ -283,12 +511,18 @@ impl fmt::Debug for StdErrLog {
             .field("quiet", &self.quiet)
             .field("show_level", &self.show_level);
         #[cfg(feature = "timestamps")]
-        builder.field("timestamp", &self.timestamp);
+        builder.field("timestamp", &self.timestamp).field("utc", &self.utc);
+        builder.field("modules", &self.modules);
+        #[cfg(feature = "regex")]
+        builder.field("regex_set", &self.regex_set);
         builder
-            .field("modules", &self.modules)
-            .field("writer", &"stderr")
+            .field("target", &self.target)
             .field("color_choice", &self.color_choice)
             .field("show_module_names", &self.show_module_names)
+            .field("format", &self.format)
+            .field("directives", &self.directives)
+            .field("default_level", &self.default_level)
+            .field("colors", &self.colors)
             .finish()
     }
 }
@@ -297,7 +531,14 @@ impl Clone for StdErrLog {
     fn clone(&self) -> StdErrLog {
         StdErrLog {
             modules: self.modules.clone(),
+            #[cfg(feature = "regex")]
+            regex_set: self.regex_set.clone(),
+            target: self.target.clone(),
             writer: ThreadLocal::new(),
+            format: self.format.clone(),
+            parsed_format: self.parsed_format.clone(),
+            directives: self.directives.clone(),
+            colors: self.colors.clone(),
             ..*self
         }
     }
@@ -305,7 +546,8 @@ impl Clone for StdErrLog {
 
 impl Log for StdErrLog {
     fn enabled(&self, metadata: &Metadata) -> bool {
-        metadata.level() <= self.log_level_filter() && self.includes_module(metadata.target())
+        metadata.level() <= self.effective_level(metadata.target())
+            && self.includes_module(metadata.target())
     }
 
     fn log(&self, record: &Record) {
@@ -316,46 +558,60 @@ impl Log for StdErrLog {
 
         let writer = self
             .writer
-            .get_or(|| RefCell::new(StandardStream::stderr(self.color_choice)));
+            .get_or(|| RefCell::new(Sink::open(&self.target, self.color_choice).expect(
+                "stderrlog: failed to open log target",
+            )));
         let writer = writer.borrow_mut();
         let mut writer = io::LineWriter::new(writer.lock());
-        let color = match record.metadata().level() {
-            Level::Error => Color::Red,
-            Level::Warn => Color::Magenta,
-            Level::Info => Color::Yellow,
-            Level::Debug => Color::Cyan,
-            Level::Trace => Color::Blue,
-        };
+        let color = self.color_spec_for(record.metadata().level());
+
+        if let Some(segments) = &self.parsed_format {
+            // A custom format() template: walk its segments, coloring only the
+            // {L} token span (the rest of a custom line is the user's literal
+            // text and other fields, which shouldn't be tinted by the level).
+            for segment in segments {
+                match segment {
+                    FormatSegment::Literal(s) => {
+                        let _ = write!(writer, "{}", s);
+                    }
+                    FormatSegment::Token(FormatToken::Level) => {
+                        // A failure here indicates the stream closed. Do not panic.
+                        writer.get_mut().set_color(&color).ok();
+                        let _ = write!(writer, "{}", record.level());
+                        writer.get_mut().reset().ok();
+                    }
+                    FormatSegment::Token(FormatToken::Message) => {
+                        let _ = write!(writer, "{}", record.args());
+                    }
+                    FormatSegment::Token(FormatToken::Module) => {
+                        let _ = write!(writer, "{}", record.metadata().target());
+                    }
+                    #[cfg(feature = "timestamps")]
+                    FormatSegment::Token(FormatToken::Timestamp) => {
+                        let _ = write!(writer, "{}", self.format_timestamp());
+                    }
+                    #[cfg(not(feature = "timestamps"))]
+                    FormatSegment::Token(FormatToken::Timestamp) => {}
+                }
+            }
+            let _ = writeln!(writer);
+            return;
+        }
+
         {
             // A failure here indicates the stream closed. Do not panic.
-            writer
-                .get_mut()
-                .set_color(ColorSpec::new().set_fg(Some(color)))
-                .ok();
+            writer.get_mut().set_color(&color).ok();
         }
 
         if self.show_module_names {
             let _ = write!(writer, "{}: ", record.metadata().target());
         }
         #[cfg(feature = "timestamps")]
-        match self.timestamp {
-            Timestamp::Second => {
-                let fmt = "%Y-%m-%dT%H:%M:%S%:z";
-                let _ = write!(writer, "{} - ", Local::now().format(fmt));
-            }
-            Timestamp::Millisecond => {
-                let fmt = "%Y-%m-%dT%H:%M:%S%.3f%:z";
-                let _ = write!(writer, "{} - ", Local::now().format(fmt));
-            }
-            Timestamp::Microsecond => {
-                let fmt = "%Y-%m-%dT%H:%M:%S%.6f%:z";
-                let _ = write!(writer, "{} - ", Local::now().format(fmt));
-            }
-            Timestamp::Nanosecond => {
-                let fmt = "%Y-%m-%dT%H:%M:%S%.9f%:z";
-                let _ = write!(writer, "{} - ", Local::now().format(fmt));
+        {
+            let ts = self.format_timestamp();
+            if !ts.is_empty() {
+                let _ = write!(writer, "{} - ", ts);
             }
-            Timestamp::Off => {}
         }
         if self.show_level {
             let _ = write!(writer, "{} - ", record.level());
@@ -370,9 +626,11 @@ impl Log for StdErrLog {
     fn flush(&self) {
         let writer = self
             .writer
-            .get_or(|| RefCell::new(StandardStream::stderr(self.color_choice)));
+            .get_or(|| RefCell::new(Sink::open(&self.target, self.color_choice).expect(
+                "stderrlog: failed to open log target",
+            )));
         let mut writer = writer.borrow_mut();
-        writer.flush().ok();
+        writer.lock().flush().ok();
     }
 }
 
@@ -431,10 +689,20 @@ impl StdErrLog {
             show_level: true,
             #[cfg(feature = "timestamps")]
             timestamp: Timestamp::Off,
+            #[cfg(feature = "timestamps")]
+            utc: false,
             modules: Vec::new(),
+            #[cfg(feature = "regex")]
+            regex_set: None,
+            target: Target::Stderr,
             writer: ThreadLocal::new(),
             color_choice: ColorChoice::Auto,
             show_module_names: false,
+            format: None,
+            parsed_format: None,
+            directives: Vec::new(),
+            default_level: None,
+            colors: Vec::new(),
         }
     }
 
@@ -483,12 +751,39 @@ impl StdErrLog {
         self
     }
 
+    /// When true, formats timestamps in UTC (`Utc::now()`) instead of the local timezone.
+    /// Useful when aggregating logs from machines in different timezones.
+    #[cfg(feature = "timestamps")]
+    pub fn utc(&mut self, utc: bool) -> &mut StdErrLog {
+        self.utc = utc;
+        self
+    }
+
     /// Enables or disables the use of color in log messages
     pub fn color(&mut self, choice: ColorChoice) -> &mut StdErrLog {
         self.color_choice = choice;
         self
     }
 
+    /// Overrides the [`ColorSpec`] used for `level`'s log lines (e.g. to add bold/intensity
+    /// or match a house style). Levels without an override keep the default mapping
+    /// (Error=Red, Warn=Magenta, Info=Yellow, Debug=Cyan, Trace=Blue).
+    pub fn color_for(&mut self, level: Level, spec: ColorSpec) -> &mut StdErrLog {
+        match self.colors.iter_mut().find(|(l, _)| *l == level) {
+            Some((_, existing)) => *existing = spec,
+            None => self.colors.push((level, spec)),
+        }
+        self
+    }
+
+    /// Sets where log lines are written (default: [`Target::Stderr`]). A [`Target::File`] is
+    /// opened in append mode on first use and forces colors off unless [`color`](Self::color)
+    /// was explicitly set to something other than [`ColorChoice::Auto`].
+    pub fn target(&mut self, target: Target) -> &mut StdErrLog {
+        self.target = target;
+        self
+    }
+
     /// specify a module to allow to log to stderr
     pub fn module<T: Into<String>>(&mut self, module: T) -> &mut StdErrLog {
         self._module(module.into())
@@ -500,6 +795,24 @@ impl StdErrLog {
         self
     }
 
+    /// Sets a custom line format template, with placeholders substituted at log time:
+    ///
+    /// - `{s}`: the message body
+    /// - `{L}`: the log level
+    /// - `{m}`: the target/module path
+    /// - `{t}`: the formatted timestamp (honors the granularity set via
+    ///   [`timestamp`](Self::timestamp))
+    /// - `{{` / `}}`: a literal brace
+    ///
+    /// If never called, the prior hard-coded `module: timestamp - level - message` layout is
+    /// used. The template is parsed once in [`init`](Self::init); a malformed template (an
+    /// unterminated `{` or an unknown placeholder name) is reported as an error there rather
+    /// than panicking.
+    pub fn format<T: Into<String>>(&mut self, fmt: T) -> &mut StdErrLog {
+        self.format = Some(fmt.into());
+        self
+    }
+
     fn _module(&mut self, module: String) -> &mut StdErrLog {
         // If Ok, the module was already found
         if let Err(i) = self.modules.binary_search(&module) {
@@ -517,6 +830,45 @@ impl StdErrLog {
         self
     }
 
+    /// Parses a comma-separated list of `RUST_LOG`-style `path=level` directives, e.g.
+    /// `my_crate=debug,my_crate::net=trace,hyper=warn`, overriding `verbosity` on a
+    /// per-module basis. A bare directive with no path (`debug`) sets the default level
+    /// used when no path matches. Malformed directives (unparseable levels) are ignored.
+    pub fn parse_filters(&mut self, spec: &str) -> &mut StdErrLog {
+        for directive in spec.split(',').map(str::trim).filter(|d| !d.is_empty()) {
+            let mut parts = directive.splitn(2, '=');
+            let first = parts.next().unwrap();
+            match parts.next() {
+                Some(level) => {
+                    if let Ok(level) = level.parse() {
+                        match self
+                            .directives
+                            .binary_search_by(|(path, _)| path.as_str().cmp(first))
+                        {
+                            Ok(i) => self.directives[i].1 = level,
+                            Err(i) => self.directives.insert(i, (first.to_owned(), level)),
+                        }
+                    }
+                }
+                None => {
+                    if let Ok(level) = first.parse() {
+                        self.default_level = Some(level);
+                    }
+                }
+            }
+        }
+        self
+    }
+
+    /// Reads [`parse_filters`](Self::parse_filters) directives from the named environment
+    /// variable (e.g. `RUST_LOG`), if it's set.
+    pub fn parse_env(&mut self, var: &str) -> &mut StdErrLog {
+        if let Ok(spec) = std::env::var(var) {
+            self.parse_filters(&spec);
+        }
+        self
+    }
+
     /// specify modules to allow to log to stderr
     pub fn modules<T: Into<String>, I: IntoIterator<Item = T>>(
         &mut self,
@@ -528,6 +880,23 @@ impl StdErrLog {
         self
     }
 
+    /// Adds a regex pattern that allows any module path it matches to log, OR'd together
+    /// with the exact/prefix [`module`](Self::module)/[`modules`](Self::modules) list. Lets
+    /// you select sibling modules (`module_regex(r"^my_app::(net|db)")`) or match a leaf
+    /// module name anywhere in the tree, which the prefix matcher alone can't express.
+    /// Requires the `regex` feature.
+    #[cfg(feature = "regex")]
+    pub fn module_regex(&mut self, pattern: &str) -> Result<&mut StdErrLog, regex::Error> {
+        let mut patterns: Vec<String> = self
+            .regex_set
+            .as_ref()
+            .map(|set| set.patterns().to_vec())
+            .unwrap_or_default();
+        patterns.push(pattern.to_owned());
+        self.regex_set = Some(regex::RegexSet::new(&patterns)?);
+        Ok(self)
+    }
+
     fn log_level_filter(&self) -> LevelFilter {
         if self.quiet {
             LevelFilter::Off
@@ -536,7 +905,53 @@ impl StdErrLog {
         }
     }
 
+    /// Resolves the level filter that applies to `module_path`, per the directives set via
+    /// [`parse_filters`](Self::parse_filters): the longest matching directive's level, else
+    /// the default directive's level, else the global `verbosity`.
+    fn effective_level(&self, module_path: &str) -> LevelFilter {
+        if self.quiet {
+            return LevelFilter::Off;
+        }
+        match self
+            .directives
+            .binary_search_by(|(path, _)| path.as_str().cmp(module_path))
+        {
+            Ok(i) => self.directives[i].1,
+            Err(0) => self.default_level.unwrap_or(self.verbosity),
+            Err(i) if is_submodule(&self.directives[i - 1].0, module_path) => {
+                self.directives[i - 1].1
+            }
+            Err(_) => self.default_level.unwrap_or(self.verbosity),
+        }
+    }
+
+    /// The max level across `verbosity`, the default directive, and every per-module
+    /// directive, i.e. the level `log::set_max_level` must allow so the `log` macros don't
+    /// short-circuit records that a more-verbose module still wants.
+    fn max_level_filter(&self) -> LevelFilter {
+        if self.quiet {
+            return LevelFilter::Off;
+        }
+        self.directives
+            .iter()
+            .map(|(_, level)| *level)
+            .chain(self.default_level)
+            .fold(self.verbosity, |acc, level| acc.max(level))
+    }
+
     fn includes_module(&self, module_path: &str) -> bool {
+        #[cfg(feature = "regex")]
+        if let Some(set) = &self.regex_set {
+            if set.is_match(module_path) {
+                return true;
+            }
+            if self.modules.is_empty() {
+                // A regex restriction is configured, so (unlike the plain
+                // prefix-list case below) an empty `modules` no longer means
+                // "unrestricted" — it just adds nothing on top of the regex.
+                return false;
+            }
+        }
         // If modules is empty, include all module paths
         if self.modules.is_empty() {
             return true;
@@ -561,23 +976,61 @@ impl StdErrLog {
     }
 
     /// sets the the logger as active
-    pub fn init(&mut self) -> Result<(), log::SetLoggerError> {
+    pub fn init(&mut self) -> Result<(), InitError> {
         /* if the user is using auto color choices then
-         * detect if stderr is a tty, if it is continue
-         * otherwise turn off colors by default
+         * detect if the chosen target's fd is a tty, if it is continue
+         * otherwise turn off colors by default; a file target never gets a tty
          */
         self.color_choice = match self.color_choice {
-            ColorChoice::Auto => {
-                if atty::is(Stream::Stderr) {
-                    ColorChoice::Auto
-                } else {
-                    ColorChoice::Never
-                }
-            }
+            ColorChoice::Auto => match self.target {
+                Target::Stderr if atty::is(Stream::Stderr) => ColorChoice::Auto,
+                Target::Stdout if atty::is(Stream::Stdout) => ColorChoice::Auto,
+                _ => ColorChoice::Never,
+            },
             other => other,
         };
-        log::set_max_level(self.log_level_filter());
-        log::set_boxed_logger(Box::new(self.clone()))
+        if let Some(fmt) = &self.format {
+            self.parsed_format = Some(parse_format(fmt).map_err(InitError::Format)?);
+        }
+        log::set_max_level(self.max_level_filter());
+        log::set_boxed_logger(Box::new(self.clone()))?;
+        Ok(())
+    }
+
+    /// Formats the current timestamp per the granularity set via [`timestamp`](Self::timestamp)
+    /// and the timezone set via [`utc`](Self::utc), without any trailing separator. Returns
+    /// an empty string if timestamps are off.
+    #[cfg(feature = "timestamps")]
+    fn format_timestamp(&self) -> String {
+        let fmt = match self.timestamp {
+            Timestamp::Second => "%Y-%m-%dT%H:%M:%S%:z",
+            Timestamp::Millisecond => "%Y-%m-%dT%H:%M:%S%.3f%:z",
+            Timestamp::Microsecond => "%Y-%m-%dT%H:%M:%S%.6f%:z",
+            Timestamp::Nanosecond => "%Y-%m-%dT%H:%M:%S%.9f%:z",
+            Timestamp::Off => return String::new(),
+        };
+        if self.utc {
+            Utc::now().format(fmt).to_string()
+        } else {
+            Local::now().format(fmt).to_string()
+        }
+    }
+
+    /// Resolves the [`ColorSpec`] to use for `level`'s log lines: the user's
+    /// [`color_for`](Self::color_for) override if one was set, else the default mapping.
+    fn color_spec_for(&self, level: Level) -> ColorSpec {
+        if let Some((_, spec)) = self.colors.iter().find(|(l, _)| *l == level) {
+            return spec.clone();
+        }
+        let mut spec = ColorSpec::new();
+        spec.set_fg(Some(match level {
+            Level::Error => Color::Red,
+            Level::Warn => Color::Magenta,
+            Level::Info => Color::Yellow,
+            Level::Debug => Color::Cyan,
+            Level::Trace => Color::Blue,
+        }));
+        spec
     }
 }
 
@@ -616,7 +1069,8 @@ fn is_submodule(parent: &str, possible_child: &str) -> bool {
 
 #[cfg(test)]
 mod tests {
-    use super::is_submodule;
+    use super::{is_submodule, parse_format, FormatError, FormatSegment, FormatToken};
+    use log::LevelFilter;
 
     #[test]
     fn submodule() {
@@ -636,4 +1090,82 @@ mod tests {
 
         assert_eq!(log::Level::Error, log::max_level())
     }
+
+    #[test]
+    fn parse_format_happy_path() {
+        assert_eq!(
+            parse_format("{m}: {t} - {L} - {s}").unwrap(),
+            vec![
+                FormatSegment::Token(FormatToken::Module),
+                FormatSegment::Literal(": ".to_owned()),
+                FormatSegment::Token(FormatToken::Timestamp),
+                FormatSegment::Literal(" - ".to_owned()),
+                FormatSegment::Token(FormatToken::Level),
+                FormatSegment::Literal(" - ".to_owned()),
+                FormatSegment::Token(FormatToken::Message),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_format_escaped_braces() {
+        assert_eq!(
+            parse_format("{{{L}}}").unwrap(),
+            vec![
+                FormatSegment::Literal("{".to_owned()),
+                FormatSegment::Token(FormatToken::Level),
+                FormatSegment::Literal("}".to_owned()),
+            ]
+        );
+        assert_eq!(
+            parse_format("no placeholders, just {{ and }}").unwrap(),
+            vec![FormatSegment::Literal("no placeholders, just { and }".to_owned())]
+        );
+    }
+
+    #[test]
+    fn parse_format_unterminated_placeholder() {
+        assert!(matches!(
+            parse_format("{L"),
+            Err(FormatError::UnterminatedPlaceholder)
+        ));
+    }
+
+    #[test]
+    fn parse_format_unknown_placeholder() {
+        match parse_format("{bogus}") {
+            Err(FormatError::UnknownPlaceholder(name)) => assert_eq!(name, "bogus"),
+            other => panic!("expected UnknownPlaceholder, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parse_filters_default_level() {
+        let mut log = super::new();
+        log.parse_filters("debug");
+        assert_eq!(log.effective_level("anything"), LevelFilter::Debug);
+        assert_eq!(log.effective_level("anything::else"), LevelFilter::Debug);
+    }
+
+    #[test]
+    fn parse_filters_per_module_override() {
+        let mut log = super::new();
+        log.verbosity(LevelFilter::Error);
+        log.parse_filters("my_crate=trace,other_crate=warn");
+        assert_eq!(log.effective_level("my_crate"), LevelFilter::Trace);
+        assert_eq!(log.effective_level("other_crate"), LevelFilter::Warn);
+        // Unrelated modules fall back to the global verbosity, since there's no
+        // default directive here.
+        assert_eq!(log.effective_level("unrelated"), LevelFilter::Error);
+    }
+
+    #[test]
+    fn parse_filters_submodule_inheritance() {
+        let mut log = super::new();
+        log.parse_filters("my_crate::net=trace");
+        assert_eq!(log.effective_level("my_crate::net"), LevelFilter::Trace);
+        assert_eq!(log.effective_level("my_crate::net::tcp"), LevelFilter::Trace);
+        // A sibling module isn't a submodule of my_crate::net, so it isn't affected.
+        assert_eq!(log.effective_level("my_crate::db"), log.verbosity);
+    }
 }